@@ -295,6 +295,29 @@ level1::
         })
     });
 
+    // Long blocks nested deeply, where a parser that looks ahead over the
+    // rest of each block would read every line once per level
+    let mut wide_nested = String::new();
+    for depth in 0..20 {
+        let indent = "  ".repeat(depth);
+        for i in 0..50 {
+            wide_nested.push_str(&format!("{indent}key{i}: {i}\n"));
+        }
+        wide_nested.push_str(&format!("{indent}items::\n"));
+        for i in 0..50 {
+            wide_nested.push_str(&format!("{indent}  - {i}\n"));
+        }
+        wide_nested.push_str(&format!("{indent}next::\n"));
+    }
+    wide_nested.push_str(&format!("{}last: true\n", "  ".repeat(20)));
+
+    group.bench_function("parse_wide_nested_blocks", |b| {
+        b.iter(|| {
+            let result = parse_huml(black_box(&wide_nested));
+            black_box(result)
+        })
+    });
+
     // Stress test with many comments
     let commented_doc = r#"# Main document with many comments
 # This is a comment
@@ -0,0 +1,222 @@
+//! Parsing into a caller-provided bump arena.
+//!
+//! [`parse_huml_in`] allocates every string, list and dict of the document from
+//! a [`Bump`], so parsing costs a handful of arena chunk allocations instead of
+//! one heap allocation per node. The returned values borrow from the arena and
+//! are freed all at once when it is reset or dropped.
+//!
+//! ```
+//! use huml_rs::arena::{parse_huml_in, ArenaValue, Bump};
+//!
+//! let arena = Bump::new();
+//! let doc = parse_huml_in("name: \"huml\"\nports:: 80, 443\n", &arena).unwrap();
+//! assert_eq!(doc.root.get("name"), Some(&ArenaValue::String("huml")));
+//! ```
+
+use crate::parser::{expand_indent_tabs, strip_bom, Builder, Parser};
+use crate::{HumlNumber, HumlValue, ParseError, ParseOptions, StringStyle};
+use bumpalo::collections::Vec as BumpVec;
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+pub use bumpalo::Bump;
+
+/// A HUML value whose contents live in a [`Bump`] arena.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArenaValue<'b> {
+    String(&'b str),
+    Number(HumlNumber),
+    Boolean(bool),
+    Null,
+    List(&'b [ArenaValue<'b>]),
+    /// Entries in document order. Keys are unique.
+    Dict(&'b [(&'b str, ArenaValue<'b>)]),
+}
+
+impl<'b> ArenaValue<'b> {
+    /// Looks up `key` if this is a dict. Lookups scan the entries linearly.
+    pub fn get(&self, key: &str) -> Option<&ArenaValue<'b>> {
+        match self {
+            ArenaValue::Dict(entries) => entries.iter().find(|(k, _)| *k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Copies the value out of the arena into an owned [`HumlValue`].
+    pub fn to_value(&self) -> HumlValue {
+        match self {
+            ArenaValue::String(s) => HumlValue::String((*s).into()),
+            ArenaValue::Number(n) => HumlValue::Number(n.clone()),
+            ArenaValue::Boolean(b) => HumlValue::Boolean(*b),
+            ArenaValue::Null => HumlValue::Null,
+            ArenaValue::List(items) => HumlValue::List(items.iter().map(Self::to_value).collect()),
+            ArenaValue::Dict(entries) => HumlValue::Dict(
+                entries
+                    .iter()
+                    .map(|(k, v)| ((*k).to_string(), v.to_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// A parsed HUML document whose contents live in a [`Bump`] arena.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArenaDocument<'b> {
+    pub version: Option<&'b str>,
+    pub directives: &'b [&'b str],
+    pub root: ArenaValue<'b>,
+}
+
+/// Parse a complete HUML document, allocating the result in `arena`.
+pub fn parse_huml_in<'b>(input: &str, arena: &'b Bump) -> Result<ArenaDocument<'b>, ParseError> {
+    parse_huml_in_with_options(input, &ParseOptions::default(), arena)
+}
+
+/// Parse a complete HUML document using the given [`ParseOptions`], allocating
+/// the result in `arena`.
+pub fn parse_huml_in_with_options<'b>(
+    input: &str,
+    options: &ParseOptions,
+    arena: &'b Bump,
+) -> Result<ArenaDocument<'b>, ParseError> {
+    let input = strip_bom(input, options)?;
+    let input = match options.tab_width {
+        Some(width) => expand_indent_tabs(input, width),
+        None => Cow::Borrowed(input),
+    };
+    let mut parser = Parser::with_builder(&input, *options, ArenaBuilder { arena });
+    let doc = parser.parse_document_parts()?;
+
+    let directives =
+        BumpVec::from_iter_in(doc.directives.iter().map(|d| &*arena.alloc_str(d)), arena);
+    Ok(ArenaDocument {
+        version: doc.version.map(|v| &*arena.alloc_str(&v)),
+        directives: directives.into_bump_slice(),
+        root: doc.root,
+    })
+}
+
+struct ArenaBuilder<'b> {
+    arena: &'b Bump,
+}
+
+/// Dicts with more entries than this get a hash index of their keys to find
+/// duplicates; smaller ones are scanned, which saves allocating the index.
+const INDEX_THRESHOLD: usize = 8;
+
+/// A dict being built, with an index of its keys once it is large.
+struct ArenaDict<'b> {
+    entries: BumpVec<'b, (&'b str, ArenaValue<'b>)>,
+    index: Option<HashSet<&'b str>>,
+}
+
+impl<'a, 'b> Builder<'a> for ArenaBuilder<'b> {
+    type Value = ArenaValue<'b>;
+    type List = BumpVec<'b, ArenaValue<'b>>;
+    type Dict = ArenaDict<'b>;
+
+    fn string(&mut self, value: Cow<'a, str>, _style: StringStyle) -> ArenaValue<'b> {
+        ArenaValue::String(self.arena.alloc_str(&value))
+    }
+
+    fn number(&mut self, value: HumlNumber) -> ArenaValue<'b> {
+        ArenaValue::Number(value)
+    }
+
+    fn boolean(&mut self, value: bool) -> ArenaValue<'b> {
+        ArenaValue::Boolean(value)
+    }
+
+    fn null(&mut self) -> ArenaValue<'b> {
+        ArenaValue::Null
+    }
+
+    fn list(&mut self, capacity: usize) -> Self::List {
+        BumpVec::with_capacity_in(capacity, self.arena)
+    }
+
+    fn push(&mut self, list: &mut Self::List, value: ArenaValue<'b>) {
+        list.push(value);
+    }
+
+    fn end_list(&mut self, list: Self::List) -> ArenaValue<'b> {
+        ArenaValue::List(list.into_bump_slice())
+    }
+
+    fn dict(&mut self, capacity: usize) -> Self::Dict {
+        ArenaDict {
+            entries: BumpVec::with_capacity_in(capacity, self.arena),
+            index: None,
+        }
+    }
+
+    fn insert_key(&mut self, dict: &mut Self::Dict, key: Cow<'a, str>) -> Result<(), String> {
+        let duplicate = match &dict.index {
+            Some(index) => index.contains(&*key),
+            None => dict.entries.iter().any(|(k, _)| *k == key),
+        };
+        if duplicate {
+            return Err(key.into_owned());
+        }
+        let key = &*self.arena.alloc_str(&key);
+        dict.entries.push((key, ArenaValue::Null));
+        match &mut dict.index {
+            Some(index) => {
+                index.insert(key);
+            }
+            None if dict.entries.len() > INDEX_THRESHOLD => {
+                dict.index = Some(dict.entries.iter().map(|(k, _)| *k).collect());
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn insert_value(&mut self, dict: &mut Self::Dict, value: ArenaValue<'b>) {
+        if let Some((_, slot)) = dict.entries.last_mut() {
+            *slot = value;
+        }
+    }
+
+    fn end_dict(&mut self, dict: Self::Dict) -> ArenaValue<'b> {
+        ArenaValue::Dict(dict.entries.into_bump_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_huml;
+
+    #[test]
+    fn arena_parse_matches_owned_parse() {
+        let input = "%HUML v0.2.0 strict\nname: \"caf\\u00e9\"\nports:: 80, 443\nlimits::\n  cpu: 2\n  tags:: []\n";
+        let arena = Bump::new();
+        let doc = parse_huml_in(input, &arena).expect("should parse");
+        let (_, owned) = parse_huml(input).unwrap();
+
+        assert_eq!(doc.version, Some("0.2.0"));
+        assert_eq!(doc.directives, &["strict"]);
+        assert_eq!(doc.root.to_value(), owned.root);
+        assert_eq!(doc.root.get("name"), Some(&ArenaValue::String("café")));
+    }
+
+    #[test]
+    fn arena_parse_reports_duplicate_keys() {
+        let arena = Bump::new();
+        let err = parse_huml_in("a: 1\nb:: x: 1, x: 2\n", &arena).unwrap_err();
+        assert!(err.message.contains("duplicate key 'x'"));
+
+        // Past the size where dicts are indexed
+        let mut input: String = (0..20).map(|i| format!("k{i}: {i}\n")).collect();
+        let doc = parse_huml_in(&input, &arena).expect("should parse");
+        assert_eq!(
+            doc.root.get("k19"),
+            Some(&ArenaValue::Number(HumlNumber::Integer(19)))
+        );
+        input.push_str("k3: 0\n");
+        let err = parse_huml_in(&input, &arena).unwrap_err();
+        assert!(err.message.contains("duplicate key 'k3'"));
+    }
+}
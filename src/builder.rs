@@ -0,0 +1,129 @@
+//! Building documents in code.
+
+use crate::{HumlDict, HumlDocument, HumlList, HumlValue};
+
+/// Builds a [`HumlDocument`], or a nested value, by chaining calls.
+///
+/// ```
+/// use huml_rs::{huml, HumlBuilder};
+///
+/// let doc = HumlBuilder::dict()
+///     .version("0.2.0")
+///     .key("port", 8080)
+///     .key("features", HumlBuilder::list().item("auth").item("metrics"))
+///     .key("limits", HumlBuilder::dict().key("cpu", 2))
+///     .build();
+///
+/// assert_eq!(doc.version.as_deref(), Some("0.2.0"));
+/// assert_eq!(
+///     doc.root,
+///     huml!({ port: 8080, features: ["auth", "metrics"], limits: { cpu: 2 } })
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct HumlBuilder {
+    version: Option<String>,
+    directives: Vec<String>,
+    root: HumlValue,
+}
+
+impl HumlBuilder {
+    /// Starts a builder whose root is `root`.
+    pub fn new(root: impl Into<HumlValue>) -> Self {
+        Self {
+            version: None,
+            directives: Vec::new(),
+            root: root.into(),
+        }
+    }
+
+    /// Starts a builder with an empty dict root, to be filled with [`key`](Self::key).
+    pub fn dict() -> Self {
+        Self::new(HumlValue::Dict(HumlDict::new()))
+    }
+
+    /// Starts a builder with an empty list root, to be filled with [`item`](Self::item).
+    pub fn list() -> Self {
+        Self::new(HumlValue::List(HumlList::new()))
+    }
+
+    /// Sets the version written on the `%HUML` line, without the leading `v`.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Adds a directive after the version on the `%HUML` line.
+    pub fn directive(mut self, directive: impl Into<String>) -> Self {
+        self.directives.push(directive.into());
+        self
+    }
+
+    /// Inserts an entry into the root dict, replacing any earlier value for `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the root is not a dict.
+    pub fn key(mut self, key: impl Into<String>, value: impl Into<HumlValue>) -> Self {
+        match &mut self.root {
+            HumlValue::Dict(dict) => {
+                dict.insert(key.into(), value.into());
+            }
+            _ => panic!("HumlBuilder::key called on a builder whose root is not a dict"),
+        }
+        self
+    }
+
+    /// Appends an item to the root list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the root is not a list.
+    pub fn item(mut self, value: impl Into<HumlValue>) -> Self {
+        match &mut self.root {
+            HumlValue::List(items) => items.push(value.into()),
+            _ => panic!("HumlBuilder::item called on a builder whose root is not a list"),
+        }
+        self
+    }
+
+    /// Finishes the document.
+    pub fn build(self) -> HumlDocument {
+        HumlDocument {
+            version: self.version,
+            directives: self.directives,
+            root: self.root,
+        }
+    }
+}
+
+/// Nests a builder as a value; its version and directives are dropped.
+impl From<HumlBuilder> for HumlValue {
+    fn from(builder: HumlBuilder) -> Self {
+        builder.root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_nests_dicts_and_lists() {
+        let doc = HumlBuilder::list()
+            .directive("strict")
+            .item(HumlBuilder::dict().key("a", 1).key("a", 2))
+            .item(HumlBuilder::list())
+            .item(None::<i64>)
+            .build();
+        assert_eq!(doc.version, None);
+        assert_eq!(doc.directives, ["strict"]);
+        assert_eq!(doc.root, huml!([{ a: 2 }, [], null]));
+    }
+
+    #[test]
+    #[should_panic(expected = "root is not a dict")]
+    fn key_requires_a_dict_root() {
+        let _ = HumlBuilder::list().key("a", 1);
+    }
+}
@@ -0,0 +1,349 @@
+//! Writing values as HUML text.
+//!
+//! Dicts are written as blocks in entry order, lists of scalars inline
+//! (`key:: 1, 2, 3`) and other lists as `- ` items, each nesting level indented
+//! by two spaces. Strings that were parsed from multiline blocks are written as
+//! blocks again when the target spec version can represent them exactly. Every
+//! line ends in a newline.
+
+use crate::serde::{EscapeProfile, UnicodeEscape};
+use crate::version::SpecVersion;
+use crate::{HumlDict, HumlString, HumlValue, StringStyle};
+use std::fmt::Write;
+
+const INDENT: &str = "  ";
+
+/// Writes `value` as the body of a document following the rules of `version`.
+pub(crate) fn write_root(out: &mut String, value: &HumlValue, version: SpecVersion) {
+    match value {
+        HumlValue::Dict(dict) if !dict.is_empty() => write_block_dict(out, dict, 0, version),
+        // A single item written inline would read back as a scalar
+        HumlValue::List(items)
+            if items.len() == 1 || !items.is_empty() && !items.iter().all(is_scalar) =>
+        {
+            write_block_list(out, items, 0, version)
+        }
+        _ => {
+            write_inline(out, value);
+            out.push('\n');
+        }
+    }
+}
+
+fn write_block_dict(out: &mut String, dict: &HumlDict, depth: usize, version: SpecVersion) {
+    for (key, value) in dict {
+        push_indent(out, depth);
+        write_key(out, key);
+        match value {
+            HumlValue::Dict(dict) if !dict.is_empty() => {
+                out.push_str("::\n");
+                write_block_dict(out, dict, depth + 1, version);
+            }
+            HumlValue::List(items) if !items.is_empty() && !items.iter().all(is_scalar) => {
+                out.push_str("::\n");
+                write_block_list(out, items, depth + 1, version);
+            }
+            HumlValue::List(_) | HumlValue::Dict(_) => {
+                out.push_str(":: ");
+                write_inline(out, value);
+                out.push('\n');
+            }
+            HumlValue::String(s) if let Some(delimiter) = block_delimiter(s, version) => {
+                out.push_str(": ");
+                write_block_string(out, s, delimiter, depth);
+            }
+            _ => {
+                out.push_str(": ");
+                write_scalar(out, value, UnicodeEscape::default());
+                out.push('\n');
+            }
+        }
+    }
+}
+
+fn write_block_list(out: &mut String, items: &[HumlValue], depth: usize, version: SpecVersion) {
+    for item in items {
+        push_indent(out, depth);
+        match item {
+            HumlValue::Dict(dict) if !dict.is_empty() => {
+                out.push_str("- ::\n");
+                write_block_dict(out, dict, depth + 1, version);
+            }
+            HumlValue::List(items) if !items.is_empty() && !items.iter().all(is_scalar) => {
+                out.push_str("- ::\n");
+                write_block_list(out, items, depth + 1, version);
+            }
+            HumlValue::List(items) if !items.is_empty() => {
+                out.push_str("- :: ");
+                write_inline(out, item);
+                out.push('\n');
+            }
+            _ => {
+                out.push_str("- ");
+                write_inline(out, item);
+                out.push('\n');
+            }
+        }
+    }
+}
+
+/// Writes `value` using only inline syntax, or returns `None` if it contains
+/// a collection nested in a non-empty collection or is a list of one item,
+/// which would read back as that item.
+pub(crate) fn inline_string(value: &HumlValue) -> Option<String> {
+    let inline = match value {
+        HumlValue::List(items) => items.len() != 1 && items.iter().all(is_scalar),
+        HumlValue::Dict(dict) => dict.values().all(is_scalar),
+        _ => true,
+    };
+    inline.then(|| {
+        let mut out = String::new();
+        write_inline(&mut out, value);
+        out
+    })
+}
+
+/// Writes a scalar, an empty collection, or a collection of scalars on one line.
+fn write_inline(out: &mut String, value: &HumlValue) {
+    match value {
+        HumlValue::List(items) if items.is_empty() => out.push_str("[]"),
+        HumlValue::Dict(dict) if dict.is_empty() => out.push_str("{}"),
+        HumlValue::List(items) => {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_scalar(out, item, UnicodeEscape::default());
+            }
+        }
+        HumlValue::Dict(dict) => {
+            for (i, (key, value)) in dict.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_key(out, key);
+                out.push_str(": ");
+                write_scalar(out, value, UnicodeEscape::default());
+            }
+        }
+        _ => write_scalar(out, value, UnicodeEscape::default()),
+    }
+}
+
+fn write_scalar(out: &mut String, value: &HumlValue, escape: UnicodeEscape) {
+    match value {
+        HumlValue::String(s) => write_quoted(out, s, escape, EscapeProfile::default(), false),
+        HumlValue::Number(n) => {
+            let _ = write!(out, "{n}");
+        }
+        HumlValue::Boolean(b) => out.push_str(if *b { "true" } else { "false" }),
+        HumlValue::Null => out.push_str("null"),
+        HumlValue::List(_) | HumlValue::Dict(_) => unreachable!("not a scalar"),
+    }
+}
+
+/// The delimiter to write `s` as a multiline block with, if it was parsed from
+/// one and `version` can represent its text exactly.
+fn block_delimiter(s: &HumlString, version: SpecVersion) -> Option<&'static str> {
+    if !fits_block(s) {
+        return None;
+    }
+    match s.style() {
+        StringStyle::Quoted => None,
+        StringStyle::MultilineBacktick if version.allows_backtick_strings() => Some("```"),
+        // Lines of `"""` blocks lose their surrounding whitespace in v0.1.0.
+        _ if version.trims_quoted_multiline_lines() => s
+            .split('\n')
+            .all(|line| line == line.trim())
+            .then_some("\"\"\""),
+        _ => Some("\"\"\""),
+    }
+}
+
+/// Whether `s` can be written as a multiline block and read back unchanged:
+/// no line may look like a closing delimiter, and there must be no control
+/// characters other than newlines.
+pub(crate) fn fits_block(s: &str) -> bool {
+    s.lines().all(|line| {
+        let line = line.trim_start_matches(' ');
+        !line.starts_with("\"\"\"") && !line.starts_with("```")
+    }) && !s.chars().any(|c| c.is_control() && c != '\n')
+}
+
+/// Writes the text of `s` as a block, closing it at the indentation of its key.
+pub(crate) fn write_block_string(out: &mut String, s: &str, delimiter: &str, depth: usize) {
+    out.push_str(delimiter);
+    out.push('\n');
+    for line in s.split('\n') {
+        if !line.is_empty() {
+            push_indent(out, depth + 1);
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    push_indent(out, depth);
+    out.push_str(delimiter);
+    out.push('\n');
+}
+
+fn is_scalar(value: &HumlValue) -> bool {
+    !matches!(value, HumlValue::List(_) | HumlValue::Dict(_))
+}
+
+fn push_indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str(INDENT);
+    }
+}
+
+/// Writes `key` bare if the default parser accepts it that way, else quoted.
+pub(crate) fn write_key(out: &mut String, key: &str) {
+    let mut bytes = key.bytes();
+    let bare = bytes.next().is_some_and(|b| b.is_ascii_alphabetic())
+        && bytes.all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    if bare {
+        out.push_str(key);
+    } else {
+        write_quoted(
+            out,
+            key,
+            UnicodeEscape::default(),
+            EscapeProfile::default(),
+            false,
+        );
+    }
+}
+
+/// Writes `s` as a double-quoted string with HUML escapes. With `ascii_only`,
+/// non-ASCII characters are escaped too; in the fixed form, characters beyond
+/// `\uffff` take a UTF-16 surrogate pair.
+pub(crate) fn write_quoted(
+    out: &mut String,
+    s: &str,
+    escape: UnicodeEscape,
+    profile: EscapeProfile,
+    ascii_only: bool,
+) {
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\x08' => out.push_str("\\b"),
+            '\x0C' => out.push_str("\\f"),
+            '/' if profile == EscapeProfile::Json => out.push_str("\\/"),
+            c if c.is_control() || (ascii_only && !c.is_ascii()) => match escape {
+                UnicodeEscape::Fixed => {
+                    for unit in c.encode_utf16(&mut [0; 2]) {
+                        let _ = write!(out, "\\u{:04x}", unit);
+                    }
+                }
+                UnicodeEscape::Braced => {
+                    let _ = write!(out, "\\u{{{:x}}}", c as u32);
+                }
+            },
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_huml, HumlNumber, HumlValue, Radix, StringStyle};
+
+    fn assert_round_trips(value: &HumlValue) {
+        let text = value.to_huml_string();
+        let (_, doc) = parse_huml(&text).unwrap_or_else(|e| panic!("{e}\n{text}"));
+        assert_eq!(&doc.root, value, "{text}");
+    }
+
+    #[test]
+    fn emitted_text_parses_back_to_the_same_value() {
+        let mask = HumlValue::Number(HumlNumber::Prefixed(255, Radix::Hexadecimal));
+        let value = huml!({
+            name: "quote \" slash / tab \t bell \u{7}",
+            "quoted key": 1,
+            "1st": 2.0,
+            "": null,
+            mask: mask,
+            small: 1e-7,
+            special: [(f64::NAN), (f64::INFINITY), (f64::NEG_INFINITY), (i128::MIN)],
+            empty: { list: [], dict: {} },
+            nested: [[1, 2], [], {}, [[true]], { a: [1], b: { c: "d" } }, "x"],
+        });
+        assert_round_trips(&value);
+
+        for root in [
+            huml!("scalar"),
+            huml!([]),
+            huml!({}),
+            huml!([1, "a"]),
+            huml!([[1], { a: 1 }]),
+            huml!([1]),
+            huml!([[]]),
+        ] {
+            assert_round_trips(&root);
+        }
+        assert_eq!(huml!([1]).to_huml_string(), "- 1\n");
+    }
+
+    #[test]
+    fn inline_strings_parse_back_where_legal() {
+        for value in [
+            huml!([1, "a", null]),
+            huml!({ b: 2, a: "x y" }),
+            huml!([]),
+            huml!(1.5),
+        ] {
+            let text = value.to_inline_string().unwrap();
+            let (_, doc) = parse_huml(&text).unwrap_or_else(|e| panic!("{e}\n{text}"));
+            assert_eq!(doc.root, value);
+            assert!(!text.contains('\n'));
+        }
+        assert_eq!(
+            huml!({ b: 2, a: 1 }).to_inline_string().unwrap(),
+            "b: 2, a: 1"
+        );
+        assert_eq!(huml!([[1]]).to_inline_string(), None);
+        assert_eq!(huml!([1]).to_inline_string(), None);
+        assert_eq!(huml!({ a: {} }).to_inline_string(), None);
+    }
+
+    #[test]
+    fn documents_keep_their_version_line() {
+        let text = "%HUML v0.2.0 strict\nitems::\n  - ::\n    id: 1\n  - :: 2, 3\n";
+        let (_, doc) = parse_huml(text).unwrap();
+        assert_eq!(doc.to_huml_string(), text);
+        assert_eq!(doc.to_string(), text);
+        assert_eq!(huml!(["a", 1]).to_string(), "\"a\", 1");
+    }
+
+    #[test]
+    fn multiline_strings_keep_their_block_style() {
+        let text =
+            "config::\n  script: \"\"\"\n    echo hi\n\n      indented\n  \"\"\"\n  name: \"x\"\n";
+        let (_, doc) = parse_huml(text).unwrap();
+        let script = doc.root["config"]["script"].as_str().unwrap();
+        assert_eq!(script, "echo hi\n\n  indented");
+        assert_eq!(doc.to_huml_string(), text);
+
+        let legacy = "%HUML v0.1.0\nscript: ```\n  a\n    b\n```\n";
+        let (_, doc) = parse_huml(legacy).unwrap();
+        assert_eq!(doc.to_huml_string(), legacy);
+        // Backtick blocks are no longer valid, so the current version uses `"""`.
+        assert_eq!(
+            doc.root.to_huml_string(),
+            "script: \"\"\"\n  a\n    b\n\"\"\"\n"
+        );
+
+        let mut value = doc.root;
+        if let HumlValue::String(s) = &mut value["script"] {
+            s.set_style(StringStyle::Quoted);
+        }
+        assert_eq!(value.to_huml_string(), "script: \"a\\n  b\"\n");
+    }
+}
@@ -0,0 +1,214 @@
+//! Input decoding for HUML documents that arrive as raw bytes.
+//!
+//! HUML documents are UTF-8, but files saved by some editors are UTF-16 with or
+//! without a byte order mark. [`parse_bytes`] detects the encoding, transcodes the
+//! input to UTF-8 and parses it, reporting what it found alongside the document.
+
+use crate::parser::{parse_huml_with_options, ParseError, ParseOptions};
+use crate::HumlDocument;
+use std::borrow::Cow;
+
+/// Number of leading bytes inspected when guessing the encoding of BOM-less input.
+const SNIFF_LEN: usize = 64;
+
+/// Text encoding detected for a byte input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// A document parsed from bytes, together with how those bytes were encoded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedDocument {
+    pub document: HumlDocument,
+    pub encoding: Encoding,
+    /// Whether the input started with a byte order mark.
+    pub had_bom: bool,
+}
+
+/// Detect the encoding of `input` and parse it as a HUML document.
+///
+/// A byte order mark selects the encoding when present. Without one, NUL bytes in
+/// the first 64 bytes (which never occur in UTF-8 HUML) identify UTF-16 and their
+/// position decides the byte order. BOM-less UTF-16 without a NUL byte there, such
+/// as a long run of CJK text, is read as UTF-8 and fails as invalid UTF-8.
+///
+/// # Example
+///
+/// ```rust
+/// use huml_rs::{parse_bytes, Encoding};
+///
+/// let bytes: Vec<u8> = "key: 1\n".encode_utf16().flat_map(u16::to_le_bytes).collect();
+/// let decoded = parse_bytes(&bytes).unwrap();
+/// assert_eq!(decoded.encoding, Encoding::Utf16Le);
+/// ```
+pub fn parse_bytes(input: &[u8]) -> Result<DecodedDocument, ParseError> {
+    let (text, encoding, had_bom) = decode_bytes(input)?;
+    let document = parse_huml_with_options(&text, &ParseOptions::default())?;
+    Ok(DecodedDocument {
+        document,
+        encoding,
+        had_bom,
+    })
+}
+
+/// Detect the encoding of `input` and transcode it to UTF-8 without parsing.
+///
+/// The byte order mark, if any, is removed from the returned text. UTF-8 input
+/// without a BOM is borrowed rather than copied.
+pub fn decode_bytes(input: &[u8]) -> Result<(Cow<'_, str>, Encoding, bool), ParseError> {
+    if let Some(rest) = input.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return decode_utf8(rest).map(|text| (text, Encoding::Utf8, true));
+    }
+    if let Some(rest) = input.strip_prefix(&[0xFF, 0xFE]) {
+        return decode_utf16(rest, Encoding::Utf16Le).map(|text| (text, Encoding::Utf16Le, true));
+    }
+    if let Some(rest) = input.strip_prefix(&[0xFE, 0xFF]) {
+        return decode_utf16(rest, Encoding::Utf16Be).map(|text| (text, Encoding::Utf16Be, true));
+    }
+
+    match sniff_utf16(input) {
+        Some(encoding) => decode_utf16(input, encoding).map(|text| (text, encoding, false)),
+        None => decode_utf8(input)
+            .map(|text| (text, Encoding::Utf8, false))
+            .map_err(|mut err| {
+                err.message = format!(
+                    "{}; UTF-16 without a byte order mark is only detected by NUL bytes \
+                     in its first {SNIFF_LEN} bytes",
+                    err.message
+                );
+                err
+            }),
+    }
+}
+
+/// Guess the byte order of BOM-less UTF-16 from where the NUL bytes fall.
+fn sniff_utf16(input: &[u8]) -> Option<Encoding> {
+    let head = &input[..input.len().min(SNIFF_LEN)];
+    let (mut even, mut odd) = (0, 0);
+    for (i, byte) in head.iter().enumerate() {
+        if *byte == 0 {
+            if i.is_multiple_of(2) {
+                even += 1;
+            } else {
+                odd += 1;
+            }
+        }
+    }
+
+    match (even, odd) {
+        (0, 0) => None,
+        (even, odd) if odd >= even => Some(Encoding::Utf16Le),
+        _ => Some(Encoding::Utf16Be),
+    }
+}
+
+fn decode_utf8(input: &[u8]) -> Result<Cow<'_, str>, ParseError> {
+    std::str::from_utf8(input)
+        .map(Cow::Borrowed)
+        .map_err(|err| {
+            let valid = &input[..err.valid_up_to()];
+            // The prefix is valid UTF-8 by definition, so this cannot fail.
+            let prefix = std::str::from_utf8(valid).unwrap_or_default();
+            position_error(prefix, "invalid UTF-8 byte sequence")
+        })
+}
+
+fn decode_utf16(input: &[u8], encoding: Encoding) -> Result<Cow<'_, str>, ParseError> {
+    if !input.len().is_multiple_of(2) {
+        return Err(ParseError::new(
+            1,
+            1,
+            "truncated UTF-16 input (odd number of bytes)",
+        ));
+    }
+
+    let units = input.chunks_exact(2).map(|pair| match encoding {
+        Encoding::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+        _ => u16::from_le_bytes([pair[0], pair[1]]),
+    });
+
+    let mut out = String::with_capacity(input.len() / 2);
+    for decoded in char::decode_utf16(units) {
+        match decoded {
+            Ok(ch) => out.push(ch),
+            Err(_) => return Err(position_error(&out, "invalid UTF-16 surrogate sequence")),
+        }
+    }
+    Ok(Cow::Owned(out))
+}
+
+/// Build an error pointing just past the successfully decoded `prefix`.
+fn position_error(prefix: &str, message: &str) -> ParseError {
+    let line = prefix.matches('\n').count() + 1;
+    let line_start = prefix.rfind('\n').map_or(0, |idx| idx + 1);
+    let column = prefix[line_start..].chars().count() + 1;
+    ParseError::new(line, column, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HumlValue;
+
+    fn utf16(text: &str, encoding: Encoding, bom: bool) -> Vec<u8> {
+        let mut units: Vec<u16> = Vec::new();
+        if bom {
+            units.push(0xFEFF);
+        }
+        units.extend(text.encode_utf16());
+        units
+            .into_iter()
+            .flat_map(|unit| match encoding {
+                Encoding::Utf16Be => unit.to_be_bytes(),
+                _ => unit.to_le_bytes(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detects_encodings_with_and_without_bom() {
+        let text = "name: \"größe\"\n";
+        for encoding in [Encoding::Utf16Le, Encoding::Utf16Be] {
+            for bom in [true, false] {
+                let decoded = parse_bytes(&utf16(text, encoding, bom)).expect("should parse");
+                assert_eq!(decoded.encoding, encoding);
+                assert_eq!(decoded.had_bom, bom);
+                let HumlValue::Dict(map) = decoded.document.root else {
+                    panic!("expected dict");
+                };
+                assert_eq!(map.get("name"), Some(&HumlValue::String("größe".into())));
+            }
+        }
+
+        let mut utf8 = vec![0xEF, 0xBB, 0xBF];
+        utf8.extend_from_slice(text.as_bytes());
+        let decoded = parse_bytes(&utf8).expect("should parse");
+        assert_eq!((decoded.encoding, decoded.had_bom), (Encoding::Utf8, true));
+    }
+
+    #[test]
+    fn invalid_utf8_reports_position() {
+        let err = parse_bytes(b"a: 1\nb: \"\xFF\"\n").expect_err("invalid UTF-8");
+        assert_eq!((err.line, err.column), (2, 5));
+        assert!(err.message.contains("invalid UTF-8"));
+    }
+
+    #[test]
+    fn bomless_utf16_without_nul_bytes_names_the_limit() {
+        let text = "鍵".repeat(40);
+        let err = parse_bytes(&utf16(&text, Encoding::Utf16Le, false)).expect_err("not detected");
+        assert_eq!(
+            err.message,
+            "invalid UTF-8 byte sequence; UTF-16 without a byte order mark is only \
+             detected by NUL bytes in its first 64 bytes"
+        );
+
+        let mut utf8 = vec![0xEF, 0xBB, 0xBF];
+        utf8.extend_from_slice(b"a: \"\xFF\"\n");
+        let err = parse_bytes(&utf8).expect_err("invalid UTF-8");
+        assert_eq!(err.message, "invalid UTF-8 byte sequence");
+    }
+}
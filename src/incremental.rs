@@ -0,0 +1,293 @@
+//! Re-parsing a document after small edits.
+//!
+//! [`IncrementalDocument`] keeps the source text together with its parse result
+//! and the span of every top-level entry. An edit re-parses only the entries it
+//! touches and patches them into the root dict, falling back to a full parse
+//! whenever the edit reaches outside them (for example, by opening a multiline
+//! string that swallows the rest of the document).
+
+use crate::parser::{Parser, RootEntry};
+use crate::{
+    parse_huml_with_options, HumlDocument, HumlValue, ParseError, ParseOptions, SpecVersion,
+};
+use std::ops::Range;
+
+/// A parsed document that can be updated in place as its text is edited.
+///
+/// ```
+/// use huml_rs::{HumlValue, IncrementalDocument};
+///
+/// let mut doc = IncrementalDocument::parse("name: \"a\"\nport: 80\n").unwrap();
+/// doc.edit(16..18, "8080").unwrap();
+/// assert_eq!(doc.text(), "name: \"a\"\nport: 8080\n");
+/// ```
+#[derive(Debug, Clone)]
+pub struct IncrementalDocument {
+    text: String,
+    options: ParseOptions,
+    document: HumlDocument,
+    /// Top-level entries in source order when the root is a block dict and the
+    /// text currently parses; `None` forces the next edit to parse everything.
+    entries: Option<Vec<Span>>,
+    /// Offset of the first entry; edits before it re-parse the whole document.
+    body_start: usize,
+    version: SpecVersion,
+}
+
+#[derive(Debug, Clone)]
+struct Span {
+    start: usize,
+    key: String,
+}
+
+impl IncrementalDocument {
+    /// Parse `text` with the default [`ParseOptions`].
+    pub fn parse(text: impl Into<String>) -> Result<Self, ParseError> {
+        Self::parse_with_options(text, &ParseOptions::default())
+    }
+
+    /// Parse `text` with the given [`ParseOptions`].
+    pub fn parse_with_options(
+        text: impl Into<String>,
+        options: &ParseOptions,
+    ) -> Result<Self, ParseError> {
+        let mut doc = Self {
+            text: text.into(),
+            options: *options,
+            document: HumlDocument {
+                version: None,
+                directives: Vec::new(),
+                root: HumlValue::Null,
+            },
+            entries: None,
+            body_start: 0,
+            version: SpecVersion::LATEST,
+        };
+        doc.reparse()?;
+        Ok(doc)
+    }
+
+    /// The current source text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The document as of the last edit that left the text valid.
+    pub fn document(&self) -> &HumlDocument {
+        &self.document
+    }
+
+    pub fn into_document(self) -> HumlDocument {
+        self.document
+    }
+
+    /// Replace `range` of the text with `replacement` and bring the document up
+    /// to date.
+    ///
+    /// On error the text is still edited, and [`document`](Self::document) keeps
+    /// its previous value until a later edit makes the text valid again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds or not on `char` boundaries, like
+    /// [`String::replace_range`].
+    pub fn edit(&mut self, range: Range<usize>, replacement: &str) -> Result<(), ParseError> {
+        let entries = self.entries.take();
+        self.text.replace_range(range.clone(), replacement);
+
+        if let Some(mut entries) = entries
+            && range.start >= self.body_start
+            && self.patch_entries(&mut entries, range, replacement.len())
+        {
+            self.entries = Some(entries);
+            return Ok(());
+        }
+
+        self.reparse()
+    }
+
+    /// Re-parses the entries overlapping `range` (in pre-edit offsets). Returns
+    /// false when the change can't be confined to them.
+    fn patch_entries(
+        &mut self,
+        entries: &mut Vec<Span>,
+        range: Range<usize>,
+        inserted: usize,
+    ) -> bool {
+        let HumlValue::Dict(root) = &mut self.document.root else {
+            return false;
+        };
+        let old_len = self.text.len() + range.len() - inserted;
+        let end_of = |entries: &[Span], i: usize| entries.get(i + 1).map_or(old_len, |e| e.start);
+
+        // An edit at the very start of an entry can join it to the one before,
+        // e.g. by indenting its key line.
+        let mut first = entries
+            .partition_point(|e| e.start <= range.start)
+            .saturating_sub(1);
+        if first > 0 && entries[first].start == range.start {
+            first -= 1;
+        }
+        let last = entries
+            .partition_point(|e| e.start <= range.end)
+            .saturating_sub(1)
+            .max(first);
+
+        let region_start = entries[first].start;
+        let region_end = end_of(entries, last) + inserted - range.len();
+        let region = &self.text[region_start..region_end];
+        let Ok(parsed) = Parser::parse_entries(region, self.options, self.version) else {
+            return false;
+        };
+
+        let replaced = &entries[first..=last];
+        let outside = |key: &str| root.contains_key(key) && !replaced.iter().any(|e| e.key == key);
+        if parsed.iter().any(|e| outside(&e.key))
+            || (parsed.is_empty() && replaced.len() == entries.len())
+        {
+            // A duplicate of an untouched key, or a document left empty.
+            return false;
+        }
+
+        let delta = inserted as isize - range.len() as isize;
+        for span in &mut entries[last + 1..] {
+            span.start = span
+                .start
+                .checked_add_signed(delta)
+                .expect("edit offsets in bounds");
+        }
+        // The root dict holds the entries in source order, so the new ones
+        // take the place of those they replace.
+        let mut spans = Vec::with_capacity(parsed.len());
+        let parsed = parsed.into_iter().map(|RootEntry { start, key, value }| {
+            spans.push(Span {
+                start: region_start + start,
+                key: key.clone(),
+            });
+            (key, value)
+        });
+        root.splice(first..last + 1, parsed);
+        entries.splice(first..=last, spans);
+        true
+    }
+
+    /// Parses the whole text, recording the top-level entry spans when its
+    /// root is a block dict. On error the document is left as it was.
+    fn reparse(&mut self) -> Result<(), ParseError> {
+        self.entries = None;
+        let mut parser = Parser::with_options(&self.text, self.options);
+        if let Ok(Some(prelude)) = parser.parse_sectioned_prelude()
+            && let Ok(parsed) = parser.parse_root_entries()
+        {
+            self.version = parser.spec_version();
+            let mut spans = Vec::with_capacity(parsed.len());
+            let root = parsed
+                .into_iter()
+                .map(|RootEntry { start, key, value }| {
+                    spans.push(Span {
+                        start,
+                        key: key.clone(),
+                    });
+                    (key, value)
+                })
+                .collect();
+            self.document = HumlDocument {
+                version: prelude.version,
+                directives: prelude.directives,
+                root: HumlValue::Dict(root),
+            };
+            self.body_start = prelude.root;
+            self.entries = Some(spans);
+            return Ok(());
+        }
+        self.document = parse_huml_with_options(&self.text, &self.options)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_huml;
+
+    fn assert_matches_full_parse(doc: &IncrementalDocument) {
+        let (_, expected) = parse_huml(doc.text()).expect("text should parse");
+        assert_eq!(doc.document(), &expected);
+        // Dict equality ignores order, so compare the keys separately.
+        if let (HumlValue::Dict(patched), HumlValue::Dict(parsed)) =
+            (&doc.document().root, &expected.root)
+        {
+            assert!(
+                patched.keys().eq(parsed.keys()),
+                "{patched:?} != {parsed:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn edits_patch_affected_entries() {
+        let text = "%HUML v0.2.0\n# config\nname: \"app\"\nserver::\n  host: \"localhost\"\n  port: 80\nfeatures:: \"a\", \"b\"\n";
+        let mut doc = IncrementalDocument::parse(text).unwrap();
+
+        let at = doc.text().find("80").unwrap();
+        doc.edit(at..at + 2, "8080").unwrap();
+        assert!(doc.entries.is_some());
+        assert_matches_full_parse(&doc);
+
+        // Insert a new top-level key between two entries.
+        let at = doc.text().find("features").unwrap();
+        doc.edit(at..at, "debug: true\n").unwrap();
+        assert_matches_full_parse(&doc);
+
+        // Rename a key.
+        let at = doc.text().find("name").unwrap();
+        doc.edit(at..at + 4, "title").unwrap();
+        assert_matches_full_parse(&doc);
+
+        // Indent a key line so it joins the previous entry.
+        let at = doc.text().find("debug").unwrap();
+        doc.edit(at..at, "  ").unwrap();
+        assert_matches_full_parse(&doc);
+
+        // Delete an entry entirely.
+        let start = doc.text().find("features").unwrap();
+        doc.edit(start..doc.text().len(), "").unwrap();
+        assert_matches_full_parse(&doc);
+    }
+
+    #[test]
+    fn edits_that_escape_their_entries_reparse_everything() {
+        let mut doc = IncrementalDocument::parse("a: 1\nb: 2\nc: 3\n").unwrap();
+
+        // Opening a multiline string swallows the following entries.
+        doc.edit(3..4, "\"\"\"").unwrap_err();
+        assert_eq!(doc.text(), "a: \"\"\"\nb: 2\nc: 3\n");
+
+        doc.edit(doc.text().len()..doc.text().len(), "\"\"\"\n")
+            .unwrap();
+        assert_matches_full_parse(&doc);
+
+        // Introducing a duplicate key is reported with its real position.
+        let mut doc = IncrementalDocument::parse("a: 1\nb: 2\n").unwrap();
+        let err = doc.edit(5..6, "a").unwrap_err();
+        assert_eq!(err, parse_huml("a: 1\na: 2\n").unwrap_err());
+        doc.edit(5..6, "c").unwrap();
+        assert_matches_full_parse(&doc);
+    }
+
+    #[test]
+    fn edits_keep_entries_in_source_order() {
+        let mut doc = IncrementalDocument::parse("a: 1\nb: 2\nc: 3\n").unwrap();
+        doc.edit(8..9, "20").unwrap();
+        assert!(doc.entries.is_some());
+        let HumlValue::Dict(root) = &doc.document().root else {
+            panic!("expected a dict root");
+        };
+        assert!(root.keys().eq(["a", "b", "c"]));
+        assert_matches_full_parse(&doc);
+
+        let at = doc.text().find("b").unwrap();
+        doc.edit(at..at + 1, "x").unwrap();
+        assert_matches_full_parse(&doc);
+    }
+}
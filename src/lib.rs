@@ -1,39 +1,236 @@
-use std::collections::HashMap;
+use std::fmt;
 
+#[macro_use]
+mod macros;
+
+#[cfg(feature = "arena")]
+pub mod arena;
+mod builder;
+mod emit;
+mod encoding;
+mod incremental;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "rayon")]
+mod parallel;
 mod parser;
+mod path;
 pub mod serde;
 #[cfg(test)]
 pub mod standard_tests;
+pub mod value;
+mod version;
 
+pub use builder::HumlBuilder;
+pub use encoding::{decode_bytes, parse_bytes, DecodedDocument, Encoding};
+pub use incremental::IncrementalDocument;
+#[cfg(all(feature = "mmap", feature = "arena"))]
+pub use mmap::parse_path_in;
+#[cfg(feature = "mmap")]
+pub use mmap::{parse_file, parse_path, FileError};
+#[cfg(feature = "rayon")]
+pub use parallel::{parse_huml_parallel, parse_huml_parallel_with_options};
 pub use parser::{
-    parse_document_root, parse_empty_dict, parse_empty_list, parse_huml, parse_inline_dict,
-    parse_inline_list, parse_scalar, IResult, ParseError, HUML_VERSION,
+    parse_document_root, parse_empty_dict, parse_empty_list, parse_huml, parse_huml_with_options,
+    parse_inline_dict, parse_inline_list, parse_iter, parse_prefix, parse_scalar, IResult,
+    ParseError, ParseIter, ParseOptions, RootItem, HUML_VERSION,
 };
+pub use serde::Spanned;
+pub use value::{diff, DiffEntry, HumlDict, HumlList, HumlString, StringStyle};
+pub use version::{supported_versions, SpecVersion};
 
-#[derive(Debug, Clone, PartialEq)]
+/// A parsed HUML value.
+///
+/// Values are `Eq`, `Ord` and `Hash`, so they can be deduplicated or used as
+/// map keys. Values of different kinds order as
+/// `String < Number < Boolean < Null < List < Dict`, and dicts compare as
+/// their entries sorted by key.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HumlValue {
-    String(String),
+    String(HumlString),
     Number(HumlNumber),
     Boolean(bool),
     Null,
-    List(Vec<HumlValue>),
-    Dict(HashMap<String, HumlValue>),
+    List(HumlList),
+    Dict(HumlDict),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// A HUML number, keeping the representation it was written in.
+///
+/// Equality and ordering are structural: `Integer(1)` and `Float(1.0)` are
+/// different numbers (see [`HumlValue::semantically_equals`] for comparing by
+/// value). Floats compare with [`f64::total_cmp`], except that every NaN is
+/// equal to every other and `-0.0` equals `0.0`.
+#[derive(Debug, Clone)]
 pub enum HumlNumber {
     Integer(i64),
+    /// Integer outside the `i64` range. The parser only produces this when `Integer` can't hold the value.
+    I128(i128),
+    /// Positive integer above `i128::MAX`.
+    U128(u128),
+    /// Integer written with a `0x`, `0o` or `0b` prefix, kept so it can be written
+    /// back in the same base. Prefixed literals outside the `i64` range are stored
+    /// as `I128`/`U128` and lose their base.
+    ///
+    /// The base is only a formatting hint: `0xff` equals, orders and hashes
+    /// like `255`, and serde serializes it as a plain `i64`.
+    Prefixed(i64, Radix),
     Float(f64),
     Nan,
     Infinity(bool), // true = positive, false = negative
 }
 
+/// Base of a prefixed integer literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Radix {
+    /// `0x`
+    Hexadecimal,
+    /// `0o`
+    Octal,
+    /// `0b`
+    Binary,
+}
+
+impl Radix {
+    /// The numeric base, e.g. `16` for hexadecimal.
+    pub fn base(self) -> u32 {
+        match self {
+            Radix::Hexadecimal => 16,
+            Radix::Octal => 8,
+            Radix::Binary => 2,
+        }
+    }
+
+    /// The literal prefix, e.g. `"0x"` for hexadecimal.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Radix::Hexadecimal => "0x",
+            Radix::Octal => "0o",
+            Radix::Binary => "0b",
+        }
+    }
+}
+
+/// Formats the number as a HUML literal, re-emitting prefixed integers in their
+/// original base and always giving floats a fractional part or exponent.
+impl fmt::Display for HumlNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HumlNumber::Integer(i) => write!(f, "{i}"),
+            HumlNumber::I128(i) => write!(f, "{i}"),
+            HumlNumber::U128(u) => write!(f, "{u}"),
+            HumlNumber::Prefixed(i, radix) => {
+                let sign = if *i < 0 { "-" } else { "" };
+                let magnitude = i.unsigned_abs();
+                match radix {
+                    Radix::Hexadecimal => write!(f, "{sign}0x{magnitude:x}"),
+                    Radix::Octal => write!(f, "{sign}0o{magnitude:o}"),
+                    Radix::Binary => write!(f, "{sign}0b{magnitude:b}"),
+                }
+            }
+            HumlNumber::Float(v) => write!(f, "{v:?}"),
+            HumlNumber::Nan => f.write_str("nan"),
+            HumlNumber::Infinity(true) => f.write_str("inf"),
+            HumlNumber::Infinity(false) => f.write_str("-inf"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct HumlDocument {
     pub version: Option<String>,
+    /// Tokens following the version on the `%HUML` line, in order. The
+    /// parser does not interpret them; they are left to the application.
+    pub directives: Vec<String>,
     pub root: HumlValue,
 }
 
+impl HumlDocument {
+    /// Returns true if `name` appears among the version-line directives.
+    pub fn has_directive(&self, name: &str) -> bool {
+        self.directives.iter().any(|d| d == name)
+    }
+
+    /// Writes the document as HUML text, starting with the `%HUML` line if it
+    /// has a version.
+    pub fn to_huml_string(&self) -> String {
+        let mut out = String::new();
+        if let Some(version) = &self.version {
+            out.push_str("%HUML v");
+            out.push_str(version);
+            for directive in &self.directives {
+                out.push(' ');
+                out.push_str(directive);
+            }
+            out.push('\n');
+        }
+        let version = self
+            .version
+            .as_deref()
+            .and_then(|version| version.parse().ok())
+            .unwrap_or(SpecVersion::LATEST);
+        emit::write_root(&mut out, &self.root, version);
+        out
+    }
+}
+
+impl HumlValue {
+    /// Writes the value as the body of a HUML document.
+    ///
+    /// Dict entries are written in their stored order (see [`sort_keys`](Self::sort_keys))
+    /// and keys are quoted only when necessary.
+    /// The text parses back to an equal value.
+    ///
+    /// ```
+    /// use huml_rs::huml;
+    ///
+    /// let value = huml!({ name: "app", ports: [80, 443], tls: { enabled: true } });
+    /// assert_eq!(
+    ///     value.to_huml_string(),
+    ///     "name: \"app\"\nports:: 80, 443\ntls::\n  enabled: true\n"
+    /// );
+    /// ```
+    pub fn to_huml_string(&self) -> String {
+        let mut out = String::new();
+        emit::write_root(&mut out, self, SpecVersion::LATEST);
+        out
+    }
+
+    /// Writes the value on a single line using inline syntax (`a, b, c` or
+    /// `key: v, key2: v2`), for logs and templated fragments.
+    ///
+    /// Returns `None` when the value can't be written inline because a
+    /// non-empty list or dict contains another list or dict, or because it is
+    /// a list of one item, which inline would read back as the item itself.
+    ///
+    /// ```
+    /// use huml_rs::huml;
+    ///
+    /// assert_eq!(huml!({ port: 80, host: "a" }).to_inline_string().unwrap(), "port: 80, host: \"a\"");
+    /// assert_eq!(huml!([1, 2, 3]).to_inline_string().unwrap(), "1, 2, 3");
+    /// assert_eq!(huml!({ nested: { a: 1 } }).to_inline_string(), None);
+    /// assert_eq!(huml!([1]).to_inline_string(), None);
+    /// ```
+    pub fn to_inline_string(&self) -> Option<String> {
+        emit::inline_string(self)
+    }
+}
+
+/// Formats the value as HUML, like [`HumlValue::to_huml_string`] without the
+/// final newline.
+impl fmt::Display for HumlValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_huml_string().trim_end_matches('\n'))
+    }
+}
+
+/// Formats the document as HUML, like [`HumlDocument::to_huml_string`].
+impl fmt::Display for HumlDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_huml_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,6 +377,311 @@ second
         }
     }
 
+    #[test]
+    fn leading_bom_is_skipped() {
+        let (_, doc) = parse_huml("\u{FEFF}key: \"value\"\n").expect("should parse");
+        if let HumlValue::Dict(map) = doc.root {
+            assert_eq!(map.get("key"), Some(&HumlValue::String("value".into())));
+        } else {
+            panic!("expected dict");
+        }
+    }
+
+    #[test]
+    fn error_positions_after_multiline_content() {
+        let input = "text: \"\"\"\n  one\n  two\n\"\"\"\n# note\nkey: 1 ,\n";
+        let err = parse_huml(input).expect_err("stray comma should fail");
+        assert_eq!((err.line, err.column), (6, 8));
+    }
+
+    #[test]
+    fn quoted_keys_with_escaped_quotes_start_a_dict() {
+        let (_, doc) = parse_huml("\"a\\\": b\": 1\nplain: 2\n").expect("should parse");
+        let HumlValue::Dict(map) = doc.root else {
+            panic!("expected dict");
+        };
+        assert_eq!(
+            map.get("a\": b"),
+            Some(&HumlValue::Number(HumlNumber::Integer(1)))
+        );
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn parse_iter_yields_root_entries_lazily() {
+        let input = "%HUML v0.2.0\nfirst: 1\nsecond::\n  - true\nbroken line\n";
+        let mut iter = parse_iter(input);
+        assert_eq!(
+            iter.next(),
+            Some(Ok(RootItem::Entry(
+                "first".into(),
+                HumlValue::Number(HumlNumber::Integer(1))
+            )))
+        );
+        assert!(matches!(iter.next(), Some(Ok(RootItem::Entry(key, _))) if key == "second"));
+        assert!(matches!(iter.next(), Some(Err(_))));
+        assert_eq!(iter.next(), None);
+
+        let items: Vec<_> = parse_iter("- 1\n- \"two\"\n")
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            items,
+            vec![
+                RootItem::Item(HumlValue::Number(HumlNumber::Integer(1))),
+                RootItem::Item(HumlValue::String("two".into())),
+            ]
+        );
+        assert_eq!(parse_iter("[]\n").count(), 0);
+        assert_eq!(parse_iter("a: 1, b: 2\n").count(), 2);
+        assert!(parse_iter("a: 1\na: 2\n").any(|item| item.is_err()));
+    }
+
+    #[test]
+    fn parse_prefix_returns_trailing_text() {
+        let (rest, doc) = parse_prefix("a: 1\nb::\n  - 2\n\n# end\n```\nmore\n").unwrap();
+        assert_eq!(rest, "```\nmore\n");
+        let HumlValue::Dict(map) = doc.root else {
+            panic!("expected dict");
+        };
+        assert_eq!(map.len(), 2);
+
+        let (rest, doc) = parse_prefix("- 1\n- 2\n---\n").unwrap();
+        assert_eq!(rest, "---\n");
+        assert!(matches!(doc.root, HumlValue::List(ref items) if items.len() == 2));
+
+        let (rest, _) = parse_prefix("\"scalar\"\nnot huml: at all\n").unwrap();
+        assert_eq!(rest, "not huml: at all\n");
+
+        assert!(parse_prefix("a: 1\n  b: 2\n").is_err());
+        assert!(parse_prefix("a: 1\na: 2\n").is_err());
+    }
+
+    #[test]
+    fn leading_bom_rejected_in_strict_mode() {
+        let err = parse_huml_with_options("\u{FEFF}key: 1\n", &ParseOptions::strict())
+            .expect_err("BOM should be rejected");
+        assert_eq!((err.line, err.column), (1, 1));
+        assert!(err.message.contains("byte order mark"));
+    }
+
+    #[test]
+    fn tab_indentation_reports_targeted_error() {
+        let err = parse_huml("server::\n\thost: \"localhost\"\n").expect_err("tabs should fail");
+        assert_eq!(err.line, 2);
+        assert!(err.message.contains("tabs are not allowed for indentation"));
+
+        let err = parse_huml("key:\t1\n").expect_err("tab separator should fail");
+        assert!(err.message.contains("tabs are not allowed after ':'"));
+    }
+
+    #[test]
+    fn tab_indentation_expanded_in_lenient_mode() {
+        let input = "server::\n\thost: \"localhost\"\n\tports::\n\t\t- 80\n\t  - 443\n";
+        let options = ParseOptions::default().with_tab_width(2);
+        let doc = parse_huml_with_options(input, &options).expect("should parse");
+        let HumlValue::Dict(map) = doc.root else {
+            panic!("expected dict");
+        };
+        let Some(HumlValue::Dict(server)) = map.get("server") else {
+            panic!("expected server dict");
+        };
+        assert_eq!(
+            server.get("ports"),
+            Some(&HumlValue::List(HumlList::from(vec![
+                HumlValue::Number(HumlNumber::Integer(80)),
+                HumlValue::Number(HumlNumber::Integer(443)),
+            ])))
+        );
+    }
+
+    #[test]
+    fn tab_expansion_leaves_multiline_string_bodies_alone() {
+        let input = "server::\n\tbanner: \"\"\"\n\t\t\tindented\tbody\n\t\"\"\"\n\tport: 80\n";
+        let options = ParseOptions::default().with_tab_width(2);
+        let doc = parse_huml_with_options(input, &options).expect("should parse");
+        let HumlValue::Dict(map) = doc.root else {
+            panic!("expected dict");
+        };
+        let Some(HumlValue::Dict(server)) = map.get("server") else {
+            panic!("expected server dict");
+        };
+        assert_eq!(
+            server.get("banner"),
+            Some(&HumlValue::String("\t\t\tindented\tbody".into()))
+        );
+        assert_eq!(
+            server.get("port"),
+            Some(&HumlValue::Number(HumlNumber::Integer(80)))
+        );
+    }
+
+    #[test]
+    fn unicode_bare_keys_require_option() {
+        let input = "größe: 42\n名前:: vorname: \"Anna\", nachname: \"Schmidt\"\n";
+        assert!(parse_huml(input).is_err());
+
+        let options = ParseOptions::default().with_unicode_keys();
+        let doc = parse_huml_with_options(input, &options).expect("should parse");
+        let HumlValue::Dict(map) = doc.root else {
+            panic!("expected dict");
+        };
+        assert_eq!(
+            map.get("größe"),
+            Some(&HumlValue::Number(HumlNumber::Integer(42)))
+        );
+        let Some(HumlValue::Dict(name)) = map.get("名前") else {
+            panic!("expected inline dict");
+        };
+        assert_eq!(name.get("vorname"), Some(&HumlValue::String("Anna".into())));
+    }
+
+    #[test]
+    fn trailing_commas_require_option() {
+        let input = "features:: \"a\", \"b\",\nlimits:: cpu: 2, mem: 4, # tuned\n";
+        assert!(parse_huml(input).is_err());
+        assert!(parse_huml_with_options(input, &ParseOptions::strict()).is_err());
+
+        let options = ParseOptions::default().with_trailing_commas();
+        let doc = parse_huml_with_options(input, &options).expect("should parse");
+        let HumlValue::Dict(map) = doc.root else {
+            panic!("expected dict");
+        };
+        assert_eq!(
+            map.get("features"),
+            Some(&HumlValue::List(HumlList::from(vec![
+                HumlValue::String("a".into()),
+                HumlValue::String("b".into()),
+            ])))
+        );
+        let Some(HumlValue::Dict(limits)) = map.get("limits") else {
+            panic!("expected inline dict");
+        };
+        assert_eq!(limits.len(), 2);
+
+        // Only a single comma at the very end of the line is tolerated.
+        assert!(parse_huml_with_options("x:: 1, 2,,\n", &options).is_err());
+        assert!(parse_huml_with_options("x:: 1, 2, \n", &options).is_err());
+    }
+
+    #[test]
+    fn braced_unicode_escapes() {
+        let (_, value) = parse_scalar(r#""smile \u{1F600}, \u{e9}, \u00e9""#).unwrap();
+        assert_eq!(value, HumlValue::String("smile \u{1F600}, é, é".into()));
+
+        assert!(parse_scalar(r#""\u{}""#).is_err());
+        assert!(parse_scalar(r#""\u{1234567}""#).is_err());
+        assert!(parse_scalar(r#""\u{D800}""#).is_err());
+        assert!(parse_scalar(r#""\u{41""#).is_err());
+    }
+
+    #[test]
+    fn strings_mix_plain_runs_and_escapes() {
+        let (rest, value) = parse_scalar(r#""größe \"x\"\ttab\\ end" tail"#).unwrap();
+        assert_eq!(value, HumlValue::String("größe \"x\"\ttab\\ end".into()));
+        assert_eq!(rest, " tail");
+
+        let err = parse_scalar("\"abc").unwrap_err();
+        assert_eq!((err.column, err.message.as_str()), (5, "unclosed string"));
+        let err = parse_scalar("\"ab\ncd\"").unwrap_err();
+        assert!(err.message.contains("newlines not allowed"));
+        assert!(parse_scalar(r#""bad \q""#).is_err());
+    }
+
+    #[test]
+    fn integers_widen_beyond_i64() {
+        let parse = |input| parse_scalar(input).unwrap().1;
+        assert_eq!(
+            parse("9223372036854775807"),
+            HumlValue::Number(HumlNumber::Integer(i64::MAX))
+        );
+        assert_eq!(
+            parse("9223372036854775808"),
+            HumlValue::Number(HumlNumber::I128(i64::MAX as i128 + 1))
+        );
+        assert_eq!(
+            parse("-170141183460469231731687303715884105728"),
+            HumlValue::Number(HumlNumber::I128(i128::MIN))
+        );
+        assert_eq!(
+            parse("0xffffffffffffffffffffffffffffffff"),
+            HumlValue::Number(HumlNumber::U128(u128::MAX))
+        );
+        assert!(parse_scalar("-170141183460469231731687303715884105729").is_err());
+        assert!(parse_scalar("340282366920938463463374607431768211456").is_err());
+    }
+
+    #[test]
+    fn prefixed_integers_keep_their_radix() {
+        let input = "mask: 0xDEADBEEF\nmode: 0o755\nflags: -0b1010\nplain: 42\nratio: 1.0\n";
+        let (_, doc) = parse_huml(input).expect("should parse");
+        let HumlValue::Dict(map) = doc.root else {
+            panic!("expected dict");
+        };
+        let number = |key: &str| match map.get(key) {
+            Some(HumlValue::Number(n)) => n.clone(),
+            other => panic!("expected number for {key}, got {other:?}"),
+        };
+
+        assert_eq!(
+            number("mask"),
+            HumlNumber::Prefixed(0xDEADBEEF, Radix::Hexadecimal)
+        );
+        assert_eq!(number("mask").to_string(), "0xdeadbeef");
+        assert_eq!(number("mode").to_string(), "0o755");
+        assert_eq!(number("flags"), HumlNumber::Prefixed(-10, Radix::Binary));
+        assert_eq!(number("flags").to_string(), "-0b1010");
+        assert_eq!(number("plain").to_string(), "42");
+        assert_eq!(number("ratio").to_string(), "1.0");
+    }
+
+    #[test]
+    fn prefixed_integers_equal_their_value() {
+        let hex = HumlNumber::Prefixed(255, Radix::Hexadecimal);
+        assert_eq!(hex, HumlNumber::Integer(255));
+        assert_eq!(hex, HumlNumber::Prefixed(255, Radix::Binary));
+        assert_ne!(hex, HumlNumber::Integer(256));
+    }
+
+    #[test]
+    fn version_line_selects_spec_rules() {
+        let input = "%HUML v0.1.0\nraw: ```\n  keep\n    indent\n```\ntrimmed: \"\"\"\n    left\n  right  \n\"\"\"\n";
+        let (_, doc) = parse_huml(input).expect("v0.1.0 should accept backticks");
+        assert_eq!(doc.version.as_deref(), Some("0.1.0"));
+        let HumlValue::Dict(map) = doc.root else {
+            panic!("expected dict");
+        };
+        assert_eq!(
+            map.get("raw"),
+            Some(&HumlValue::String("keep\n  indent".into()))
+        );
+        assert_eq!(
+            map.get("trimmed"),
+            Some(&HumlValue::String("left\nright".into()))
+        );
+
+        let v2 = input.replace("v0.1.0", "v0.2.0");
+        assert!(parse_huml(&v2).is_err());
+        assert!(parse_huml(input.trim_start_matches("%HUML v0.1.0\n")).is_err());
+
+        let err = parse_huml("%HUML v0.3.0\nkey: 1\n").unwrap_err();
+        assert!(err.message.contains("expected one of v0.1.0, v0.2.0"));
+    }
+
+    #[test]
+    fn version_line_directives_are_exposed() {
+        let (_, doc) = parse_huml("%HUML v0.2.0 strict profile=prod # flags\nkey: 1\n").unwrap();
+        assert_eq!(doc.version.as_deref(), Some("0.2.0"));
+        assert_eq!(doc.directives, vec!["strict", "profile=prod"]);
+        assert!(doc.has_directive("strict"));
+
+        let (_, doc) = parse_huml("%HUML v0.2.0\nkey: 1\n").unwrap();
+        assert!(doc.directives.is_empty());
+
+        assert!(parse_huml("%HUML v0.2.0 strict \nkey: 1\n").is_err());
+        assert!(parse_huml("%HUML v0.2.0  strict\nkey: 1\n").is_err());
+    }
+
     #[test]
     fn duplicate_key_error_before_malformed_value() {
         // This test ensures duplicate key errors are reported before parsing malformed values
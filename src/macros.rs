@@ -0,0 +1,147 @@
+/// Builds a [`HumlValue`](crate::HumlValue) from a literal, in the spirit of
+/// `serde_json::json!`.
+///
+/// Dicts are written `{ key: value, ... }` and lists `[value, ...]`. Keys are
+/// bare identifiers, string literals, or a parenthesized expression that
+/// converts into a `String`. Values are `null`, nested dicts and lists, or any
+/// expression with a `From` conversion into `HumlValue`.
+///
+/// ```
+/// use huml_rs::{huml, parse_huml};
+///
+/// let port = 8080;
+/// let value = huml!({
+///     name: "app",
+///     "max-connections": 100,
+///     server: { port: port, tls: false },
+///     tags: ["a", "b"],
+///     parent: null,
+/// });
+///
+/// let (_, doc) = parse_huml(
+///     "name: \"app\"\n\"max-connections\": 100\nserver:: port: 8080, tls: false\ntags:: \"a\", \"b\"\nparent: null\n",
+/// )
+/// .unwrap();
+/// assert_eq!(value, doc.root);
+/// ```
+#[macro_export]
+macro_rules! huml {
+    // Lists: accumulate converted elements, then build the vector.
+    (@list [$($elems:expr,)*]) => {
+        ::std::vec![$($elems,)*]
+    };
+    (@list [$($elems:expr,)*] null $(, $($rest:tt)*)?) => {
+        $crate::huml!(@list [$($elems,)* $crate::HumlValue::Null,] $($($rest)*)?)
+    };
+    (@list [$($elems:expr,)*] [$($list:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::huml!(@list [$($elems,)* $crate::huml!([$($list)*]),] $($($rest)*)?)
+    };
+    (@list [$($elems:expr,)*] {$($dict:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::huml!(@list [$($elems,)* $crate::huml!({$($dict)*}),] $($($rest)*)?)
+    };
+    (@list [$($elems:expr,)*] $next:expr $(, $($rest:tt)*)?) => {
+        $crate::huml!(@list [$($elems,)* $crate::huml!($next),] $($($rest)*)?)
+    };
+
+    // Dicts: insert one `key: value` entry at a time.
+    (@dict $dict:ident) => {};
+    (@dict $dict:ident $key:tt : null $(, $($rest:tt)*)?) => {
+        $crate::huml!(@insert $dict $key $crate::HumlValue::Null);
+        $crate::huml!(@dict $dict $($($rest)*)?);
+    };
+    (@dict $dict:ident $key:tt : [$($list:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::huml!(@insert $dict $key $crate::huml!([$($list)*]));
+        $crate::huml!(@dict $dict $($($rest)*)?);
+    };
+    (@dict $dict:ident $key:tt : {$($inner:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::huml!(@insert $dict $key $crate::huml!({$($inner)*}));
+        $crate::huml!(@dict $dict $($($rest)*)?);
+    };
+    (@dict $dict:ident $key:tt : $value:expr $(, $($rest:tt)*)?) => {
+        $crate::huml!(@insert $dict $key $crate::huml!($value));
+        $crate::huml!(@dict $dict $($($rest)*)?);
+    };
+    (@insert $dict:ident $key:ident $value:expr) => {
+        $dict.insert(::std::string::String::from(stringify!($key)), $value);
+    };
+    (@insert $dict:ident $key:literal $value:expr) => {
+        $dict.insert(::std::string::String::from($key), $value);
+    };
+    (@insert $dict:ident ($key:expr) $value:expr) => {
+        $dict.insert(::std::string::String::from($key), $value);
+    };
+
+    (null) => {
+        $crate::HumlValue::Null
+    };
+    ([$($list:tt)*]) => {
+        $crate::HumlValue::List($crate::HumlList::from($crate::huml!(@list [] $($list)*)))
+    };
+    ({$($dict:tt)*}) => {
+        $crate::HumlValue::Dict({
+            #[allow(unused_mut)]
+            let mut dict = $crate::HumlDict::new();
+            $crate::huml!(@dict dict $($dict)*);
+            dict
+        })
+    };
+    ($value:expr) => {
+        $crate::HumlValue::from($value)
+    };
+}
+
+/// Asserts that two [`HumlValue`](crate::HumlValue)s are
+/// [semantically equal](crate::HumlValue::semantically_equals).
+///
+/// Takes an optional format string and arguments, like `assert_eq!`.
+///
+/// ```
+/// use huml_rs::{assert_huml_eq, huml};
+///
+/// let (_, doc) = huml_rs::parse_huml("port: 0x50\n").unwrap();
+/// assert_huml_eq!(doc.root, huml!({ port: 80 }));
+/// ```
+#[macro_export]
+macro_rules! assert_huml_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_huml_eq!($left, $right, "")
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !$crate::HumlValue::semantically_equals(left, right) {
+                    ::std::panic!(
+                        "values are not semantically equal: {}\n  left: {:?}\n right: {:?}",
+                        ::std::format_args!($($arg)+),
+                        left,
+                        right,
+                    );
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_huml, HumlList, HumlNumber, HumlValue};
+
+    #[test]
+    fn huml_macro_builds_nested_values() {
+        let name = String::from("key");
+        let value = huml!({
+            (name.clone()): [1, -2.5, [], {}, [null, true]],
+            nested: { deep: { list: ["x",] } },
+            big: u64::MAX,
+        });
+        let (_, doc) = parse_huml(
+            "key::\n  - 1\n  - -2.5\n  - []\n  - {}\n  - :: null, true\nnested::\n  deep::\n    list:: \"x\"\nbig: 18446744073709551615\n",
+        )
+        .unwrap();
+        assert_eq!(value, doc.root);
+
+        assert_eq!(huml!(null), HumlValue::Null);
+        assert_eq!(huml!(3 * 4), HumlValue::Number(HumlNumber::Integer(12)));
+        assert_eq!(huml!([]), HumlValue::List(HumlList::new()));
+    }
+}
@@ -0,0 +1,149 @@
+//! Parsing documents straight from memory-mapped files.
+//!
+//! The file is mapped rather than read into a `String`, and UTF-8 content is
+//! parsed in place, so only the resulting values are allocated. With the
+//! `arena` feature, [`parse_path_in`] allocates those in an arena as well.
+//!
+//! The functions are `unsafe`: a mapped file that another process truncates
+//! or rewrites while it is parsed is undefined behaviour. Read the file into
+//! memory and use [`parse_bytes`] when that can't be ruled out.
+
+use crate::{parse_bytes, HumlDocument, ParseError};
+use memmap2::Mmap;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// Error returned when a file cannot be read or parsed.
+#[derive(Debug)]
+pub enum FileError {
+    /// The file could not be opened or mapped.
+    Io(io::Error),
+    /// The file contents are not a valid HUML document.
+    Parse(ParseError),
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileError::Io(e) => write!(f, "IO error: {e}"),
+            FileError::Parse(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FileError::Io(e) => Some(e),
+            FileError::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for FileError {
+    fn from(err: io::Error) -> Self {
+        FileError::Io(err)
+    }
+}
+
+impl From<ParseError> for FileError {
+    fn from(err: ParseError) -> Self {
+        FileError::Parse(err)
+    }
+}
+
+/// Memory-map the file at `path` and parse it as a HUML document.
+///
+/// The encoding is detected as in [`parse_bytes`].
+///
+/// # Safety
+///
+/// The file must not be truncated or modified, by this or any other process,
+/// until the function returns.
+pub unsafe fn parse_path(path: impl AsRef<Path>) -> Result<HumlDocument, FileError> {
+    // SAFETY: passed on to the caller.
+    unsafe { parse_file(&File::open(path)?) }
+}
+
+/// Memory-map an open file and parse it as a HUML document.
+///
+/// # Safety
+///
+/// The file must not be truncated or modified, by this or any other process,
+/// until the function returns.
+pub unsafe fn parse_file(file: &File) -> Result<HumlDocument, FileError> {
+    // SAFETY: passed on to the caller.
+    let map = unsafe { map_file(file)? };
+    Ok(parse_bytes(&map)?.document)
+}
+
+/// Memory-map the file at `path` and parse it into `arena`.
+///
+/// # Safety
+///
+/// The file must not be truncated or modified, by this or any other process,
+/// until the function returns.
+#[cfg(feature = "arena")]
+pub unsafe fn parse_path_in<'b>(
+    path: impl AsRef<Path>,
+    arena: &'b crate::arena::Bump,
+) -> Result<crate::arena::ArenaDocument<'b>, FileError> {
+    // SAFETY: passed on to the caller.
+    let map = unsafe { map_file(&File::open(path)?)? };
+    let (text, _, _) = crate::decode_bytes(&map)?;
+    Ok(crate::arena::parse_huml_in(&text, arena)?)
+}
+
+/// # Safety
+///
+/// The file must not change while the map is alive. Callers only read the
+/// map while parsing and drop it before returning.
+unsafe fn map_file(file: &File) -> io::Result<Mmap> {
+    // SAFETY: passed on to the caller.
+    unsafe { Mmap::map(file) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HumlValue;
+    use std::io::Write;
+
+    fn temp_file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("huml-rs-{}-{name}", std::process::id()));
+        File::create(&path).unwrap().write_all(contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn parse_path_maps_and_parses() {
+        let path = temp_file("ok.huml", b"name: \"huml\"\nports:: 80, 443\n");
+        // SAFETY: nothing else touches the temporary file.
+        let doc = unsafe { parse_path(&path) }.expect("should parse");
+        std::fs::remove_file(&path).unwrap();
+        let HumlValue::Dict(map) = doc.root else {
+            panic!("expected dict");
+        };
+        assert_eq!(map.get("name"), Some(&HumlValue::String("huml".into())));
+    }
+
+    #[test]
+    fn parse_path_reports_io_and_parse_errors() {
+        let missing = std::env::temp_dir().join("huml-rs-definitely-missing.huml");
+        // SAFETY: nothing else touches the temporary files.
+        assert!(matches!(
+            unsafe { parse_path(&missing) },
+            Err(FileError::Io(_))
+        ));
+
+        let path = temp_file("bad.huml", b"key: 1\nkey: 2\n");
+        let err = unsafe { parse_path(&path) }.unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        let FileError::Parse(err) = err else {
+            panic!("expected parse error");
+        };
+        assert_eq!(err.line, 2);
+    }
+}
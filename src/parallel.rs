@@ -0,0 +1,151 @@
+//! Parsing large documents on a thread pool.
+//!
+//! A document whose root is a block dict is cut at top-level keys into sections
+//! of roughly equal size, which are parsed concurrently with rayon and merged.
+//! When a cut turns out not to be a real section boundary (for example, inside
+//! a multiline string), or any section fails to parse, the whole document is
+//! parsed again on the calling thread so results and error positions are
+//! exactly those of [`parse_huml_with_options`].
+
+use crate::parser::{expand_indent_tabs, strip_bom, Parser};
+use crate::{parse_huml_with_options, HumlDict, HumlDocument, HumlValue, ParseError, ParseOptions};
+use memchr::memchr;
+use rayon::prelude::*;
+use std::borrow::Cow;
+
+/// Sections are never cut smaller than this, and smaller documents are parsed
+/// on the calling thread.
+const MIN_SECTION_LEN: usize = 64 * 1024;
+
+/// Parse a complete HUML document, splitting large block-dict roots into
+/// sections that are parsed in parallel.
+pub fn parse_huml_parallel(input: &str) -> Result<HumlDocument, ParseError> {
+    parse_huml_parallel_with_options(input, &ParseOptions::default())
+}
+
+/// Like [`parse_huml_parallel`], using the given [`ParseOptions`].
+pub fn parse_huml_parallel_with_options(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<HumlDocument, ParseError> {
+    if input.len() < 2 * MIN_SECTION_LEN {
+        return parse_huml_with_options(input, options);
+    }
+
+    let text = strip_bom(input, options)?;
+    let text = match options.tab_width {
+        Some(width) => expand_indent_tabs(text, width),
+        None => Cow::Borrowed(text),
+    };
+    let mut prelude_parser = Parser::with_options(&text, *options);
+    let Some(prelude) = prelude_parser.parse_sectioned_prelude()? else {
+        return parse_huml_with_options(input, options);
+    };
+    let version = prelude_parser.spec_version();
+
+    let body = &text[prelude.root..];
+    let target = MIN_SECTION_LEN.max(body.len() / (rayon::current_num_threads() * 4));
+    let sections = split_sections(body, target, options.unicode_keys);
+    if sections.len() < 2 {
+        return parse_huml_with_options(input, options);
+    }
+
+    let parsed: Result<Vec<_>, _> = sections
+        .par_iter()
+        .map(|section| Parser::parse_section(section, *options, version))
+        .collect();
+    // Section errors have positions relative to the section, so let the serial
+    // parser report them.
+    let Ok(parts) = parsed else {
+        return parse_huml_with_options(input, options);
+    };
+
+    let mut root = HumlDict::with_capacity(parts.iter().map(HumlDict::len).sum());
+    for part in parts {
+        for (key, value) in part {
+            if root.insert(key, value).is_some() {
+                // Duplicate key across sections.
+                return parse_huml_with_options(input, options);
+            }
+        }
+    }
+
+    Ok(HumlDocument {
+        version: prelude.version,
+        directives: prelude.directives,
+        root: HumlValue::Dict(root),
+    })
+}
+
+/// Cuts `body` before unindented key lines, once each piece is at least `target` bytes.
+fn split_sections(body: &str, target: usize, unicode_keys: bool) -> Vec<&str> {
+    let bytes = body.as_bytes();
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut pos = 0;
+    while let Some(offset) = memchr(b'\n', &bytes[pos..]) {
+        pos += offset + 1;
+        if pos - start >= target && starts_key_line(&body[pos..], unicode_keys) {
+            sections.push(&body[start..pos]);
+            start = pos;
+        }
+    }
+    sections.push(&body[start..]);
+    sections
+}
+
+fn starts_key_line(line: &str, unicode_keys: bool) -> bool {
+    match line.chars().next() {
+        Some('"') => !line.starts_with("\"\"\""),
+        Some(c) if unicode_keys => c.is_alphabetic(),
+        Some(c) => c.is_ascii_alphabetic(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn large_document(sections: usize) -> String {
+        let mut doc = String::from("%HUML v0.2.0\n# generated\n");
+        for i in 0..sections {
+            doc.push_str(&format!(
+                "section{i}::\n  name: \"item {i}\"\n  values:: 1, 2, {i}\n  nested::\n    - true\n    - null\n"
+            ));
+        }
+        doc
+    }
+
+    #[test]
+    fn parallel_parse_matches_serial_parse() {
+        let input = large_document(5000);
+        assert!(input.len() > 2 * MIN_SECTION_LEN);
+        assert!(split_sections(&input, MIN_SECTION_LEN, false).len() > 1);
+
+        let parallel = parse_huml_parallel(&input).expect("should parse");
+        let (_, serial) = crate::parse_huml(&input).unwrap();
+        assert_eq!(parallel, serial);
+    }
+
+    #[test]
+    fn parallel_parse_falls_back_for_errors_and_strings() {
+        // A multiline string whose content looks like top-level keys.
+        let mut input = large_document(2000);
+        input.push_str("text: \"\"\"\n");
+        for i in 0..8000 {
+            input.push_str(&format!("fake{i}: {i}\n"));
+        }
+        input.push_str("\"\"\"\n");
+        input.push_str(&large_document(2000)["%HUML v0.2.0\n".len()..].replace("section", "more"));
+        let parallel = parse_huml_parallel(&input).expect("should parse");
+        let (_, serial) = crate::parse_huml(&input).unwrap();
+        assert_eq!(parallel, serial);
+
+        // Duplicate keys in different sections are reported like a serial parse.
+        let mut input = large_document(3000);
+        input.push_str("section0: 1\n");
+        let err = parse_huml_parallel(&input).unwrap_err();
+        assert_eq!(err, crate::parse_huml(&input).unwrap_err());
+    }
+}
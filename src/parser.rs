@@ -1,9 +1,15 @@
-use crate::{HumlDocument, HumlNumber, HumlValue};
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use crate::version::SpecVersion;
+use crate::{
+    HumlDict, HumlDocument, HumlList, HumlNumber, HumlString, HumlValue, Radix, StringStyle,
+};
+use memchr::{memchr, memchr3, memchr_iter, memrchr};
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fmt;
+use std::ops::Range;
 
-/// HUML specification version supported by this parser
+/// Latest HUML specification version supported by this parser. See
+/// [`supported_versions`](crate::supported_versions) for the full list.
 pub const HUML_VERSION: &str = "0.2.0";
 
 /// Result type used by all parser helpers. This mirrors the old `nom::IResult` interface
@@ -19,7 +25,7 @@ pub struct ParseError {
 }
 
 impl ParseError {
-    fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+    pub(crate) fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
         Self {
             line,
             column,
@@ -47,15 +53,250 @@ enum DataType {
     MultilineList,
 }
 
+/// Options controlling how strictly a document is parsed.
+///
+/// The defaults accept everything the specification allows plus a few harmless
+/// artifacts of common editors. Use [`ParseOptions::strict`] to reject those too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Skip a leading UTF-8 byte order mark (`U+FEFF`). When disabled, a BOM is
+    /// reported as an error instead.
+    pub allow_bom: bool,
+    /// Expand tabs in line indentation to tab stops of this width before parsing.
+    /// When `None`, tabs used for indentation are reported as errors.
+    pub tab_width: Option<usize>,
+    /// Accept any Unicode letter or digit in unquoted keys (e.g. `größe` or `名前`),
+    /// not just ASCII alphanumerics, `-` and `_`.
+    pub unicode_keys: bool,
+    /// Tolerate a single trailing comma at the end of an inline list or dict,
+    /// as in `features:: "a", "b",`.
+    pub trailing_commas: bool,
+}
+
+impl ParseOptions {
+    /// Options that reject anything outside the letter of the specification.
+    pub fn strict() -> Self {
+        Self {
+            allow_bom: false,
+            tab_width: None,
+            unicode_keys: false,
+            trailing_commas: false,
+        }
+    }
+
+    /// Accept tab-indented documents, expanding each tab to the next multiple of `width`.
+    pub fn with_tab_width(mut self, width: usize) -> Self {
+        self.tab_width = Some(width);
+        self
+    }
+
+    /// Accept Unicode letters and digits in unquoted keys.
+    pub fn with_unicode_keys(mut self) -> Self {
+        self.unicode_keys = true;
+        self
+    }
+
+    /// Accept a trailing comma at the end of inline collections.
+    pub fn with_trailing_commas(mut self) -> Self {
+        self.trailing_commas = true;
+        self
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            allow_bom: true,
+            tab_width: None,
+            unicode_keys: false,
+            trailing_commas: false,
+        }
+    }
+}
+
 /// Parse a complete HUML document, including the optional `%HUML` version line.
 pub fn parse_huml(input: &str) -> IResult<'_, HumlDocument> {
+    let input = strip_bom(input, &ParseOptions::default())?;
     let mut parser = Parser::new(input);
     let doc = parser.parse_document()?;
     Ok((parser.remaining(), doc))
 }
 
+/// Parse a complete HUML document using the given [`ParseOptions`].
+pub fn parse_huml_with_options(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<HumlDocument, ParseError> {
+    let input = strip_bom(input, options)?;
+    let input = match options.tab_width {
+        Some(width) => expand_indent_tabs(input, width),
+        None => Cow::Borrowed(input),
+    };
+    let mut parser = Parser::with_options(&input, *options);
+    parser.parse_document()
+}
+
+/// A top-level item yielded by [`parse_iter`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RootItem {
+    /// An entry of a root dict.
+    Entry(String, HumlValue),
+    /// An item of a root list, or a scalar root.
+    Item(HumlValue),
+}
+
+/// Lazily parse the top level of a document, yielding the entries of a root
+/// dict or the items of a root list.
+///
+/// Block collections at the root are parsed one entry per call to `next`, so a
+/// caller looking for a single key can stop without building the rest of the
+/// tree. Other roots are parsed in full on the first call. Iteration ends after
+/// the first error.
+///
+/// ```rust
+/// use huml_rs::{parse_iter, HumlValue, RootItem};
+///
+/// let port = parse_iter("host: \"localhost\"\nport: 8080\nlogging::\n  level: \"info\"\n")
+///     .find_map(|item| match item {
+///         Ok(RootItem::Entry(key, value)) if key == "port" => Some(value),
+///         _ => None,
+///     });
+/// assert!(matches!(port, Some(HumlValue::Number(_))));
+/// ```
+pub fn parse_iter(input: &str) -> ParseIter<'_> {
+    let input = strip_bom(input, &ParseOptions::default()).unwrap_or(input);
+    ParseIter {
+        parser: Parser::new(input),
+        state: IterState::Start,
+    }
+}
+
+/// Iterator returned by [`parse_iter`].
+pub struct ParseIter<'a> {
+    parser: Parser<'a>,
+    state: IterState<'a>,
+}
+
+enum IterState<'a> {
+    Start,
+    Dict(HashSet<Cow<'a, str>>),
+    List,
+    Buffered(std::vec::IntoIter<RootItem>),
+    Done,
+}
+
+impl<'a> ParseIter<'a> {
+    fn step(&mut self) -> Result<Option<RootItem>, ParseError> {
+        loop {
+            match &mut self.state {
+                IterState::Start => self.state = self.start()?,
+                IterState::Dict(seen) => {
+                    if !self.parser.at_block_entry(0)? {
+                        return self.finish();
+                    }
+                    let key = self.parser.parse_key()?;
+                    if !seen.insert(key.clone()) {
+                        return self.parser.err(format!("duplicate key '{}' in dict", key));
+                    }
+                    let value = self.parser.parse_entry_value(0)?;
+                    return Ok(Some(RootItem::Entry(key.into_owned(), value)));
+                }
+                IterState::List => {
+                    if !self.parser.at_list_item(0)? {
+                        return self.finish();
+                    }
+                    let value = self.parser.parse_list_item(0)?;
+                    return Ok(Some(RootItem::Item(value)));
+                }
+                IterState::Buffered(items) => {
+                    let item = items.next();
+                    if item.is_none() {
+                        self.state = IterState::Done;
+                    }
+                    return Ok(item);
+                }
+                IterState::Done => return Ok(None),
+            }
+        }
+    }
+
+    /// Parses the version line and decides how the root will be iterated.
+    fn start(&mut self) -> Result<IterState<'a>, ParseError> {
+        let parser = &mut self.parser;
+        if parser.input.is_empty() {
+            return parser.err("empty document is undefined");
+        }
+        parser.parse_version_header()?;
+        parser.skip_blank_lines()?;
+        if parser.done() {
+            return parser.err("empty document is undefined");
+        }
+
+        match parser.root_type(true)? {
+            DataType::MultilineDict => return Ok(IterState::Dict(HashSet::new())),
+            DataType::MultilineList => return Ok(IterState::List),
+            _ => {}
+        }
+        let items = match parser.parse_root_value(true)? {
+            HumlValue::Dict(dict) => dict
+                .into_iter()
+                .map(|(key, value)| RootItem::Entry(key, value))
+                .collect(),
+            HumlValue::List(items) => items.into_iter().map(RootItem::Item).collect(),
+            value => vec![RootItem::Item(value)],
+        };
+        self.finish()?;
+        Ok(IterState::Buffered(items.into_iter()))
+    }
+
+    fn finish(&mut self) -> Result<Option<RootItem>, ParseError> {
+        self.state = IterState::Done;
+        self.parser.skip_blank_lines()?;
+        if !self.parser.done() {
+            return self.parser.err("unexpected content after document root");
+        }
+        Ok(None)
+    }
+}
+
+impl Iterator for ParseIter<'_> {
+    type Item = Result<RootItem, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.step() {
+            Ok(item) => item.map(Ok),
+            Err(err) => {
+                self.state = IterState::Done;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Parse a complete HUML document from the start of `input` and return the
+/// text that follows it, for documents embedded in larger files.
+///
+/// A block dict or list at the root ends at the first unindented line that
+/// does not start another entry or `- ` item; any other root ends with its
+/// line. Blank lines and comments after the document are consumed.
+///
+/// ```rust
+/// use huml_rs::parse_prefix;
+///
+/// let (rest, doc) = parse_prefix("name: \"app\"\nport: 80\n---\nplain text\n").unwrap();
+/// assert_eq!(rest, "---\nplain text\n");
+/// assert!(matches!(doc.root, huml_rs::HumlValue::Dict(_)));
+/// ```
+pub fn parse_prefix(input: &str) -> IResult<'_, HumlDocument> {
+    let input = strip_bom(input, &ParseOptions::default())?;
+    let mut parser = Parser::new(input);
+    let doc = parser.parse_document_prefix()?;
+    Ok((parser.remaining(), doc))
+}
+
 /// Parse just the root value from a HUML document snippet.
 pub fn parse_document_root(input: &str) -> IResult<'_, HumlValue> {
+    let input = strip_bom(input, &ParseOptions::default())?;
     let mut parser = Parser::new(input);
     parser.skip_blank_lines()?;
     let root = parser.parse_root_value(false)?;
@@ -66,6 +307,85 @@ pub fn parse_document_root(input: &str) -> IResult<'_, HumlValue> {
     Ok((parser.remaining(), root))
 }
 
+/// Remove a leading byte order mark, or reject it if the options disallow it.
+pub(crate) fn strip_bom<'a>(input: &'a str, options: &ParseOptions) -> Result<&'a str, ParseError> {
+    match input.strip_prefix('\u{FEFF}') {
+        Some(_) if !options.allow_bom => Err(ParseError::new(
+            1,
+            1,
+            "byte order mark is not allowed at the start of the document",
+        )),
+        Some(rest) => Ok(rest),
+        None => Ok(input),
+    }
+}
+
+/// Replace tabs in the leading whitespace of every line with spaces up to the next tab stop.
+///
+/// The bodies of multiline strings are copied as they are, since their
+/// indentation is part of the string rather than of the document.
+pub(crate) fn expand_indent_tabs(input: &str, width: usize) -> Cow<'_, str> {
+    if !input.contains('\t') {
+        return Cow::Borrowed(input);
+    }
+
+    let width = width.max(1);
+    let mut out = String::with_capacity(input.len());
+    let mut block: Option<&str> = None;
+    for line in input.split_inclusive('\n') {
+        let indent_len = line.len() - line.trim_start_matches([' ', '\t']).len();
+        let content = &line[indent_len..];
+        match block {
+            Some(delim) if !content.starts_with(delim) => {
+                out.push_str(line);
+                continue;
+            }
+            Some(_) => block = None,
+            None => block = multiline_string_opener(content),
+        }
+
+        let mut column = 0;
+        for byte in line[..indent_len].bytes() {
+            let advance = if byte == b'\t' {
+                width - column % width
+            } else {
+                1
+            };
+            out.extend(std::iter::repeat_n(' ', advance));
+            column += advance;
+        }
+        out.push_str(content);
+    }
+    Cow::Owned(out)
+}
+
+/// The delimiter of the multiline string a line's value opens, if any.
+fn multiline_string_opener(content: &str) -> Option<&'static str> {
+    ["\"\"\"", "```"].into_iter().find(|delim| {
+        content.find(delim).is_some_and(|at| {
+            let rest = content[at + delim.len()..].trim_start_matches(' ');
+            content[..at].ends_with(' ') && (rest.trim_end().is_empty() || rest.starts_with('#'))
+        })
+    })
+}
+
+/// Build the narrowest integer variant holding `magnitude` with the given sign.
+fn integer_from_parts(negative: bool, magnitude: u128) -> Option<HumlNumber> {
+    if negative {
+        let value = 0i128.checked_sub_unsigned(magnitude)?;
+        Some(match i64::try_from(value) {
+            Ok(value) => HumlNumber::Integer(value),
+            Err(_) => HumlNumber::I128(value),
+        })
+    } else if let Ok(value) = i64::try_from(magnitude) {
+        Some(HumlNumber::Integer(value))
+    } else if let Ok(value) = i128::try_from(magnitude) {
+        Some(HumlNumber::I128(value))
+    } else {
+        Some(HumlNumber::U128(magnitude))
+    }
+}
+
 /// Parse an inline scalar (strings, numbers, bools, null, special floats).
 pub fn parse_scalar(input: &str) -> IResult<'_, HumlValue> {
     let mut parser = Parser::new(input);
@@ -77,7 +397,7 @@ pub fn parse_scalar(input: &str) -> IResult<'_, HumlValue> {
 pub fn parse_empty_list(input: &str) -> IResult<'_, HumlValue> {
     if input.trim_start().starts_with("[]") {
         let offset = input.len() - input.trim_start().len() + 2;
-        Ok((&input[offset..], HumlValue::List(Vec::new())))
+        Ok((&input[offset..], HumlValue::List(HumlList::new())))
     } else {
         Err(ParseError::new(1, 1, "expected []"))
     }
@@ -87,7 +407,7 @@ pub fn parse_empty_list(input: &str) -> IResult<'_, HumlValue> {
 pub fn parse_empty_dict(input: &str) -> IResult<'_, HumlValue> {
     if input.trim_start().starts_with("{}") {
         let offset = input.len() - input.trim_start().len() + 2;
-        Ok((&input[offset..], HumlValue::Dict(HashMap::new())))
+        Ok((&input[offset..], HumlValue::Dict(HumlDict::new())))
     } else {
         Err(ParseError::new(1, 1, "expected {}"))
     }
@@ -107,25 +427,414 @@ pub fn parse_inline_dict(input: &str) -> IResult<'_, HumlValue> {
     Ok((parser.remaining(), value))
 }
 
-#[derive(Clone)]
-struct Parser<'a> {
+/// Receives values as the parser recognises them and assembles them into a tree.
+///
+/// [`ValueBuilder`] produces [`HumlValue`]s; other builders can store the tree
+/// elsewhere, such as in an arena.
+pub(crate) trait Builder<'a> {
+    type Value;
+    type List;
+    type Dict;
+
+    fn string(&mut self, value: Cow<'a, str>, style: StringStyle) -> Self::Value;
+    fn number(&mut self, value: HumlNumber) -> Self::Value;
+    fn boolean(&mut self, value: bool) -> Self::Value;
+    fn null(&mut self) -> Self::Value;
+
+    /// Starts a list. `capacity` is a hint counted from the commas of an
+    /// inline list, or 0 for a block list, and may be inexact.
+    fn list(&mut self, capacity: usize) -> Self::List;
+    fn push(&mut self, list: &mut Self::List, value: Self::Value);
+    fn end_list(&mut self, list: Self::List) -> Self::Value;
+
+    /// Starts a dict. `capacity` is a hint counted from the commas of an
+    /// inline dict, or 0 for a block dict, and may be inexact.
+    fn dict(&mut self, capacity: usize) -> Self::Dict;
+    /// Adds an entry for `key`, to be given its value by
+    /// [`insert_value`](Builder::insert_value), or gives the key back if the
+    /// dict already has it.
+    fn insert_key(&mut self, dict: &mut Self::Dict, key: Cow<'a, str>) -> Result<(), String>;
+    /// Gives the entry added last its value.
+    fn insert_value(&mut self, dict: &mut Self::Dict, value: Self::Value);
+    fn end_dict(&mut self, dict: Self::Dict) -> Self::Value;
+
+    /// Called with the byte range of the input each value was parsed from,
+    /// once the value is complete.
+    fn spanned(&mut self, value: Self::Value, _span: Range<usize>) -> Self::Value {
+        value
+    }
+
+    /// Called with where the key of each dict entry starts, before the entry
+    /// is inserted.
+    fn key_start(&mut self, _value: &Self::Value, _start: usize) {}
+}
+
+/// Builds the owned [`HumlValue`] tree.
+pub(crate) struct ValueBuilder;
+
+impl<'a> Builder<'a> for ValueBuilder {
+    type Value = HumlValue;
+    type List = HumlList;
+    type Dict = HumlDict;
+
+    fn string(&mut self, value: Cow<'a, str>, style: StringStyle) -> HumlValue {
+        HumlValue::String(HumlString::with_style(value, style))
+    }
+
+    fn number(&mut self, value: HumlNumber) -> HumlValue {
+        HumlValue::Number(value)
+    }
+
+    fn boolean(&mut self, value: bool) -> HumlValue {
+        HumlValue::Boolean(value)
+    }
+
+    fn null(&mut self) -> HumlValue {
+        HumlValue::Null
+    }
+
+    fn list(&mut self, capacity: usize) -> HumlList {
+        HumlList::with_capacity(capacity)
+    }
+
+    fn push(&mut self, list: &mut HumlList, value: HumlValue) {
+        list.push(value);
+    }
+
+    fn end_list(&mut self, list: HumlList) -> HumlValue {
+        HumlValue::List(list)
+    }
+
+    fn dict(&mut self, capacity: usize) -> HumlDict {
+        HumlDict::with_capacity(capacity)
+    }
+
+    fn insert_key(&mut self, dict: &mut HumlDict, key: Cow<'a, str>) -> Result<(), String> {
+        dict.insert_new(key.into_owned(), HumlValue::Null)
+            .map(|_| ())
+    }
+
+    fn insert_value(&mut self, dict: &mut HumlDict, value: HumlValue) {
+        if let Some(slot) = dict.last_value_mut() {
+            *slot = value;
+        }
+    }
+
+    fn end_dict(&mut self, dict: HumlDict) -> HumlValue {
+        HumlValue::Dict(dict)
+    }
+}
+
+/// The byte range of a parsed value in the input.
+///
+/// Recorded by [`SpanBuilder`] in pre-order: the node of a collection is
+/// followed by the nodes of its items, and so on down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SpanNode {
+    pub(crate) span: Range<usize>,
+    /// The number of nodes for the value and everything in it
+    pub(crate) size: usize,
+    /// Where the key is, for the value of a dict entry
+    pub(crate) key_start: Option<usize>,
+}
+
+/// Builds the owned [`HumlValue`] tree and records where each value is.
+pub(crate) struct SpanBuilder {
+    nodes: Vec<SpanNode>,
+}
+
+impl SpanBuilder {
+    /// Adds a node for a value, to be given its span and size when complete.
+    fn node(&mut self) -> usize {
+        self.nodes.push(SpanNode {
+            span: 0..0,
+            size: 1,
+            key_start: None,
+        });
+        self.nodes.len() - 1
+    }
+}
+
+impl<'a> Builder<'a> for SpanBuilder {
+    type Value = (HumlValue, usize);
+    type List = (HumlList, usize);
+    type Dict = (HumlDict, usize);
+
+    fn string(&mut self, value: Cow<'a, str>, style: StringStyle) -> Self::Value {
+        (ValueBuilder.string(value, style), self.node())
+    }
+
+    fn number(&mut self, value: HumlNumber) -> Self::Value {
+        (HumlValue::Number(value), self.node())
+    }
+
+    fn boolean(&mut self, value: bool) -> Self::Value {
+        (HumlValue::Boolean(value), self.node())
+    }
+
+    fn null(&mut self) -> Self::Value {
+        (HumlValue::Null, self.node())
+    }
+
+    fn list(&mut self, capacity: usize) -> Self::List {
+        (HumlList::with_capacity(capacity), self.node())
+    }
+
+    fn push(&mut self, list: &mut Self::List, value: Self::Value) {
+        list.0.push(value.0);
+    }
+
+    fn end_list(&mut self, (list, node): Self::List) -> Self::Value {
+        self.nodes[node].size = self.nodes.len() - node;
+        (HumlValue::List(list), node)
+    }
+
+    fn dict(&mut self, capacity: usize) -> Self::Dict {
+        (HumlDict::with_capacity(capacity), self.node())
+    }
+
+    fn insert_key(&mut self, dict: &mut Self::Dict, key: Cow<'a, str>) -> Result<(), String> {
+        ValueBuilder.insert_key(&mut dict.0, key)
+    }
+
+    fn insert_value(&mut self, dict: &mut Self::Dict, value: Self::Value) {
+        ValueBuilder.insert_value(&mut dict.0, value.0);
+    }
+
+    fn end_dict(&mut self, (dict, node): Self::Dict) -> Self::Value {
+        self.nodes[node].size = self.nodes.len() - node;
+        (HumlValue::Dict(dict), node)
+    }
+
+    fn spanned(&mut self, value: Self::Value, span: Range<usize>) -> Self::Value {
+        self.nodes[value.1].span = span;
+        value
+    }
+
+    fn key_start(&mut self, value: &Self::Value, start: usize) {
+        self.nodes[value.1].key_start = Some(start);
+    }
+}
+
+/// Parses a complete document like [`parse_huml`], returning the root value
+/// and the spans of it and everything in it, as offsets into `input`.
+pub(crate) fn parse_huml_with_spans(input: &str) -> Result<(HumlValue, Vec<SpanNode>), ParseError> {
+    let stripped = strip_bom(input, &ParseOptions::default())?;
+    let offset = input.len() - stripped.len();
+    let builder = SpanBuilder { nodes: Vec::new() };
+    let mut parser = Parser::with_builder(stripped, ParseOptions::default(), builder);
+    let (root, _) = parser.parse_document_parts()?.root;
+    let mut nodes = parser.builder.nodes;
+    if offset > 0 {
+        for node in &mut nodes {
+            node.span = node.span.start + offset..node.span.end + offset;
+            if let Some(start) = &mut node.key_start {
+                *start += offset;
+            }
+        }
+    }
+    Ok((root, nodes))
+}
+
+/// The pieces of a parsed document, before they are assembled by the caller.
+pub(crate) struct ParsedDocument<V> {
+    pub(crate) version: Option<String>,
+    pub(crate) directives: Vec<String>,
+    pub(crate) root: V,
+}
+
+pub(crate) struct Parser<'a, B = ValueBuilder> {
     input: &'a str,
     bytes: &'a [u8],
     len: usize,
     pos: usize,
-    line: usize,
-    line_start: usize,
+    options: ParseOptions,
+    version: SpecVersion,
+    builder: B,
+    /// Where the value parsed last ends, before any comment or newline
+    value_end: usize,
 }
 
 impl<'a> Parser<'a> {
     fn new(input: &'a str) -> Self {
+        Self::with_options(input, ParseOptions::default())
+    }
+
+    pub(crate) fn with_options(input: &'a str, options: ParseOptions) -> Self {
+        Self::with_builder(input, options, ValueBuilder)
+    }
+
+    fn parse_document(&mut self) -> Result<HumlDocument, ParseError> {
+        let doc = self.parse_document_parts()?;
+        Ok(HumlDocument {
+            version: doc.version,
+            directives: doc.directives,
+            root: doc.root,
+        })
+    }
+
+    fn parse_document_prefix(&mut self) -> Result<HumlDocument, ParseError> {
+        if self.input.is_empty() {
+            return self.err("empty document is undefined");
+        }
+        let (version, directives) = self.parse_version_header()?;
+        self.skip_blank_lines()?;
+        if self.done() {
+            return self.err("empty document is undefined");
+        }
+
+        let root = match self.root_type(true)? {
+            DataType::MultilineDict => {
+                let mut dict = HumlDict::new();
+                while !self.at_prefix_end(|p| p.has_key_value_pair()) && self.at_block_entry(0)? {
+                    let key = self.parse_key()?;
+                    let slot = match dict.insert_new(key.into_owned(), HumlValue::Null) {
+                        Ok(slot) => slot,
+                        Err(key) => return self.err(format!("duplicate key '{}' in dict", key)),
+                    };
+                    *slot = self.parse_entry_value(0)?;
+                }
+                HumlValue::Dict(dict)
+            }
+            DataType::MultilineList => {
+                let mut items = HumlList::new();
+                while !self.at_prefix_end(|p| p.starts_with("- ")) && self.at_list_item(0)? {
+                    items.push(self.parse_list_item(0)?);
+                }
+                HumlValue::List(items)
+            }
+            _ => {
+                let root = self.parse_root_value(true)?;
+                self.skip_blank_lines()?;
+                root
+            }
+        };
+
+        Ok(HumlDocument {
+            version,
+            directives,
+            root,
+        })
+    }
+
+    /// Skips blank lines and reports whether the cursor is at an unindented line
+    /// that does not continue the root, as judged by `continues`.
+    fn at_prefix_end(&mut self, continues: impl Fn(&Self) -> bool) -> bool {
+        if self.skip_blank_lines().is_err() {
+            // Let the entry parser report it.
+            return false;
+        }
+        self.done() || (self.get_cur_indent() == 0 && !continues(self))
+    }
+
+    /// Parses the version line and any blank lines after it. Returns the header
+    /// and the offset of the root value when the root is a block dict, the only
+    /// shape that can be split into sections and parsed piecewise.
+    pub(crate) fn parse_sectioned_prelude(
+        &mut self,
+    ) -> Result<Option<ParsedDocument<usize>>, ParseError> {
+        if self.input.is_empty() {
+            return Ok(None);
+        }
+        let (version, directives) = self.parse_version_header()?;
+        self.skip_blank_lines()?;
+        if self.done() || self.get_cur_indent() != 0 {
+            return Ok(None);
+        }
+        if !matches!(self.get_root_type(), DataType::MultilineDict) {
+            return Ok(None);
+        }
+        Ok(Some(ParsedDocument {
+            version,
+            directives,
+            root: self.line_start(),
+        }))
+    }
+
+    /// The spec version selected by the version line, if any.
+    pub(crate) fn spec_version(&self) -> SpecVersion {
+        self.version
+    }
+
+    /// Parses `input` as a run of top-level dict entries under `version`. Error
+    /// positions are relative to `input`.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn parse_section(
+        input: &'a str,
+        options: ParseOptions,
+        version: SpecVersion,
+    ) -> Result<HumlDict, ParseError> {
+        let mut parser = Self::with_options(input, options);
+        parser.version = version;
+        let HumlValue::Dict(dict) = parser.parse_multiline_dict(0)? else {
+            unreachable!("block dict parser returned a non-dict");
+        };
+        parser.skip_blank_lines()?;
+        if !parser.done() {
+            return parser.err("unexpected content after document root");
+        }
+        Ok(dict)
+    }
+
+    /// Parses the entries of a root block dict from the cursor to the end of the
+    /// input, recording where each one starts. The first entry starts at the
+    /// cursor; later ones at their key line, so the spans tile the input.
+    pub(crate) fn parse_root_entries(&mut self) -> Result<Vec<RootEntry>, ParseError> {
+        let mut entries: Vec<RootEntry> = Vec::new();
+        let mut seen = HashSet::new();
+        let mut start = self.pos;
+        while self.at_block_entry(0)? {
+            if !entries.is_empty() {
+                start = self.line_start();
+            }
+            let key = self.parse_key()?;
+            if !seen.insert(key.clone()) {
+                return self.err(format!("duplicate key '{}' in dict", key));
+            }
+            let value = self.parse_entry_value(0)?;
+            entries.push(RootEntry {
+                start,
+                key: key.into_owned(),
+                value,
+            });
+        }
+        if !self.done() {
+            return self.err("unexpected content after document root");
+        }
+        Ok(entries)
+    }
+
+    /// Parses `input` as a run of top-level dict entries under `version`, with
+    /// offsets and error positions relative to `input`.
+    pub(crate) fn parse_entries(
+        input: &'a str,
+        options: ParseOptions,
+        version: SpecVersion,
+    ) -> Result<Vec<RootEntry>, ParseError> {
+        let mut parser = Self::with_options(input, options);
+        parser.version = version;
+        parser.parse_root_entries()
+    }
+}
+
+/// A top-level dict entry and the offset where its source text begins.
+pub(crate) struct RootEntry {
+    pub(crate) start: usize,
+    pub(crate) key: String,
+    pub(crate) value: HumlValue,
+}
+
+impl<'a, B: Builder<'a>> Parser<'a, B> {
+    pub(crate) fn with_builder(input: &'a str, options: ParseOptions, builder: B) -> Self {
         Self {
             input,
             bytes: input.as_bytes(),
             len: input.len(),
             pos: 0,
-            line: 1,
-            line_start: 0,
+            options,
+            version: SpecVersion::LATEST,
+            builder,
+            value_end: 0,
         }
     }
 
@@ -156,38 +865,45 @@ impl<'a> Parser<'a> {
     }
 
     fn advance(&mut self, n: usize) {
-        for _ in 0..n {
-            if self.done() {
-                break;
-            }
-            if self.bytes[self.pos] == b'\n' {
-                self.pos += 1;
-                self.line += 1;
-                self.line_start = self.pos;
-            } else {
-                self.pos += 1;
-            }
-        }
+        self.pos = (self.pos + n).min(self.len);
+    }
+
+    /// Offset of the first byte of the line containing the cursor.
+    fn line_start(&self) -> usize {
+        memrchr(b'\n', &self.bytes[..self.pos]).map_or(0, |i| i + 1)
+    }
+
+    /// Offset of the newline ending the current line, or the end of input.
+    fn line_end(&self) -> usize {
+        memchr(b'\n', &self.bytes[self.pos..]).map_or(self.len, |i| self.pos + i)
+    }
+
+    /// Moves the cursor to the end of the current line, before its newline.
+    fn skip_to_line_end(&mut self) {
+        self.pos = self.line_end();
     }
 
     fn column(&self) -> usize {
-        self.pos - self.line_start + 1
+        self.pos - self.line_start() + 1
     }
 
+    // Line numbers are only needed for errors, so they are counted on demand
+    // rather than tracked on every advance.
     fn error(&self, msg: impl Into<String>) -> ParseError {
-        ParseError::new(self.line, self.column(), msg)
+        let line = memchr_iter(b'\n', &self.bytes[..self.pos]).count() + 1;
+        ParseError::new(line, self.column(), msg)
     }
 
     fn err<T>(&self, msg: impl Into<String>) -> Result<T, ParseError> {
         Err(self.error(msg))
     }
 
-    fn parse_document(&mut self) -> Result<HumlDocument, ParseError> {
+    pub(crate) fn parse_document_parts(&mut self) -> Result<ParsedDocument<B::Value>, ParseError> {
         if self.input.is_empty() {
             return self.err("empty document is undefined");
         }
 
-        let version = self.parse_version_header()?;
+        let (version, directives) = self.parse_version_header()?;
         self.skip_blank_lines()?;
         if self.done() {
             return self.err("empty document is undefined");
@@ -199,12 +915,16 @@ impl<'a> Parser<'a> {
             return self.err("unexpected content after document root");
         }
 
-        Ok(HumlDocument { version, root })
+        Ok(ParsedDocument {
+            version,
+            directives,
+            root,
+        })
     }
 
-    fn parse_version_header(&mut self) -> Result<Option<String>, ParseError> {
+    fn parse_version_header(&mut self) -> Result<(Option<String>, Vec<String>), ParseError> {
         if !self.starts_with("%HUML") {
-            return Ok(None);
+            return Ok((None, Vec::new()));
         }
 
         self.advance("%HUML".len());
@@ -222,54 +942,50 @@ impl<'a> Parser<'a> {
             }
             if self.pos > start {
                 let token = &self.input[start..self.pos];
-                if token.starts_with('v') {
-                    let trimmed = token.trim_start_matches('v').to_string();
-                    if trimmed != HUML_VERSION {
-                        return self.err(format!(
-                            "unsupported version 'v{}'. expected 'v{}'",
-                            trimmed, HUML_VERSION
-                        ));
-                    }
-                    version = Some(trimmed);
-                } else {
+                if !token.starts_with('v') {
                     return self.err("invalid version token");
                 }
+                self.version = token.parse().map_err(|msg: String| self.error(msg))?;
+                version = Some(self.version.as_str().to_string());
             }
         }
 
-        self.consume_line()?;
-        Ok(version)
-    }
-
-    fn parse_root_value(&mut self, allow_version_line: bool) -> Result<HumlValue, ParseError> {
-        if !allow_version_line && self.starts_with("%HUML") {
-            return self.err("version directive not allowed in this context");
-        }
-
-        if self.get_cur_indent() != 0 {
-            return self.err("root element must not be indented");
-        }
-
-        if self.starts_with("::") {
-            return self.err("'::' indicator not allowed at document root");
+        // Anything after the version token is kept verbatim as a directive,
+        // one per space-separated token, up to an inline comment.
+        let mut directives = Vec::new();
+        while version.is_some() && self.current_byte() == Some(b' ') {
+            match self.bytes.get(self.pos + 1) {
+                Some(b' ') | Some(b'\n') | Some(b'#') | None => break,
+                Some(_) => {}
+            }
+            self.advance(1);
+            let start = self.pos;
+            while let Some(b) = self.current_byte() {
+                if matches!(b, b' ' | b'\n' | b'#') {
+                    break;
+                }
+                self.advance(1);
+            }
+            directives.push(self.input[start..self.pos].to_string());
         }
 
-        if self.starts_with(":") && !self.has_key_value_pair() {
-            return self.err("':' indicator not allowed at document root");
-        }
+        self.consume_line()?;
+        Ok((version, directives))
+    }
 
-        match self.get_root_type() {
+    fn parse_root_value(&mut self, allow_version_line: bool) -> Result<B::Value, ParseError> {
+        match self.root_type(allow_version_line)? {
             DataType::InlineDict => self.parse_inline_vector_contents(DataType::InlineDict),
             DataType::MultilineDict => self.parse_multiline_dict(0),
             DataType::EmptyList => {
-                self.advance(2);
+                let value = self.empty_list();
                 self.consume_line()?;
-                Ok(HumlValue::List(Vec::new()))
+                Ok(value)
             }
             DataType::EmptyDict => {
-                self.advance(2);
+                let value = self.empty_dict();
                 self.consume_line()?;
-                Ok(HumlValue::Dict(HashMap::new()))
+                Ok(value)
             }
             DataType::MultilineList => self.parse_multiline_list(0),
             DataType::InlineList => self.parse_inline_vector_contents(DataType::InlineList),
@@ -281,60 +997,101 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_scalar_value(&mut self, key_indent: usize) -> Result<HumlValue, ParseError> {
+    /// Validates the start of the root value and determines its shape.
+    fn root_type(&self, allow_version_line: bool) -> Result<DataType, ParseError> {
+        if !allow_version_line && self.starts_with("%HUML") {
+            return self.err("version directive not allowed in this context");
+        }
+
+        if self.get_cur_indent() != 0 {
+            return self.err("root element must not be indented");
+        }
+
+        if self.starts_with("::") {
+            return self.err("'::' indicator not allowed at document root");
+        }
+
+        if self.starts_with(":") && !self.has_key_value_pair() {
+            return self.err("':' indicator not allowed at document root");
+        }
+
+        Ok(self.get_root_type())
+    }
+
+    fn parse_scalar_value(&mut self, key_indent: usize) -> Result<B::Value, ParseError> {
+        let start = self.pos;
+        let is_multiline_string = self.at_multiline_string();
+        let value = self.parse_unspanned_scalar(key_indent)?;
+        // Multiline strings set the end themselves, as they go on to consume
+        // the line of the closing delimiter
+        let end = if is_multiline_string {
+            self.value_end
+        } else {
+            self.pos
+        };
+        Ok(self.spanned(value, start, end))
+    }
+
+    fn parse_unspanned_scalar(&mut self, key_indent: usize) -> Result<B::Value, ParseError> {
         if self.done() {
             return self.err("unexpected end of input, expected a value");
         }
 
         if self.starts_with("[]") {
-            self.advance(2);
-            return Ok(HumlValue::List(Vec::new()));
+            return Ok(self.empty_list());
         }
         if self.starts_with("{}") {
-            self.advance(2);
-            return Ok(HumlValue::Dict(HashMap::new()));
+            return Ok(self.empty_dict());
         }
 
         match self.current_byte().unwrap_or_default() {
             b'"' => {
-                if self.starts_with("\"\"\"") {
+                let (value, style) = if self.starts_with("\"\"\"") {
                     let value = self.parse_multiline_string(key_indent)?;
-                    Ok(HumlValue::String(value))
+                    (Cow::Owned(value), StringStyle::MultilineQuoted)
                 } else {
-                    let value = self.parse_string()?;
-                    Ok(HumlValue::String(value))
+                    (self.parse_string()?, StringStyle::Quoted)
+                };
+                Ok(self.builder.string(value, style))
+            }
+            b'`' if self.starts_with("```") => {
+                if !self.version.allows_backtick_strings() {
+                    return self.err(
+                        "triple-backtick multiline strings were removed in v0.2.0; use \"\"\" instead",
+                    );
                 }
+                let value = self.parse_multiline_string(key_indent)?;
+                Ok(self
+                    .builder
+                    .string(Cow::Owned(value), StringStyle::MultilineBacktick))
             }
-            b'`' if self.starts_with("```") => self.err(
-                "triple-backtick multiline strings were removed in v0.2.0; use \"\"\" instead",
-            ),
             b't' if self.starts_with("true") => {
                 self.advance(4);
-                Ok(HumlValue::Boolean(true))
+                Ok(self.builder.boolean(true))
             }
             b'f' if self.starts_with("false") => {
                 self.advance(5);
-                Ok(HumlValue::Boolean(false))
+                Ok(self.builder.boolean(false))
             }
             b'n' if self.starts_with("null") => {
                 self.advance(4);
-                Ok(HumlValue::Null)
+                Ok(self.builder.null())
             }
             b'n' if self.starts_with("nan") => {
                 self.advance(3);
-                Ok(HumlValue::Number(HumlNumber::Nan))
+                Ok(self.builder.number(HumlNumber::Nan))
             }
             b'i' if self.starts_with("inf") => {
                 self.advance(3);
-                Ok(HumlValue::Number(HumlNumber::Infinity(true)))
+                Ok(self.builder.number(HumlNumber::Infinity(true)))
             }
             b'+' => {
                 if self.pos + 1 < self.len && self.input[self.pos + 1..].starts_with("inf") {
                     self.advance(4);
-                    Ok(HumlValue::Number(HumlNumber::Infinity(true)))
+                    Ok(self.builder.number(HumlNumber::Infinity(true)))
                 } else if self.pos + 1 < self.len && self.bytes[self.pos + 1].is_ascii_digit() {
                     let number = self.parse_number()?;
-                    Ok(HumlValue::Number(number))
+                    Ok(self.builder.number(number))
                 } else {
                     self.err("invalid character after '+'")
                 }
@@ -342,17 +1099,17 @@ impl<'a> Parser<'a> {
             b'-' => {
                 if self.pos + 1 < self.len && self.input[self.pos + 1..].starts_with("inf") {
                     self.advance(4);
-                    Ok(HumlValue::Number(HumlNumber::Infinity(false)))
+                    Ok(self.builder.number(HumlNumber::Infinity(false)))
                 } else if self.pos + 1 < self.len && self.bytes[self.pos + 1].is_ascii_digit() {
                     let number = self.parse_number()?;
-                    Ok(HumlValue::Number(number))
+                    Ok(self.builder.number(number))
                 } else {
                     self.err("invalid character after '-'")
                 }
             }
             b if b.is_ascii_digit() => {
                 let number = self.parse_number()?;
-                Ok(HumlValue::Number(number))
+                Ok(self.builder.number(number))
             }
             _ => self.err(format!(
                 "unexpected character '{}' when parsing value",
@@ -361,97 +1118,120 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_multiline_dict(&mut self, indent: usize) -> Result<HumlValue, ParseError> {
-        let mut dict = HashMap::new();
-
-        loop {
-            self.skip_blank_lines()?;
-            if self.done() {
-                break;
-            }
-
-            let cur_indent = self.get_cur_indent();
-            if cur_indent < indent {
-                break;
-            }
-            if cur_indent != indent {
-                return self.err(format!("bad indent {}, expected {}", cur_indent, indent));
-            }
-
-            if !self.is_key_start() {
-                return self.err("expected key");
-            }
+    fn parse_multiline_dict(&mut self, indent: usize) -> Result<B::Value, ParseError> {
+        let mut dict = self.builder.dict(0);
+        let mut start = None;
 
+        while self.at_block_entry(indent)? {
+            start.get_or_insert(self.pos);
+            let key_start = self.pos;
             let key = self.parse_key()?;
 
             // Check for duplicate immediately after parsing key, before parsing value
-            match dict.entry(key) {
-                Entry::Vacant(entry) => {
-                    let indicator = self.parse_indicator()?;
-                    let value = if indicator == ":" {
-                        self.assert_space("after ':'")?;
-                        let is_multiline_string = self.starts_with("\"\"\"");
-                        let scalar = self.parse_scalar_value(cur_indent)?;
-                        if !is_multiline_string {
-                            self.consume_line()?;
-                        }
-                        scalar
-                    } else {
-                        self.parse_vector(indent + 2)?
-                    };
-                    entry.insert(value);
-                }
-                Entry::Occupied(e) => {
-                    return self.err(format!("duplicate key '{}' in dict", e.key()));
-                }
+            if let Err(key) = self.builder.insert_key(&mut dict, key) {
+                return self.err(format!("duplicate key '{}' in dict", key));
             }
+            let value = self.parse_entry_value(indent)?;
+            self.builder.key_start(&value, key_start);
+            self.builder.insert_value(&mut dict, value);
         }
 
-        Ok(HumlValue::Dict(dict))
+        let dict = self.builder.end_dict(dict);
+        let end = self.value_end;
+        Ok(self.spanned(dict, start.unwrap_or(end), end))
     }
 
-    fn parse_multiline_list(&mut self, indent: usize) -> Result<HumlValue, ParseError> {
-        let mut items = Vec::new();
+    /// Skips blank lines and reports whether another key of the block dict at
+    /// `indent` follows, leaving the cursor on it.
+    fn at_block_entry(&mut self, indent: usize) -> Result<bool, ParseError> {
+        self.skip_blank_lines()?;
+        if self.done() {
+            return Ok(false);
+        }
+
+        let cur_indent = self.get_cur_indent();
+        if cur_indent < indent {
+            return Ok(false);
+        }
+        if cur_indent != indent {
+            return self.err(format!("bad indent {}, expected {}", cur_indent, indent));
+        }
 
-        loop {
-            self.skip_blank_lines()?;
-            if self.done() {
-                break;
-            }
+        if !self.is_key_start() {
+            return self.err("expected key");
+        }
+        Ok(true)
+    }
 
-            let cur_indent = self.get_cur_indent();
-            if cur_indent < indent {
-                break;
-            }
-            if cur_indent != indent {
-                return self.err(format!("bad indent {}, expected {}", cur_indent, indent));
+    /// Parses the indicator and value following a key of the block dict at `indent`.
+    fn parse_entry_value(&mut self, indent: usize) -> Result<B::Value, ParseError> {
+        let indicator = self.parse_indicator()?;
+        if indicator == ":" {
+            self.assert_space("after ':'")?;
+            let is_multiline_string = self.at_multiline_string();
+            let scalar = self.parse_scalar_value(indent)?;
+            if !is_multiline_string {
+                self.consume_line()?;
             }
+            Ok(scalar)
+        } else {
+            self.parse_vector(indent + 2)
+        }
+    }
 
-            if self.current_byte() != Some(b'-') {
-                break;
-            }
-            self.advance(1);
-            self.assert_space("after '-'")?;
+    fn parse_multiline_list(&mut self, indent: usize) -> Result<B::Value, ParseError> {
+        let mut items = self.builder.list(0);
+        let mut start = None;
 
-            let value = if self.starts_with("::") {
-                self.advance(2);
-                self.parse_vector(indent + 2)?
-            } else {
-                let is_multiline_string = self.starts_with("\"\"\"");
-                let scalar = self.parse_scalar_value(indent)?;
-                if !is_multiline_string {
-                    self.consume_line()?;
-                }
-                scalar
-            };
+        while self.at_list_item(indent)? {
+            start.get_or_insert(self.pos);
+            let value = self.parse_list_item(indent)?;
+            self.builder.push(&mut items, value);
+        }
+
+        let items = self.builder.end_list(items);
+        let end = self.value_end;
+        Ok(self.spanned(items, start.unwrap_or(end), end))
+    }
+
+    /// Skips blank lines and reports whether another `-` item of the block list
+    /// at `indent` follows, leaving the cursor on it.
+    fn at_list_item(&mut self, indent: usize) -> Result<bool, ParseError> {
+        self.skip_blank_lines()?;
+        if self.done() {
+            return Ok(false);
+        }
 
-            items.push(value);
+        let cur_indent = self.get_cur_indent();
+        if cur_indent < indent {
+            return Ok(false);
+        }
+        if cur_indent != indent {
+            return self.err(format!("bad indent {}, expected {}", cur_indent, indent));
         }
 
-        Ok(HumlValue::List(items))
+        Ok(self.current_byte() == Some(b'-'))
+    }
+
+    /// Parses a `- value` item of the block list at `indent`.
+    fn parse_list_item(&mut self, indent: usize) -> Result<B::Value, ParseError> {
+        self.advance(1);
+        self.assert_space("after '-'")?;
+
+        if self.starts_with("::") {
+            self.advance(2);
+            self.parse_vector(indent + 2)
+        } else {
+            let is_multiline_string = self.at_multiline_string();
+            let scalar = self.parse_scalar_value(indent)?;
+            if !is_multiline_string {
+                self.consume_line()?;
+            }
+            Ok(scalar)
+        }
     }
 
-    fn parse_vector(&mut self, indent: usize) -> Result<HumlValue, ParseError> {
+    fn parse_vector(&mut self, indent: usize) -> Result<B::Value, ParseError> {
         let start_pos = self.pos;
         self.skip_spaces();
 
@@ -475,14 +1255,14 @@ impl<'a> Parser<'a> {
             self.assert_space("after '::'")?;
 
             if self.starts_with("[]") {
-                self.advance(2);
+                let value = self.empty_list();
                 self.consume_line()?;
-                return Ok(HumlValue::List(Vec::new()));
+                return Ok(value);
             }
             if self.starts_with("{}") {
-                self.advance(2);
+                let value = self.empty_dict();
                 self.consume_line()?;
-                return Ok(HumlValue::Dict(HashMap::new()));
+                return Ok(value);
             }
 
             if self.has_inline_dict() {
@@ -511,62 +1291,106 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_inline_vector_contents(&mut self, typ: DataType) -> Result<HumlValue, ParseError> {
-        match typ {
+    fn parse_inline_vector_contents(&mut self, typ: DataType) -> Result<B::Value, ParseError> {
+        let start = self.pos;
+        let value = match typ {
             DataType::InlineDict => {
-                let mut dict = HashMap::new();
+                let capacity = self.count_inline_items();
+                let mut dict = self.builder.dict(capacity);
                 self.parse_inline_items(|parser| {
+                    let key_start = parser.pos;
                     let key = parser.parse_key()?;
 
                     // Check for duplicate immediately after parsing key, before parsing value
-                    match dict.entry(key) {
-                        Entry::Vacant(entry) => {
-                            if parser.current_byte() != Some(b':') {
-                                return parser.err("expected ':' in inline dict");
-                            }
-                            parser.advance(1);
-                            parser.assert_space("in inline dict")?;
-                            let value = parser.parse_scalar_value(0)?;
-                            entry.insert(value);
-                            Ok(())
-                        }
-                        Entry::Occupied(e) => {
-                            parser.err(format!("duplicate key '{}' in dict", e.key()))
-                        }
+                    if let Err(key) = parser.builder.insert_key(&mut dict, key) {
+                        return parser.err(format!("duplicate key '{}' in dict", key));
                     }
+                    if parser.current_byte() != Some(b':') {
+                        return parser.err("expected ':' in inline dict");
+                    }
+                    parser.advance(1);
+                    parser.assert_space("in inline dict")?;
+                    let value = parser.parse_scalar_value(0)?;
+                    parser.builder.key_start(&value, key_start);
+                    parser.builder.insert_value(&mut dict, value);
+                    Ok(())
                 })?;
-                Ok(HumlValue::Dict(dict))
+                self.builder.end_dict(dict)
             }
             DataType::InlineList => {
-                let mut items = Vec::new();
+                let capacity = self.count_inline_items();
+                let mut items = self.builder.list(capacity);
                 self.parse_inline_items(|parser| {
                     let value = parser.parse_scalar_value(0)?;
-                    items.push(value);
+                    parser.builder.push(&mut items, value);
                     Ok(())
                 })?;
-                Ok(HumlValue::List(items))
+                self.builder.end_list(items)
             }
             _ => unreachable!("inline vector helper called with non-inline type"),
-        }
+        };
+        let end = self.value_end;
+        Ok(self.spanned(value, start, end))
     }
 
-    fn parse_key(&mut self) -> Result<String, ParseError> {
+    /// Parses the `[]` at the cursor.
+    fn empty_list(&mut self) -> B::Value {
+        let start = self.pos;
+        self.advance(2);
+        let list = self.builder.list(0);
+        let list = self.builder.end_list(list);
+        self.spanned(list, start, self.pos)
+    }
+
+    /// Parses the `{}` at the cursor.
+    fn empty_dict(&mut self) -> B::Value {
+        let start = self.pos;
+        self.advance(2);
+        let dict = self.builder.dict(0);
+        let dict = self.builder.end_dict(dict);
+        self.spanned(dict, start, self.pos)
+    }
+
+    /// Hands a complete value to the builder along with where it is.
+    fn spanned(&mut self, value: B::Value, start: usize, end: usize) -> B::Value {
+        self.value_end = end;
+        self.builder.spanned(value, start..end)
+    }
+
+    /// Estimates the number of entries in the inline collection at the cursor
+    /// from the commas left on the line.
+    fn count_inline_items(&self) -> usize {
+        memchr_iter(b',', &self.bytes[self.pos..self.line_end()]).count() + 1
+    }
+
+    fn parse_key(&mut self) -> Result<Cow<'a, str>, ParseError> {
         self.skip_spaces();
         if self.current_byte() == Some(b'"') {
             return self.parse_string();
         }
 
         let start = self.pos;
-        while !self.done() {
-            match self.current_byte().unwrap() {
-                b if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' => self.advance(1),
-                _ => break,
-            }
-        }
-        if self.pos == start {
+        let end = self.bare_key_end(start);
+        if end == start {
             self.err("expected a key")
         } else {
-            Ok(self.input[start..self.pos].to_string())
+            self.pos = end;
+            Ok(Cow::Borrowed(&self.input[start..end]))
+        }
+    }
+
+    /// Offset just past the bare key characters starting at `start`.
+    fn bare_key_end(&self, start: usize) -> usize {
+        if self.options.unicode_keys {
+            self.input[start..]
+                .char_indices()
+                .find(|&(_, ch)| !(ch.is_alphanumeric() || ch == '-' || ch == '_'))
+                .map_or(self.len, |(i, _)| start + i)
+        } else {
+            self.bytes[start..]
+                .iter()
+                .position(|&b| !(b.is_ascii_alphanumeric() || b == b'-' || b == b'_'))
+                .map_or(self.len, |i| start + i)
         }
     }
 
@@ -583,94 +1407,148 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_string(&mut self) -> Result<String, ParseError> {
+    fn parse_string(&mut self) -> Result<Cow<'a, str>, ParseError> {
         if self.current_byte() != Some(b'"') {
             return self.err("expected string");
         }
 
         self.advance(1); // opening quote
-        let mut out = String::new();
-        while !self.done() {
-            let ch = self
-                .current_char()
-                .ok_or_else(|| self.error("unexpected end of input"))?;
-            match ch {
-                '"' => {
-                    self.advance_char();
-                    return Ok(out);
-                }
-                '\n' => return self.err("newlines not allowed in single-line strings"),
-                '\\' => {
-                    self.advance_char();
-                    let esc = self
-                        .current_char()
-                        .ok_or_else(|| self.error("incomplete escape sequence"))?;
-                    match esc {
-                        '"' => {
-                            out.push('"');
-                            self.advance_char();
-                        }
-                        '\\' => {
-                            out.push('\\');
-                            self.advance_char();
-                        }
-                        '/' => {
-                            out.push('/');
-                            self.advance_char();
-                        }
-                        'b' => {
-                            out.push('\u{0008}');
-                            self.advance_char();
-                        }
-                        'f' => {
-                            out.push('\u{000C}');
-                            self.advance_char();
-                        }
-                        'n' => {
-                            out.push('\n');
-                            self.advance_char();
-                        }
-                        'r' => {
-                            out.push('\r');
-                            self.advance_char();
-                        }
-                        't' => {
-                            out.push('\t');
-                            self.advance_char();
-                        }
-                        'v' => {
-                            out.push('\u{000B}');
-                            self.advance_char();
-                        }
-                        'u' => {
-                            self.advance_char();
-                            if self.pos + 4 > self.len {
-                                return self.err("incomplete unicode escape");
-                            }
-                            let hex = &self.input[self.pos..self.pos + 4];
-                            if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
-                                return self.err("invalid unicode escape digits");
-                            }
-                            let code_point = u32::from_str_radix(hex, 16)
-                                .map_err(|_| self.error("invalid unicode escape digits"))?;
-                            let decoded = std::char::from_u32(code_point)
-                                .ok_or_else(|| self.error("invalid unicode scalar value"))?;
-                            out.push(decoded);
-                            self.advance(4);
-                        }
-                        _ => {
-                            return Err(self.error(format!("invalid escape character '\\{}'", esc)));
+
+        // Copy whole runs of plain characters at a time. Strings without
+        // escapes are borrowed straight from the input.
+        let mut out: Option<String> = None;
+        loop {
+            let Some(offset) = memchr3(b'"', b'\\', b'\n', &self.bytes[self.pos..]) else {
+                self.pos = self.len;
+                return self.err("unclosed string");
+            };
+            let chunk = &self.input[self.pos..self.pos + offset];
+            self.advance(offset);
+            match self.bytes[self.pos] {
+                b'"' => {
+                    self.advance(1);
+                    return Ok(match out {
+                        Some(mut out) => {
+                            out.push_str(chunk);
+                            Cow::Owned(out)
                         }
-                    }
+                        None => Cow::Borrowed(chunk),
+                    });
                 }
+                b'\n' => return self.err("newlines not allowed in single-line strings"),
                 _ => {
-                    out.push(ch);
-                    self.advance_char();
+                    let out = out.get_or_insert_with(String::new);
+                    out.push_str(chunk);
+                    self.advance(1);
+                    self.parse_escape(out)?;
+                }
+            }
+        }
+    }
+
+    /// Decodes the escape sequence following a backslash into `out`.
+    fn parse_escape(&mut self, out: &mut String) -> Result<(), ParseError> {
+        let esc = self
+            .current_char()
+            .ok_or_else(|| self.error("incomplete escape sequence"))?;
+        match esc {
+            '"' => {
+                out.push('"');
+                self.advance_char();
+            }
+            '\\' => {
+                out.push('\\');
+                self.advance_char();
+            }
+            '/' => {
+                out.push('/');
+                self.advance_char();
+            }
+            'b' => {
+                out.push('\u{0008}');
+                self.advance_char();
+            }
+            'f' => {
+                out.push('\u{000C}');
+                self.advance_char();
+            }
+            'n' => {
+                out.push('\n');
+                self.advance_char();
+            }
+            'r' => {
+                out.push('\r');
+                self.advance_char();
+            }
+            't' => {
+                out.push('\t');
+                self.advance_char();
+            }
+            'v' => {
+                out.push('\u{000B}');
+                self.advance_char();
+            }
+            'u' if self.bytes.get(self.pos + 1) == Some(&b'{') => {
+                self.advance(2);
+                let digits = self
+                    .remaining()
+                    .bytes()
+                    .take_while(u8::is_ascii_hexdigit)
+                    .count();
+                if digits == 0 || digits > 6 {
+                    return self.err("unicode escape must have 1 to 6 hex digits inside braces");
+                }
+                if self.bytes.get(self.pos + digits) != Some(&b'}') {
+                    return self.err("unclosed unicode escape, expected '}'");
                 }
+                let hex = &self.input[self.pos..self.pos + digits];
+                let code_point = u32::from_str_radix(hex, 16)
+                    .map_err(|_| self.error("invalid unicode escape digits"))?;
+                let decoded = std::char::from_u32(code_point)
+                    .ok_or_else(|| self.error("invalid unicode scalar value"))?;
+                out.push(decoded);
+                self.advance(digits + 1);
+            }
+            'u' => {
+                self.advance_char();
+                let mut code_point = self.parse_fixed_unicode_escape()?;
+                // Characters outside the Basic Multilingual Plane are written
+                // as a UTF-16 surrogate pair, as in JSON
+                if (0xD800..0xDC00).contains(&code_point) {
+                    if !self.remaining().starts_with("\\u") {
+                        return self.err("high surrogate must be followed by a low surrogate");
+                    }
+                    self.advance(2);
+                    let low = self.parse_fixed_unicode_escape()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return self.err("high surrogate must be followed by a low surrogate");
+                    }
+                    code_point = 0x10000 + ((code_point - 0xD800) << 10) + (low - 0xDC00);
+                }
+                let decoded = std::char::from_u32(code_point)
+                    .ok_or_else(|| self.error("invalid unicode scalar value"))?;
+                out.push(decoded);
+            }
+            _ => {
+                return Err(self.error(format!("invalid escape character '\\{}'", esc)));
             }
         }
+        Ok(())
+    }
 
-        self.err("unclosed string")
+    /// Reads the four hex digits of a `\\uXXXX` escape
+    fn parse_fixed_unicode_escape(&mut self) -> Result<u32, ParseError> {
+        if self.pos + 4 > self.len {
+            return self.err("incomplete unicode escape");
+        }
+        let hex = &self.input[self.pos..self.pos + 4];
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return self.err("invalid unicode escape digits");
+        }
+        let code_point = u32::from_str_radix(hex, 16)
+            .map_err(|_| self.error("invalid unicode escape digits"))?;
+        self.advance(4);
+        Ok(code_point)
     }
 
     fn parse_multiline_string(&mut self, key_indent: usize) -> Result<String, ParseError> {
@@ -703,6 +1581,7 @@ impl<'a> Parser<'a> {
                     ));
                 }
                 self.advance(3);
+                self.value_end = self.pos;
                 self.consume_line()?;
 
                 if out.ends_with('\n') {
@@ -714,6 +1593,12 @@ impl<'a> Parser<'a> {
             self.pos = line_start;
             let line_content = self.consume_line_content();
 
+            if delim == "\"\"\"" && self.version.trims_quoted_multiline_lines() {
+                out.push_str(line_content.trim());
+                out.push('\n');
+                continue;
+            }
+
             let required = key_indent + 2;
             let bytes = line_content.as_bytes();
             if bytes.len() >= required && bytes[..required].iter().all(|b| *b == b' ') {
@@ -732,14 +1617,10 @@ impl<'a> Parser<'a> {
             self.advance(1);
         }
 
-        if self.starts_with("0x") {
-            return self.parse_base_number(start, 16, "0x");
-        }
-        if self.starts_with("0o") {
-            return self.parse_base_number(start, 8, "0o");
-        }
-        if self.starts_with("0b") {
-            return self.parse_base_number(start, 2, "0b");
+        for radix in [Radix::Hexadecimal, Radix::Octal, Radix::Binary] {
+            if self.starts_with(radix.prefix()) {
+                return self.parse_base_number(start, radix);
+            }
         }
 
         let mut is_float = false;
@@ -776,29 +1657,28 @@ impl<'a> Parser<'a> {
                 .parse::<f64>()
                 .map(HumlNumber::Float)
                 .map_err(|_| self.error("invalid float literal"))
+        } else if let Ok(value) = literal.parse::<i64>() {
+            Ok(HumlNumber::Integer(value))
         } else {
-            literal
-                .parse::<i64>()
-                .map(HumlNumber::Integer)
-                .map_err(|_| self.error("invalid integer literal"))
+            let negative = literal.starts_with('-');
+            let magnitude = literal
+                .trim_start_matches(['+', '-'])
+                .parse::<u128>()
+                .map_err(|_| self.error("invalid integer literal"))?;
+            integer_from_parts(negative, magnitude)
+                .ok_or_else(|| self.error("integer literal out of range"))
         }
     }
 
-    fn parse_base_number(
-        &mut self,
-        start: usize,
-        base: u32,
-        prefix: &str,
-    ) -> Result<HumlNumber, ParseError> {
-        self.advance(prefix.len());
+    fn parse_base_number(&mut self, start: usize, radix: Radix) -> Result<HumlNumber, ParseError> {
+        self.advance(radix.prefix().len());
         let num_start = self.pos;
         while !self.done() {
             let byte = self.current_byte().unwrap();
-            let valid = match base {
-                16 => byte.is_ascii_hexdigit() || byte == b'_',
-                8 => (b'0'..=b'7').contains(&byte) || byte == b'_',
-                2 => byte == b'0' || byte == b'1' || byte == b'_',
-                _ => false,
+            let valid = match radix {
+                Radix::Hexadecimal => byte.is_ascii_hexdigit() || byte == b'_',
+                Radix::Octal => (b'0'..=b'7').contains(&byte) || byte == b'_',
+                Radix::Binary => byte == b'0' || byte == b'1' || byte == b'_',
             };
             if !valid {
                 break;
@@ -810,14 +1690,15 @@ impl<'a> Parser<'a> {
             return self.err("invalid number literal, requires digits after prefix");
         }
 
-        let sign = match self.input.as_bytes()[start] {
-            b'-' => -1,
-            _ => 1,
-        };
+        let negative = self.input.as_bytes()[start] == b'-';
         let digits = self.input[num_start..self.pos].replace('_', "");
-        let parsed = i64::from_str_radix(&digits, base)
+        let magnitude = u128::from_str_radix(&digits, radix.base())
             .map_err(|_| self.error("invalid digits for number literal"))?;
-        Ok(HumlNumber::Integer(parsed * sign))
+        match integer_from_parts(negative, magnitude) {
+            Some(HumlNumber::Integer(value)) => Ok(HumlNumber::Prefixed(value, radix)),
+            Some(number) => Ok(number),
+            None => self.err("integer literal out of range"),
+        }
     }
 
     fn skip_blank_lines(&mut self) -> Result<(), ParseError> {
@@ -837,6 +1718,9 @@ impl<'a> Parser<'a> {
             }
 
             match self.current_byte() {
+                Some(b'\t') if self.in_indentation() => {
+                    return self.err("tabs are not allowed for indentation, use spaces");
+                }
                 Some(b'\n') => {
                     if self.pos > line_start {
                         return self.err("trailing spaces are not allowed");
@@ -864,7 +1748,7 @@ impl<'a> Parser<'a> {
             }
         } else if self.current_byte() == Some(b'#') {
             if self.pos == content_start
-                && self.get_cur_indent() != self.pos.saturating_sub(self.line_start)
+                && self.get_cur_indent() != self.pos.saturating_sub(self.line_start())
             {
                 return self.err("a value must be separated from an inline comment by a space");
             }
@@ -878,9 +1762,7 @@ impl<'a> Parser<'a> {
         }
 
         let comment_end = self.pos;
-        while !self.done() && self.current_byte() != Some(b'\n') {
-            self.advance(1);
-        }
+        self.skip_to_line_end();
 
         if self.pos > 0
             && self.bytes[self.pos.saturating_sub(1)] == b' '
@@ -898,9 +1780,7 @@ impl<'a> Parser<'a> {
 
     fn consume_line_content(&mut self) -> &'a str {
         let start = self.pos;
-        while !self.done() && self.current_byte() != Some(b'\n') {
-            self.advance(1);
-        }
+        self.skip_to_line_end();
         let content = &self.input[start..self.pos];
         if self.current_byte() == Some(b'\n') {
             self.advance(1);
@@ -909,6 +1789,12 @@ impl<'a> Parser<'a> {
     }
 
     fn assert_space(&mut self, context: &str) -> Result<(), ParseError> {
+        if self.current_byte() == Some(b'\t') {
+            return self.err(format!(
+                "tabs are not allowed {}, use a single space",
+                context
+            ));
+        }
         if self.current_byte() != Some(b' ') {
             return self.err(format!("expected single space {}", context));
         }
@@ -931,9 +1817,35 @@ impl<'a> Parser<'a> {
         self.assert_space("after comma")
     }
 
+    /// Whether the cursor is at a comma that ends its line, optionally followed
+    /// by an inline comment.
+    fn at_trailing_comma(&self) -> bool {
+        if self.current_byte() != Some(b',') {
+            return false;
+        }
+        match self.bytes.get(self.pos + 1) {
+            None | Some(b'\n') => true,
+            Some(b' ') => self.bytes.get(self.pos + 2) == Some(&b'#'),
+            Some(_) => false,
+        }
+    }
+
+    /// Whether the cursor is at the opening delimiter of a multiline string.
+    fn at_multiline_string(&self) -> bool {
+        self.starts_with("\"\"\"")
+            || (self.version.allows_backtick_strings() && self.starts_with("```"))
+    }
+
+    /// Whether everything between the start of the line and the cursor is spaces.
+    fn in_indentation(&self) -> bool {
+        self.bytes[self.line_start()..self.pos]
+            .iter()
+            .all(|b| *b == b' ')
+    }
+
     fn get_cur_indent(&self) -> usize {
         let mut indent = 0;
-        let mut idx = self.line_start;
+        let mut idx = self.line_start();
         while idx < self.len && self.bytes[idx] == b' ' {
             indent += 1;
             idx += 1;
@@ -954,7 +1866,13 @@ impl<'a> Parser<'a> {
         if self.starts_with("{}") {
             return DataType::EmptyDict;
         }
-        if self.current_byte() == Some(b'-') {
+        // `-5` and `-inf` are negative numbers, not list items
+        if self.current_byte() == Some(b'-')
+            && !self
+                .bytes
+                .get(self.pos + 1)
+                .is_some_and(|&b| b.is_ascii_digit() || b == b'i')
+        {
             return DataType::MultilineList;
         }
         if self.has_inline_list_at_root() {
@@ -963,21 +1881,41 @@ impl<'a> Parser<'a> {
         DataType::Scalar
     }
 
+    /// Whether the cursor is at a key followed by `:`, without consuming anything.
     fn has_key_value_pair(&self) -> bool {
-        let mut clone = self.clone();
-        clone.parse_key().is_ok() && clone.current_byte() == Some(b':')
+        let mut pos = self.pos;
+        while self.bytes.get(pos) == Some(&b' ') {
+            pos += 1;
+        }
+        let end = if self.bytes.get(pos) == Some(&b'"') {
+            match self.quoted_string_end(pos) {
+                Some(end) => end,
+                None => return false,
+            }
+        } else {
+            self.bare_key_end(pos)
+        };
+        end > pos && self.bytes.get(end) == Some(&b':')
     }
 
-    fn has_inline_list_at_root(&self) -> bool {
-        let mut pos = self.pos;
-        while pos < self.len && self.bytes[pos] != b'\n' && self.bytes[pos] != b'#' {
-            match self.bytes[pos] {
-                b',' => return true,
-                b':' => return false,
+    /// Offset just past the closing quote of the single-line string opening at
+    /// `start`, skipping over escaped characters.
+    fn quoted_string_end(&self, start: usize) -> Option<usize> {
+        let mut pos = start + 1;
+        while let Some(&b) = self.bytes.get(pos) {
+            match b {
+                b'"' => return Some(pos + 1),
+                b'\\' => pos += 2,
+                b'\n' => return None,
                 _ => pos += 1,
             }
         }
-        false
+        None
+    }
+
+    fn has_inline_list_at_root(&self) -> bool {
+        let line = &self.bytes[self.pos..self.line_end()];
+        memchr3(b',', b':', b'#', line).is_some_and(|i| line[i] == b',')
     }
 
     fn has_inline_dict_at_root(&self) -> bool {
@@ -1017,12 +1955,7 @@ impl<'a> Parser<'a> {
                     pos += 1;
                 }
                 b'#' => {
-                    while pos < self.len && self.bytes[pos] != b'\n' {
-                        pos += 1;
-                    }
-                    if pos < self.len && self.bytes[pos] == b'\n' {
-                        pos += 1;
-                    }
+                    pos = memchr(b'\n', &self.bytes[pos..]).map_or(self.len, |i| pos + i + 1);
                 }
                 _ => return false,
             }
@@ -1034,10 +1967,8 @@ impl<'a> Parser<'a> {
     fn has_inline_dict(&self) -> bool {
         let mut pos = self.pos;
         while pos < self.len && self.bytes[pos] != b'\n' && self.bytes[pos] != b'#' {
-            if self.bytes[pos] == b':' {
-                if pos + 1 < self.len && self.bytes[pos + 1] != b':' {
-                    return true;
-                }
+            if self.bytes[pos] == b':' && pos + 1 < self.len && self.bytes[pos + 1] != b':' {
+                return true;
             }
             pos += 1;
         }
@@ -1045,10 +1976,13 @@ impl<'a> Parser<'a> {
     }
 
     fn is_key_start(&self) -> bool {
+        if self.options.unicode_keys {
+            return self
+                .current_char()
+                .is_some_and(|c| c == '"' || c.is_alphabetic());
+        }
         matches!(self.current_byte(), Some(b'"'))
-            || self
-                .current_byte()
-                .map_or(false, |b| b.is_ascii_alphabetic())
+            || self.current_byte().is_some_and(|b| b.is_ascii_alphabetic())
     }
 
     fn skip_spaces(&mut self) {
@@ -1059,7 +1993,7 @@ impl<'a> Parser<'a> {
 
     fn parse_inline_items<F>(&mut self, mut parse_item: F) -> Result<(), ParseError>
     where
-        F: FnMut(&mut Parser<'a>) -> Result<(), ParseError>,
+        F: FnMut(&mut Self) -> Result<(), ParseError>,
     {
         let mut first = true;
         while !self.done()
@@ -1067,6 +2001,10 @@ impl<'a> Parser<'a> {
             && self.current_byte() != Some(b'#')
         {
             if !first {
+                if self.options.trailing_commas && self.at_trailing_comma() {
+                    self.advance(1);
+                    break;
+                }
                 self.expect_comma()?;
             }
             first = false;
@@ -1088,3 +2026,81 @@ impl<'a> Parser<'a> {
         self.consume_line()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_item_counts_stop_at_line_end() {
+        let parser = Parser::new("1, 2, 3, 4\nnext");
+        assert_eq!(parser.count_inline_items(), 4);
+    }
+
+    #[test]
+    fn negative_root_scalars_are_not_list_items() {
+        let root = |input| parse_huml(input).unwrap().1.root;
+        assert_eq!(root("-5\n"), -5);
+        assert_eq!(
+            root("-inf\n"),
+            HumlValue::Number(HumlNumber::Infinity(false))
+        );
+        assert_eq!(root("-1, -2\n"), huml!([-1, -2]));
+        assert_eq!(root("- -1\n"), huml!([-1]));
+        assert!(parse_huml("-x\n").is_err());
+    }
+
+    #[test]
+    fn surrogate_pair_escapes() {
+        let root = |input| parse_huml(input).map(|(_, doc)| doc.root);
+        assert_eq!(
+            root(r#""\ud83d\ude00 \u00e9""#).unwrap(),
+            "\u{1F600} \u{e9}"
+        );
+        assert!(root(r#""\ud83d""#).is_err());
+        assert!(root(r#""\ud83d\u0041""#).is_err());
+        assert!(root(r#""\ude00""#).is_err());
+    }
+
+    #[test]
+    fn spans_cover_each_value() {
+        let input = "\u{feff}a: 1\nb:: 2, \"x\"  # c\nc::\n  - []\n  - ::\n    d: \"\"\"\n      t\n    \"\"\"\n";
+        let (root, nodes) = parse_huml_with_spans(input).unwrap();
+        assert_eq!(root, parse_huml(input).unwrap().1.root);
+        let text: Vec<&str> = nodes.iter().map(|node| &input[node.span.clone()]).collect();
+        assert_eq!(
+            text,
+            [
+                input[3..].trim_end(),
+                "1",
+                "2, \"x\"",
+                "2",
+                "\"x\"",
+                "- []\n  - ::\n    d: \"\"\"\n      t\n    \"\"\"",
+                "[]",
+                "d: \"\"\"\n      t\n    \"\"\"",
+                "\"\"\"\n      t\n    \"\"\"",
+            ]
+        );
+        let sizes: Vec<usize> = nodes.iter().map(|node| node.size).collect();
+        assert_eq!(sizes, [9, 1, 3, 1, 1, 4, 1, 2, 1]);
+        let keys: Vec<Option<&str>> = nodes
+            .iter()
+            .map(|node| node.key_start.map(|start| &input[start..start + 1]))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                None,
+                Some("a"),
+                Some("b"),
+                None,
+                None,
+                Some("c"),
+                None,
+                None,
+                Some("d")
+            ]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Dotted key paths such as `database.replicas[2].host`.
+
+use crate::HumlValue;
+use std::borrow::Cow;
+use std::fmt;
+
+/// One step of a key path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment<'a> {
+    /// A dict key.
+    Key(Cow<'a, str>),
+    /// A list index.
+    Index(usize),
+}
+
+/// The location of a value inside a document, e.g. `database.replicas[2].host`.
+///
+/// Displays in the syntax accepted by [`HumlValue::get_path`], quoting keys
+/// that contain `.`, `[` or `"`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Path<'a> {
+    segments: Vec<PathSegment<'a>>,
+}
+
+impl<'a> Path<'a> {
+    /// The empty path, naming the root value.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Parses a dotted path, returning `None` if it is malformed.
+    pub fn parse(path: &'a str) -> Option<Self> {
+        parse_path(path).map(|segments| Self { segments })
+    }
+
+    pub fn segments(&self) -> &[PathSegment<'a>] {
+        &self.segments
+    }
+
+    /// Returns true for the root path.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn push(&mut self, segment: PathSegment<'a>) {
+        self.segments.push(segment);
+    }
+
+    pub fn pop(&mut self) -> Option<PathSegment<'a>> {
+        self.segments.pop()
+    }
+
+    /// Adds a segment at the start, for building a path from the inside out.
+    pub(crate) fn push_front(&mut self, segment: PathSegment<'a>) {
+        self.segments.insert(0, segment);
+    }
+
+    /// Copies any borrowed keys so the path can outlive the value it came from.
+    pub fn into_owned(self) -> Path<'static> {
+        Path {
+            segments: self
+                .segments
+                .into_iter()
+                .map(|segment| match segment {
+                    PathSegment::Key(key) => PathSegment::Key(Cow::Owned(key.into_owned())),
+                    PathSegment::Index(index) => PathSegment::Index(index),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+                PathSegment::Key(key) => {
+                    if i > 0 {
+                        f.write_str(".")?;
+                    }
+                    if key.is_empty() || key.contains(['.', '[', '"']) {
+                        f.write_str("\"")?;
+                        for c in key.chars() {
+                            if matches!(c, '"' | '\\') {
+                                f.write_str("\\")?;
+                            }
+                            write!(f, "{c}")?;
+                        }
+                        f.write_str("\"")?;
+                    } else {
+                        f.write_str(key)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PathSegment<'_> {
+    pub(crate) fn get<'v>(&self, value: &'v HumlValue) -> Option<&'v HumlValue> {
+        match (value, self) {
+            (HumlValue::Dict(dict), PathSegment::Key(key)) => dict.get(key),
+            (HumlValue::List(items), PathSegment::Index(index)) => items.get(*index),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn get_mut<'v>(&self, value: &'v mut HumlValue) -> Option<&'v mut HumlValue> {
+        match (value, self) {
+            (HumlValue::Dict(dict), PathSegment::Key(key)) => dict.get_mut(key),
+            (HumlValue::List(items), PathSegment::Index(index)) => items.get_mut(*index),
+            _ => None,
+        }
+    }
+
+    /// Removes the entry this segment names from `value`, shifting later list
+    /// items down.
+    pub(crate) fn remove(&self, value: &mut HumlValue) -> Option<HumlValue> {
+        match (value, self) {
+            (HumlValue::Dict(dict), PathSegment::Key(key)) => dict.remove(key),
+            (HumlValue::List(items), PathSegment::Index(index)) if *index < items.len() => {
+                Some(items.remove(*index))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Splits a path into segments. Keys are separated by `.` and list indices are
+/// written `[n]`; a key containing `.`, `[` or `"` is written as a quoted string
+/// with `\"` and `\\` escapes. The empty path has no segments. Returns `None` if
+/// the path is malformed.
+pub(crate) fn parse_path(path: &str) -> Option<Vec<PathSegment<'_>>> {
+    let mut segments = Vec::new();
+    let mut rest = path;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let (index, after) = after.split_once(']')?;
+            segments.push(PathSegment::Index(list_index(index)?));
+            rest = after;
+        } else {
+            if !segments.is_empty() {
+                rest = rest.strip_prefix('.')?;
+            }
+            let (key, after) = split_key(rest)?;
+            segments.push(PathSegment::Key(key));
+            rest = after;
+        }
+    }
+    Some(segments)
+}
+
+fn split_key(input: &str) -> Option<(Cow<'_, str>, &str)> {
+    let Some(quoted) = input.strip_prefix('"') else {
+        let end = input.find(['.', '[']).unwrap_or(input.len());
+        return (end > 0).then(|| (Cow::Borrowed(&input[..end]), &input[end..]));
+    };
+
+    let mut key = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((Cow::Owned(key), &quoted[i + 1..])),
+            '\\' => key.push(chars.next()?.1),
+            _ => key.push(c),
+        }
+    }
+    None
+}
+
+/// Parses a list index, rejecting signs and leading zeros.
+pub(crate) fn list_index(segment: &str) -> Option<usize> {
+    if segment.starts_with('+') || (segment.len() > 1 && segment.starts_with('0')) {
+        return None;
+    }
+    segment.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_display_in_parseable_form() {
+        for path in ["a.b[0][1].c", r#"[0]."a.b"."q\"\\"."""#, ""] {
+            assert_eq!(Path::parse(path).unwrap().to_string(), path);
+        }
+    }
+
+    #[test]
+    fn paths_split_into_keys_and_indices() {
+        let key = |k: &'static str| PathSegment::Key(Cow::Borrowed(k));
+        assert_eq!(
+            parse_path("database.replicas[2].host"),
+            Some(vec![
+                key("database"),
+                key("replicas"),
+                PathSegment::Index(2),
+                key("host")
+            ])
+        );
+        assert_eq!(
+            parse_path(r#"[0][1]."a.b"."q\"""#),
+            Some(vec![
+                PathSegment::Index(0),
+                PathSegment::Index(1),
+                key("a.b"),
+                key("q\""),
+            ])
+        );
+        assert_eq!(parse_path(""), Some(vec![]));
+        for bad in [
+            ".a", "a.", "a..b", "a.[0]", "a[01]", "a[x]", "a[0", "\"a", "a[0]b",
+        ] {
+            assert_eq!(parse_path(bad), None, "{bad}");
+        }
+    }
+}
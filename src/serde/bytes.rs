@@ -0,0 +1,131 @@
+//! Text encodings for byte strings, which HUML has no literal for
+
+/// How byte strings (`serialize_bytes`, e.g. `serde_bytes::ByteBuf`) are
+/// written as HUML strings, and read back
+///
+/// Strings read into byte types are decoded, so with the default encoding
+/// `data: "hello"` no longer reads as the bytes of `hello`. Use
+/// [`BytesEncoding::Raw`] for documents that give bytes as plain text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BytesEncoding {
+    /// Standard base64 with padding (`aGk=`)
+    #[default]
+    Base64,
+    /// Lowercase hex digits, two per byte (`6869`)
+    Hex,
+    /// No encoding: byte strings are written as a list of byte values
+    /// (`104, 105`), and strings are read as their UTF-8 bytes (`"hi"`)
+    Raw,
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl BytesEncoding {
+    /// Encodes `bytes`, or returns `None` for [`BytesEncoding::Raw`], which
+    /// writes them as a list instead
+    pub(crate) fn encode(self, bytes: &[u8]) -> Option<String> {
+        Some(match self {
+            BytesEncoding::Base64 => encode_base64(bytes),
+            BytesEncoding::Hex => {
+                let mut out = String::with_capacity(bytes.len() * 2);
+                for byte in bytes {
+                    out.push(char::from_digit((byte >> 4) as u32, 16).unwrap());
+                    out.push(char::from_digit((byte & 0xf) as u32, 16).unwrap());
+                }
+                out
+            }
+            BytesEncoding::Raw => return None,
+        })
+    }
+
+    /// Decodes `text`, or returns `None` if it isn't valid in this encoding
+    pub(crate) fn decode(self, text: &str) -> Option<Vec<u8>> {
+        match self {
+            BytesEncoding::Base64 => decode_base64(text),
+            BytesEncoding::Hex => {
+                if !text.len().is_multiple_of(2) {
+                    return None;
+                }
+                text.as_bytes()
+                    .chunks(2)
+                    .map(|pair| {
+                        let high = (pair[0] as char).to_digit(16)?;
+                        let low = (pair[1] as char).to_digit(16)?;
+                        Some((high * 16 + low) as u8)
+                    })
+                    .collect()
+            }
+            BytesEncoding::Raw => Some(text.as_bytes().to_vec()),
+        }
+    }
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (i, &b)| group | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (group >> (18 - 6 * i)) & 0x3f;
+                out.push(BASE64_ALPHABET[index as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    for (n, chunk) in text.chunks(4).enumerate() {
+        let last = n == text.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+        let mut group = 0u32;
+        for &c in &chunk[..4 - padding] {
+            let value = BASE64_ALPHABET.iter().position(|&a| a == c)?;
+            group = group << 6 | value as u32;
+        }
+        group <<= 6 * padding as u32;
+        out.extend_from_slice(&group.to_be_bytes()[1..4 - padding]);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodings_round_trip() {
+        for bytes in [&b""[..], b"f", b"fo", b"foo", b"foob", &[0, 255, 16, 1]] {
+            for encoding in [BytesEncoding::Base64, BytesEncoding::Hex] {
+                let text = encoding.encode(bytes).unwrap();
+                assert_eq!(encoding.decode(&text).as_deref(), Some(bytes), "{text}");
+            }
+        }
+        assert_eq!(BytesEncoding::Base64.encode(b"foob").unwrap(), "Zm9vYg==");
+        assert_eq!(BytesEncoding::Hex.encode(&[0, 255, 16]).unwrap(), "00ff10");
+        assert_eq!(BytesEncoding::Raw.encode(b"foo"), None);
+        assert_eq!(
+            BytesEncoding::Raw.decode("foo").as_deref(),
+            Some(&b"foo"[..])
+        );
+
+        assert_eq!(BytesEncoding::Base64.decode("Zm9v!g=="), None);
+        assert_eq!(BytesEncoding::Base64.decode("Zg==Zg=="), None);
+        assert_eq!(BytesEncoding::Hex.decode("abc"), None);
+        assert_eq!(BytesEncoding::Hex.decode("zz"), None);
+    }
+}
@@ -0,0 +1,101 @@
+//! Naming conventions for struct field keys
+
+use std::borrow::Cow;
+
+/// A naming convention for the keys of struct fields in HUML, to use instead
+/// of the `snake_case` Rust field names without a `#[serde(rename)]` on every
+/// field
+///
+/// Field names are expected to be `snake_case`, as Rust names them. Fields
+/// renamed with `#[serde(rename)]` are converted like any other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyCase {
+    /// `max-connections`
+    Kebab,
+    /// `maxConnections`
+    Camel,
+    /// `MaxConnections`
+    Pascal,
+    /// `MAX_CONNECTIONS`
+    ScreamingSnake,
+}
+
+impl KeyCase {
+    /// Converts the `snake_case` field name `field` to this convention
+    pub fn apply(self, field: &str) -> Cow<'_, str> {
+        // Leading underscores, as in `_private`, are kept in every case
+        let name = field.trim_start_matches('_');
+        let leading = &field[..field.len() - name.len()];
+        match self {
+            KeyCase::Kebab if name.contains('_') => {
+                Cow::Owned(format!("{leading}{}", name.replace('_', "-")))
+            }
+            KeyCase::Kebab => Cow::Borrowed(field),
+            KeyCase::ScreamingSnake => Cow::Owned(field.to_uppercase()),
+            KeyCase::Camel | KeyCase::Pascal => {
+                let mut out = String::with_capacity(field.len());
+                let mut upper = self == KeyCase::Pascal;
+                for c in field.chars() {
+                    if c == '_' {
+                        if out.bytes().all(|b| b == b'_') {
+                            out.push(c);
+                        } else {
+                            upper = true;
+                        }
+                    } else if upper {
+                        out.extend(c.to_uppercase());
+                        upper = false;
+                    } else {
+                        out.push(c);
+                    }
+                }
+                Cow::Owned(out)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_convert_to_each_case() {
+        let convert = |case: KeyCase| -> Vec<String> {
+            ["max_connections", "port", "_private_key", "ipv4_addr"]
+                .iter()
+                .map(|field| case.apply(field).into_owned())
+                .collect()
+        };
+        assert_eq!(
+            convert(KeyCase::Kebab),
+            ["max-connections", "port", "_private-key", "ipv4-addr"]
+        );
+        assert_eq!(
+            convert(KeyCase::Camel),
+            ["maxConnections", "port", "_privateKey", "ipv4Addr"]
+        );
+        assert_eq!(
+            convert(KeyCase::Pascal),
+            ["MaxConnections", "Port", "_PrivateKey", "Ipv4Addr"]
+        );
+        assert_eq!(
+            convert(KeyCase::ScreamingSnake),
+            ["MAX_CONNECTIONS", "PORT", "_PRIVATE_KEY", "IPV4_ADDR"]
+        );
+    }
+
+    #[test]
+    fn leading_underscores_are_kept() {
+        let cases = [
+            (KeyCase::Kebab, "__raw-value"),
+            (KeyCase::Camel, "__rawValue"),
+            (KeyCase::Pascal, "__RawValue"),
+            (KeyCase::ScreamingSnake, "__RAW_VALUE"),
+        ];
+        for (case, expected) in cases {
+            assert_eq!(case.apply("__raw_value"), expected);
+        }
+        assert_eq!(KeyCase::Kebab.apply("_"), "_");
+    }
+}
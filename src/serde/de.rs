@@ -10,11 +10,25 @@
 //! - **Lists**: inline (`item1, item2, item3`) and empty (`[]`)
 //! - **Dicts**: inline (`key: value, key2: value2`) and empty (`{}`)
 //! - **Nested structures**: using proper HUML indentation
-//! - **Enums**: unit variants, struct variants, and tuple variants
-
-use crate::{parse_huml, HumlNumber, HumlValue};
-use serde::de::{self, Deserialize, DeserializeSeed, Visitor};
-use std::{fmt, str::FromStr};
+//! - **Enums**: unit variants, struct variants, and tuple variants, with unit
+//!   variants also given by their zero-based position in the enum (`level: 2`).
+//!   The position ignores explicit discriminants such as `High = 5`; use
+//!   [`serde_repr`](https://docs.rs/serde_repr) to read those. Adjacently tagged enums
+//!   (`#[serde(tag = "type", content = "value")]`) read from a two-key dict
+
+use super::spanned;
+use super::{BytesEncoding, KeyCase};
+use crate::parser::{parse_huml_with_spans, SpanNode};
+use crate::value::{dict, Path, PathSegment};
+use crate::{parse_huml, HumlDict, HumlList, HumlNumber, HumlString, HumlValue};
+use serde::de::value::{BorrowedStrDeserializer, StringDeserializer, U32Deserializer};
+use serde::de::{self, Deserialize, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::Arc;
+use std::{fmt, io, mem, str::FromStr};
 
 /// Error type for HUML deserialization
 #[derive(Debug, Clone)]
@@ -28,7 +42,79 @@ pub enum Error {
     /// Missing field error
     MissingField(&'static str),
     /// Unknown field error
+    ///
+    /// No longer produced; unknown fields are reported as
+    /// [`Error::UnexpectedKey`].
+    #[deprecated(note = "reported as UnexpectedKey")]
     UnknownField(&'static str),
+    /// A key that isn't a field of a struct that denies unknown fields, with
+    /// where it is
+    UnexpectedKey {
+        /// The key
+        key: String,
+        /// The fields of the struct
+        expected: &'static [&'static str],
+        /// The line and column of the key in the input, when known
+        position: Option<(usize, usize)>,
+    },
+    /// IO error while reading the input
+    Io(String),
+    /// The input isn't UTF-8; holds the byte offset of the first invalid byte
+    InvalidUtf8(usize),
+    /// A number too big or small for the integer type it's read into
+    OutOfRange {
+        /// The number as written
+        value: String,
+        /// The integer type, e.g. `u8`
+        ty: &'static str,
+    },
+    /// A list with a different number of items than the tuple it's read into
+    InvalidLength {
+        /// The number of items the tuple has
+        expected: usize,
+        /// The number of items in the list
+        found: usize,
+    },
+    /// An error in a value nested in the document, and where it is
+    At {
+        /// The path to the value
+        path: Path<'static>,
+        /// What went wrong with it
+        error: Box<Error>,
+        /// The line and column of the value in the input, when known
+        position: Option<(usize, usize)>,
+    },
+}
+
+impl Error {
+    /// Adds the step from a parent value to the front of the path. The
+    /// `position` of the value at `segment` is kept unless one nested deeper
+    /// is already known.
+    fn at(self, segment: PathSegment<'static>, position: Option<(usize, usize)>) -> Error {
+        match self {
+            Error::At {
+                mut path,
+                error,
+                position: inner,
+            } => {
+                path.push_front(segment);
+                Error::At {
+                    path,
+                    error,
+                    position: inner.or(position),
+                }
+            }
+            err => {
+                let mut path = Path::root();
+                path.push(segment);
+                Error::At {
+                    path,
+                    error: Box::new(err),
+                    position,
+                }
+            }
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -38,41 +124,742 @@ impl fmt::Display for Error {
             Error::ParseError(msg) => write!(f, "Parse error: {msg}"),
             Error::InvalidType(msg) => write!(f, "Invalid type: {msg}"),
             Error::MissingField(field) => write!(f, "Missing field: {field}"),
+            #[allow(deprecated)]
             Error::UnknownField(field) => write!(f, "Unknown field: {field}"),
+            Error::UnexpectedKey {
+                key,
+                expected,
+                position,
+            } => fmt_unknown_field(f, key, key, expected, *position),
+            Error::Io(msg) => write!(f, "IO error: {msg}"),
+            Error::InvalidUtf8(offset) => write!(f, "Invalid UTF-8 at byte {offset}"),
+            Error::OutOfRange { value, ty } => write!(f, "value {value} out of range for {ty}"),
+            Error::InvalidLength { expected, found } => {
+                write!(f, "expected {expected} elements, found {found}")
+            }
+            // Name the unknown field by its path
+            Error::At {
+                path,
+                error,
+                position,
+            } => match &**error {
+                Error::UnexpectedKey {
+                    key,
+                    expected,
+                    position,
+                } => fmt_unknown_field(f, path, key, expected, *position),
+                err => {
+                    write!(f, "{err} at `{path}`")?;
+                    if let Some((line, column)) = position {
+                        write!(f, " on line {line}:{column}")?;
+                    }
+                    Ok(())
+                }
+            },
         }
     }
 }
 
 impl std::error::Error for Error {}
 
+/// Writes an unknown field error, naming the field as `name`
+fn fmt_unknown_field(
+    f: &mut fmt::Formatter<'_>,
+    name: &dyn fmt::Display,
+    field: &str,
+    expected: &[&str],
+    position: Option<(usize, usize)>,
+) -> fmt::Result {
+    write!(f, "Unknown field `{name}`")?;
+    if let Some((line, column)) = position {
+        write!(f, " at line {line}:{column}")?;
+    }
+    if let Some(suggestion) = suggest(field, expected) {
+        return write!(f, ", did you mean `{suggestion}`?");
+    }
+    match expected {
+        [] => f.write_str(", there are no fields"),
+        [only] => write!(f, ", expected `{only}`"),
+        [first, second] => write!(f, ", expected `{first}` or `{second}`"),
+        [first, rest @ ..] => {
+            write!(f, ", expected one of `{first}`")?;
+            rest.iter().try_for_each(|field| write!(f, ", `{field}`"))
+        }
+    }
+}
+
+/// The expected field `field` is most likely a typo of, if any
+fn suggest<'a>(field: &str, expected: &[&'a str]) -> Option<&'a str> {
+    expected
+        .iter()
+        .map(|candidate| (edit_distance(field, candidate), *candidate))
+        .filter(|(distance, candidate)| *distance <= (candidate.len() / 3).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The number of characters to insert, remove or replace to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let replaced = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = replaced.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The 1-based line and column of byte `offset` of `input`
+fn line_column(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    let line = before.matches('\n').count() + 1;
+    (line, before[line_start..].chars().count() + 1)
+}
+
 impl de::Error for Error {
     fn custom<T: fmt::Display>(msg: T) -> Self {
         Error::Message(msg.to_string())
     }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::MissingField(field)
+    }
+
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        Error::UnexpectedKey {
+            key: field.to_string(),
+            expected,
+            position: None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err.to_string())
+    }
 }
 
 /// Result type for HUML deserialization
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// HUML deserializer
-pub struct Deserializer {
-    value: HumlValue,
+///
+/// Reads from an owned [`HumlValue`] ([`Deserializer::new`]) or a borrowed one
+/// ([`Deserializer::from_ref`]). A borrowed value is never cloned, and strings
+/// in the output may borrow from it.
+pub struct Deserializer<'de> {
+    value: Content<'de>,
+    ctx: Context<'de>,
+    /// Where the value is in the input, when it was parsed by this crate's
+    /// `from_*` functions
+    spans: Option<Spans>,
+}
+
+/// Choices about how HUML values are read into Rust types
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeserializeOptions {
+    /// How byte strings were encoded as strings. Should match the
+    /// [`SerializeOptions::bytes_encoding`](super::SerializeOptions::bytes_encoding)
+    /// they were written with.
+    pub bytes_encoding: BytesEncoding,
+    /// Read integer types only from integers and float types only from
+    /// floats, instead of converting between them
+    pub strict_numbers: bool,
+    /// Read number and boolean types from strings holding them as well,
+    /// such as `"8080"` or `"true"`
+    pub lenient_scalars: bool,
+    /// Match dict keys to struct fields ignoring case, so `Port` fills
+    /// `port`. Keys that match a field exactly are still preferred.
+    pub case_insensitive_keys: bool,
+    /// The naming convention of struct field keys, such as `max-connections`
+    /// for a `max_connections` field. Keys that match a field exactly are
+    /// still accepted.
+    pub key_case: Option<KeyCase>,
+    /// Treat struct fields set to `null` as if they were left out, so
+    /// `#[serde(default)]` fields get their default. Fields without a
+    /// default then fail as missing.
+    pub null_as_default: bool,
+}
+
+impl DeserializeOptions {
+    /// Choose how byte strings are decoded
+    pub fn with_bytes_encoding(mut self, encoding: BytesEncoding) -> Self {
+        self.bytes_encoding = encoding;
+        self
+    }
+
+    /// Choose whether integers and floats may stand in for each other
+    pub fn with_strict_numbers(mut self, strict: bool) -> Self {
+        self.strict_numbers = strict;
+        self
+    }
+
+    /// Choose whether numbers and booleans may be read from strings
+    pub fn with_lenient_scalars(mut self, lenient: bool) -> Self {
+        self.lenient_scalars = lenient;
+        self
+    }
+
+    /// Choose whether keys match struct fields regardless of case
+    pub fn with_case_insensitive_keys(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive_keys = case_insensitive;
+        self
+    }
+
+    /// Read struct fields from keys in the `case` naming convention
+    pub fn with_key_case(mut self, case: KeyCase) -> Self {
+        self.key_case = Some(case);
+        self
+    }
+
+    /// Choose whether struct fields set to `null` get their default
+    pub fn with_null_as_default(mut self, null_as_default: bool) -> Self {
+        self.null_as_default = null_as_default;
+        self
+    }
+}
+
+/// What a [`Deserializer`] passes on to the deserializers of nested values
+#[derive(Clone, Copy, Default)]
+struct Context<'de> {
+    options: DeserializeOptions,
+    /// The text the value was parsed from, which strings can borrow from
+    input: Option<&'de str>,
+    /// Where earlier attempts of [`from_str_all_errors`] failed in the value
+    recovery: Option<&'de Recovery>,
+    /// Errors [`from_str_all_errors`] read past with a placeholder
+    errors: Option<&'de RefCell<Vec<Error>>>,
+}
+
+impl<'de> Context<'de> {
+    /// The text of the string `s` parsed from `span`, as a slice of the
+    /// input, if it was written there without escapes
+    fn borrow(&self, s: &str, span: Range<usize>) -> Option<&'de str> {
+        let text = self.input?.get(span.start + 1..span.end.checked_sub(1)?)?;
+        (text == s).then_some(text)
+    }
+
+    /// The text of the dict key `key` written at `start`, as a slice of the
+    /// input, if it was written there without escapes
+    fn borrow_key(&self, key: &str, start: usize) -> Option<&'de str> {
+        let written = self.input?.get(start..)?;
+        let text = match written.strip_prefix('"') {
+            Some(quoted) if quoted.as_bytes().get(key.len()) == Some(&b'"') => {
+                quoted.get(..key.len())
+            }
+            Some(_) => None,
+            None => written.get(..key.len()),
+        }?;
+        (text == key).then_some(text)
+    }
+
+    /// The context of the value under `key` in a dict
+    fn key(self, key: &str) -> Self {
+        Self {
+            recovery: self.recovery.and_then(|recovery| {
+                recovery.child(|segment| matches!(segment, PathSegment::Key(k) if k == key))
+            }),
+            ..self
+        }
+    }
+
+    /// The context of the item at `index` in a list
+    fn index(self, index: usize) -> Self {
+        Self {
+            recovery: self.recovery.and_then(|recovery| {
+                recovery.child(|segment| *segment == PathSegment::Index(index))
+            }),
+            ..self
+        }
+    }
+
+    fn fix(&self) -> Option<Fix> {
+        self.recovery.and_then(|recovery| recovery.fix)
+    }
+
+    /// Records `err` and reads `placeholder` in place of the failing value,
+    /// when collecting errors for [`from_str_all_errors`] and fewer than
+    /// [`MAX_ERRORS`] were found; otherwise returns `err`
+    fn recover<T>(&self, err: Error, placeholder: impl FnOnce() -> Result<T>) -> Result<T> {
+        match self.errors {
+            Some(errors) if errors.borrow().len() < MAX_ERRORS => {
+                errors.borrow_mut().push(err);
+                placeholder()
+            }
+            _ => Err(err),
+        }
+    }
+
+    /// The line and column of byte `offset` of the input, if known
+    fn position(&self, offset: Option<usize>) -> Option<(usize, usize)> {
+        Some(line_column(self.input?, offset?))
+    }
+
+    /// Reads the value at `segment`, written at `offset` of the input, with
+    /// `read`, adding `segment` to the path of the error it returns and of
+    /// those it records
+    fn within<T>(
+        &self,
+        segment: PathSegment<'static>,
+        offset: Option<usize>,
+        read: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        let start = self.errors.map_or(0, |errors| errors.borrow().len());
+        let result = read();
+        if let Some(errors) = self.errors {
+            let position = self.position(offset);
+            for err in &mut errors.borrow_mut()[start..] {
+                *err =
+                    mem::replace(err, Error::Message(String::new())).at(segment.clone(), position);
+            }
+        }
+        result.map_err(|err| err.at(segment, self.position(offset)))
+    }
+}
+
+/// The most errors [`from_str_all_errors`] reports
+const MAX_ERRORS: usize = 100;
+
+/// Where [`from_str_all_errors`] ran into errors, so the next attempt can
+/// get past them
+#[derive(Default)]
+struct Recovery {
+    fix: Option<Fix>,
+    children: Vec<(PathSegment<'static>, Recovery)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Fix {
+    /// Leave out the dict entry
+    Skip,
+    /// Read a [`Placeholder`] instead of the value
+    Placeholder,
+}
+
+impl Recovery {
+    fn child(&self, is_segment: impl Fn(&PathSegment) -> bool) -> Option<&Recovery> {
+        self.children
+            .iter()
+            .find(|(segment, _)| is_segment(segment))
+            .map(|(_, child)| child)
+    }
+
+    fn child_mut(&mut self, segment: &PathSegment<'static>) -> &mut Recovery {
+        match self.children.iter().position(|(s, _)| s == segment) {
+            Some(i) => &mut self.children[i].1,
+            None => {
+                self.children.push((segment.clone(), Recovery::default()));
+                &mut self.children.last_mut().unwrap().1
+            }
+        }
+    }
+
+    /// Keys missing from `dict` that need a placeholder
+    fn missing_keys<'a>(&'a self, dict: &HumlDict) -> Vec<&'a str> {
+        self.children
+            .iter()
+            .filter_map(|(segment, child)| match segment {
+                PathSegment::Key(key)
+                    if child.fix == Some(Fix::Placeholder) && dict.get(key).is_none() =>
+                {
+                    Some(key.as_ref())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Works around `err` in the next attempt. A failing dict entry is left
+    /// out, and a failing list item, or a required field, replaced with a
+    /// placeholder. Returns whether `err` is worth reporting, or `None` if
+    /// there's no way around it.
+    fn mark(&mut self, err: &Error) -> Option<bool> {
+        let (path, err) = match err {
+            Error::At { path, error, .. } => (path.segments(), &**error),
+            err => (&[][..], err),
+        };
+        let mut node = self;
+        for segment in path {
+            if node.fix.is_some() {
+                return None;
+            }
+            node = node.child_mut(segment);
+        }
+        if node.fix.is_some() {
+            return None;
+        }
+        match (err, path.last()) {
+            (Error::MissingField(field), _) => {
+                let child = node.child_mut(&PathSegment::Key(Cow::Borrowed(field)));
+                let report = match child.fix {
+                    // The field was left out for an error already reported
+                    Some(Fix::Skip) => false,
+                    Some(Fix::Placeholder) => return None,
+                    None => true,
+                };
+                child.fix = Some(Fix::Placeholder);
+                Some(report)
+            }
+            (_, None) => None,
+            (_, Some(PathSegment::Index(_))) => {
+                node.fix = Some(Fix::Placeholder);
+                Some(true)
+            }
+            (_, Some(PathSegment::Key(_))) => {
+                node.fix = Some(Fix::Skip);
+                Some(true)
+            }
+        }
+    }
+}
+
+/// The span of a value in the input, and of everything in it
+#[derive(Clone)]
+struct Spans {
+    nodes: Arc<[SpanNode]>,
+    node: usize,
+}
+
+impl Spans {
+    fn span(&self) -> Range<usize> {
+        self.nodes[self.node].span.clone()
+    }
+
+    fn start(&self) -> usize {
+        self.nodes[self.node].span.start
+    }
+
+    /// Where the key is, for the value of a dict entry
+    fn key_start(&self) -> Option<usize> {
+        self.nodes[self.node].key_start
+    }
+
+    /// The spans of the first item of this collection
+    fn first_child(&self) -> Spans {
+        Spans {
+            nodes: self.nodes.clone(),
+            node: self.node + 1,
+        }
+    }
+
+    /// Returns these spans and moves on to those of the next item in the
+    /// same collection
+    fn advance(&mut self) -> Spans {
+        let current = self.clone();
+        self.node += self.nodes[self.node].size;
+        current
+    }
+}
+
+/// The value being deserialized, either owned or borrowed from the caller.
+enum Content<'de> {
+    Owned(HumlValue),
+    Borrowed(&'de HumlValue),
+}
+
+impl<'de> Content<'de> {
+    fn get(&self) -> &HumlValue {
+        match self {
+            Content::Owned(value) => value,
+            Content::Borrowed(value) => value,
+        }
+    }
+
+    fn into_owned(self) -> HumlValue {
+        match self {
+            Content::Owned(value) => value,
+            Content::Borrowed(value) => value.clone(),
+        }
+    }
+
+    fn into_seq(self, ctx: Context<'de>, spans: Option<Spans>) -> Option<SeqDeserializer<'de>> {
+        let (iter, len) = match self {
+            Content::Owned(HumlValue::List(list)) => {
+                let len = list.len();
+                (SeqIter::Owned(list.into_iter()), len)
+            }
+            Content::Borrowed(HumlValue::List(list)) => {
+                (SeqIter::Borrowed(list.iter()), list.len())
+            }
+            _ => return None,
+        };
+        Some(SeqDeserializer {
+            iter,
+            len,
+            index: 0,
+            ctx,
+            spans: spans.map(|spans| spans.first_child()),
+        })
+    }
+
+    fn into_map(self, ctx: Context<'de>, spans: Option<Spans>) -> Option<MapDeserializer<'de>> {
+        let missing = |dict| match ctx.recovery {
+            Some(recovery) => recovery.missing_keys(dict),
+            None => Vec::new(),
+        };
+        let (iter, len, missing) = match self {
+            Content::Owned(HumlValue::Dict(dict)) => {
+                let (len, missing) = (dict.len(), missing(&dict));
+                (MapIter::Owned(dict.into_iter()), len, missing)
+            }
+            Content::Borrowed(HumlValue::Dict(dict)) => {
+                (MapIter::Borrowed(dict.iter()), dict.len(), missing(dict))
+            }
+            _ => return None,
+        };
+        Some(MapDeserializer {
+            iter,
+            value: None,
+            len,
+            ctx,
+            spans: spans.map(|spans| spans.first_child()),
+            missing,
+            fields: &[],
+        })
+    }
+}
+
+/// A dict key or enum variant name, owned or borrowed like [`Content`].
+enum Key<'de> {
+    Owned(String),
+    Borrowed(&'de str),
+}
+
+impl<'de> Key<'de> {
+    fn name(&self) -> Cow<'de, str> {
+        match self {
+            Key::Owned(key) => Cow::Owned(key.clone()),
+            Key::Borrowed(key) => Cow::Borrowed(key),
+        }
+    }
+
+    /// Deserializes the key with `seed`, borrowing it from the input where
+    /// it was written at `start`, if known
+    fn deserialize<S>(self, seed: S, ctx: Context<'de>, start: Option<usize>) -> Result<S::Value>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        let key = match self {
+            Key::Owned(key) => match start.and_then(|start| ctx.borrow_key(&key, start)) {
+                Some(key) => Cow::Borrowed(key),
+                None => Cow::Owned(key),
+            },
+            Key::Borrowed(key) => Cow::Borrowed(key),
+        };
+        seed.deserialize(KeyDeserializer(key))
+    }
+}
+
+/// Reads a dict key or variant name. Keys are always text, so number and
+/// boolean types parse it, the way the serializer writes map keys of those
+/// types.
+struct KeyDeserializer<'de>(Cow<'de, str>);
+
+impl<'de> KeyDeserializer<'de> {
+    fn visit_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Cow::Borrowed(key) => visitor.visit_borrowed_str(key),
+            Cow::Owned(key) => visitor.visit_string(key),
+        }
+    }
+}
+
+/// Deserializer methods that parse the key as `$ty`, and leave a key that
+/// doesn't parse for the visitor to reject as a string
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident($ty:ty)),* $(,)?) => {$(
+        fn $method<V>(self, visitor: V) -> Result<V::Value>
+        where
+            V: Visitor<'de>,
+        {
+            match self.0.parse::<$ty>() {
+                Ok(value) => visitor.$visit(value),
+                Err(_) => self.visit_str(visitor),
+            }
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.visit_str(visitor)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool(bool),
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Cow::Borrowed(key) => visitor.visit_enum(BorrowedStrDeserializer::<Error>::new(key)),
+            Cow::Owned(key) => visitor.visit_enum(StringDeserializer::<Error>::new(key)),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct
+        map struct identifier ignored_any
+    }
+}
+
+/// The path segment of a dict key, for errors
+fn key_segment(key: Cow<'_, str>) -> PathSegment<'static> {
+    PathSegment::Key(Cow::Owned(key.into_owned()))
 }
 
-impl Deserializer {
+impl<'de> Deserializer<'de> {
     /// Create a new deserializer from a HUML value
     pub fn new(value: HumlValue) -> Self {
-        Self { value }
+        Self {
+            value: Content::Owned(value),
+            ctx: Context::default(),
+            spans: None,
+        }
+    }
+
+    /// Create a deserializer that reads `value` in place instead of taking it
+    pub fn from_ref(value: &'de HumlValue) -> Self {
+        Self {
+            value: Content::Borrowed(value),
+            ctx: Context::default(),
+            spans: None,
+        }
+    }
+
+    /// Use `options` instead of the defaults
+    pub fn with_options(mut self, options: DeserializeOptions) -> Self {
+        self.ctx.options = options;
+        self
+    }
+
+    /// The number or boolean in a string value, when
+    /// [`DeserializeOptions::lenient_scalars`] is set
+    fn unquoted(&self) -> Option<Deserializer<'de>> {
+        let HumlValue::String(s) = self.value.get() else {
+            return None;
+        };
+        if !self.ctx.options.lenient_scalars {
+            return None;
+        }
+        match crate::parse_scalar(s.trim()) {
+            Ok(("", value @ (HumlValue::Number(_) | HumlValue::Boolean(_)))) => {
+                Some(Deserializer {
+                    value: Content::Owned(value),
+                    ctx: self.ctx,
+                    spans: None,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Reads the value as an integer of type `T`, called `ty` in errors
+    fn integer<T>(&self, ty: &'static str) -> Result<T>
+    where
+        T: TryFrom<i128> + TryFrom<u128>,
+    {
+        if let Some(unquoted) = self.unquoted() {
+            return unquoted.integer(ty);
+        }
+        let out_of_range = |value: &dyn fmt::Display| Error::OutOfRange {
+            value: value.to_string(),
+            ty,
+        };
+        match *self.value.get() {
+            HumlValue::Number(HumlNumber::Integer(i) | HumlNumber::Prefixed(i, _)) => {
+                T::try_from(i128::from(i)).map_err(|_| out_of_range(&i))
+            }
+            HumlValue::Number(HumlNumber::I128(i)) => T::try_from(i).map_err(|_| out_of_range(&i)),
+            HumlValue::Number(HumlNumber::U128(u)) => T::try_from(u).map_err(|_| out_of_range(&u)),
+            HumlValue::Number(HumlNumber::Float(_)) if self.ctx.options.strict_numbers => {
+                Err(Error::InvalidType("Expected integer, found float"))
+            }
+            // Floats are truncated
+            HumlValue::Number(HumlNumber::Float(f)) => {
+                let truncated = f.trunc();
+                let converted = if truncated >= i128::MIN as f64 && truncated < i128::MAX as f64 {
+                    T::try_from(truncated as i128).ok()
+                } else if truncated >= 0.0 && truncated < u128::MAX as f64 {
+                    T::try_from(truncated as u128).ok()
+                } else {
+                    None
+                };
+                converted.ok_or_else(|| out_of_range(&f))
+            }
+            _ if ty.starts_with('u') => Err(Error::InvalidType("Expected unsigned integer")),
+            _ => Err(Error::InvalidType("Expected integer")),
+        }
+    }
+
+    /// Deserializes the value with `seed`, or a placeholder in its place if
+    /// an earlier attempt of [`from_str_all_errors`] failed on it
+    fn deserialize_seed<S>(self, seed: S) -> Result<S::Value>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        match self.ctx.fix() {
+            Some(_) => seed.deserialize(Placeholder),
+            None => seed.deserialize(self),
+        }
     }
 
     /// Parse individual value types (scalars, lists, inline dicts)
     fn parse_value(input: &str) -> Result<Self> {
         // Check for empty containers first (fastest check)
         if input == "[]" {
-            return Ok(Self::new(HumlValue::List(Vec::new())));
+            return Ok(Self::new(HumlValue::List(HumlList::new())));
         }
         if input == "{}" {
-            return Ok(Self::new(HumlValue::Dict(std::collections::HashMap::new())));
+            return Ok(Self::new(HumlValue::Dict(HumlDict::new())));
         }
 
         // Try scalar parsing (most common case)
@@ -92,45 +879,57 @@ impl Deserializer {
     }
 }
 
-impl FromStr for Deserializer {
+impl FromStr for Deserializer<'_> {
     type Err = Error;
     /// Create a deserializer from HUML text
     fn from_str(input: &str) -> Result<Self> {
         let trimmed = input.trim();
         if trimmed.is_empty() {
-            return Ok(Self::new(HumlValue::String(String::new())));
+            return Ok(Self::new(HumlValue::String(HumlString::default())));
         }
 
         // Fast path: try complete document parsing first (most common case)
-        if let Ok(("", document)) = parse_huml(trimmed) {
-            return Ok(Self::new(document.root));
-        }
-        if let Ok((remaining, document)) = parse_huml(trimmed) {
-            if remaining.trim().is_empty() {
+        let document_error = match parse_huml(trimmed) {
+            Ok(("", document)) => return Ok(Self::new(document.root)),
+            Ok((remaining, document)) if remaining.trim().is_empty() => {
                 return Ok(Self::new(document.root));
             }
-        }
+            Ok(_) => None,
+            Err(err) => Some(err),
+        };
 
         // Fallback: try document root parsing
-        if let Ok(("", root)) = crate::parse_document_root(trimmed) {
+        if let Ok((remaining, root)) = crate::parse_document_root(trimmed)
+            && remaining.trim().is_empty()
+        {
             return Ok(Self::new(root));
         }
-        if let Ok((remaining, root)) = crate::parse_document_root(trimmed) {
-            if remaining.trim().is_empty() {
-                return Ok(Self::new(root));
-            }
-        }
 
         // Last resort: try individual value types
-        Self::parse_value(trimmed)
-            .map_err(|_| Error::ParseError(format!("Unable to parse HUML content: {trimmed}")))
+        Self::parse_value(trimmed).map_err(|_| match document_error {
+            Some(err) => {
+                // Report where the error is in `input` rather than `trimmed`
+                let leading = &input[..input.len() - input.trim_start().len()];
+                let lines = leading.matches('\n').count();
+                let column = match leading.rfind('\n') {
+                    Some(newline) if err.line == 1 => err.column + leading.len() - newline - 1,
+                    None if err.line == 1 => err.column + leading.len(),
+                    _ => err.column,
+                };
+                let err = crate::ParseError::new(err.line + lines, column, err.message);
+                Error::ParseError(err.to_string())
+            }
+            None => Error::ParseError(format!("Unable to parse HUML content: {trimmed}")),
+        })
     }
 }
 
 /// Convenience function to deserialize HUML text into a type
 ///
 /// This is the main entry point for deserializing HUML text into Rust types.
-/// The type must implement `serde::Deserialize`.
+/// The type must implement `serde::Deserialize`. `&str` fields, and
+/// `Cow<str>` fields marked `#[serde(borrow)]`, borrow from `input` when the
+/// string is written without escapes.
 ///
 /// # Example
 ///
@@ -155,25 +954,349 @@ pub fn from_str<'a, T>(input: &'a str) -> Result<T>
 where
     T: Deserialize<'a>,
 {
-    let deserializer = Deserializer::from_str(input)?;
+    from_str_with(input, DeserializeOptions::default())
+}
+
+/// Deserialize HUML text into a type using the given options
+pub fn from_str_with<'a, T>(input: &'a str, options: DeserializeOptions) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let (root, spans) = parse_input(input)?;
+    let deserializer = Deserializer {
+        value: Content::Owned(root),
+        ctx: Context {
+            options,
+            input: Some(input),
+            ..Context::default()
+        },
+        spans,
+    };
     T::deserialize(deserializer)
 }
 
-impl<'de> de::Deserializer<'de> for Deserializer {
-    type Error = Error;
+/// Parses the input of the `from_str` family, with the spans of its values
+/// when it's a valid document
+fn parse_input(input: &str) -> Result<(HumlValue, Option<Spans>)> {
+    match parse_huml_with_spans(input) {
+        Ok((root, nodes)) => Ok((
+            root,
+            Some(Spans {
+                nodes: nodes.into(),
+                node: 0,
+            }),
+        )),
+        // Fall back to the more lenient parsing of `Deserializer::from_str`
+        Err(_) => Ok((Deserializer::from_str(input)?.value.into_owned(), None)),
+    }
+}
 
-    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: Visitor<'de>,
-    {
-        match self.value {
-            HumlValue::String(s) => visitor.visit_string(s),
-            HumlValue::Number(n) => match n {
-                HumlNumber::Integer(i) => visitor.visit_i64(i),
-                HumlNumber::Float(f) => visitor.visit_f64(f),
-                HumlNumber::Nan => visitor.visit_f64(f64::NAN),
-                HumlNumber::Infinity(positive) => {
-                    if positive {
+/// Deserialize the value at `path` in a HUML document into a type, such as
+/// `"server.tls"` or `"replicas[0]"`
+///
+/// Paths are written as for [`HumlValue::get_path`]. The rest of the
+/// document is parsed but not deserialized, so it only has to be valid
+/// HUML. Errors inside the value give the whole path.
+///
+/// # Example
+///
+/// ```rust
+/// use huml_rs::serde::from_str_at;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Tls {
+///     cert: String,
+/// }
+///
+/// let input = "server::\n  port: 443\n  tls::\n    cert: \"a.pem\"\nclients: []\n";
+/// let tls: Tls = from_str_at(input, "server.tls").unwrap();
+/// assert_eq!(tls.cert, "a.pem");
+/// ```
+pub fn from_str_at<'a, T>(input: &'a str, path: &str) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let parsed =
+        Path::parse(path).ok_or_else(|| Error::Message(format!("Invalid path: {path}")))?;
+    let (mut value, mut spans) = parse_input(input)?;
+    for segment in parsed.segments() {
+        let position = match (&value, segment) {
+            (HumlValue::Dict(dict), PathSegment::Key(key)) => {
+                dict.iter().position(|(k, _)| k == key)
+            }
+            (HumlValue::List(items), PathSegment::Index(index)) => {
+                (*index < items.len()).then_some(*index)
+            }
+            _ => None,
+        };
+        let Some(position) = position else {
+            return Err(Error::Message(format!(
+                "Nothing at `{path}` in the document"
+            )));
+        };
+        spans = spans.map(|spans| {
+            let mut child = spans.first_child();
+            for _ in 0..position {
+                child.advance();
+            }
+            child
+        });
+        if let Some(child) = segment.get_mut(&mut value) {
+            value = child.take();
+        }
+    }
+
+    let deserializer = Deserializer {
+        value: Content::Owned(value),
+        ctx: Context {
+            input: Some(input),
+            ..Context::default()
+        },
+        spans,
+    };
+    T::deserialize(deserializer).map_err(|err| {
+        let path = parsed.into_owned();
+        path.segments()
+            .iter()
+            .rev()
+            .fold(err, |err, segment| err.at(segment.clone(), None))
+    })
+}
+
+/// Deserialize HUML text, reporting every problem with it instead of just
+/// the first
+///
+/// Each error names the path to the value it's about. A value of the wrong
+/// type is read as zero, empty or the first variant, and reading carries on.
+/// Errors serde's own visitors raise, such as a missing field or an unknown
+/// variant, can't be read past: the document is read again, leaving out the
+/// failing dict entry, or reading a placeholder in place of a failing list
+/// item or missing field, until no new errors turn up. An error that can't
+/// be worked around, such as a parse error, ends the list, and so does
+/// reaching 100 errors.
+///
+/// # Example
+///
+/// ```rust
+/// use huml_rs::serde::from_str_all_errors;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     host: String,
+///     port: u16,
+///     debug: bool,
+/// }
+///
+/// let errors = from_str_all_errors::<Config>("port: \"80\"\ndebug: 1\n").err().unwrap();
+/// let errors: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+/// assert_eq!(
+///     errors,
+///     [
+///         "Invalid type: Expected unsigned integer at `port` on line 1:7",
+///         "Invalid type: Expected boolean at `debug` on line 2:8",
+///         "Missing field: host",
+///     ]
+/// );
+/// ```
+pub fn from_str_all_errors<T>(input: &str) -> std::result::Result<T, Vec<Error>>
+where
+    T: DeserializeOwned,
+{
+    let (root, spans) = parse_input(input).map_err(|err| vec![err])?;
+
+    let mut recovery = Recovery::default();
+    let mut errors = Vec::new();
+    // Errors read past in one attempt turn up again in the next
+    let mut seen = HashSet::new();
+    loop {
+        let recovered = RefCell::new(Vec::new());
+        let deserializer = Deserializer {
+            value: Content::Borrowed(&root),
+            ctx: Context {
+                input: Some(input),
+                recovery: Some(&recovery),
+                errors: Some(&recovered),
+                ..Context::default()
+            },
+            spans: spans.clone(),
+        };
+        let result = T::deserialize(deserializer);
+        for err in recovered.into_inner() {
+            if seen.insert(err.to_string()) {
+                errors.push(err);
+            }
+        }
+        let err = match result {
+            Ok(value) if errors.is_empty() => return Ok(value),
+            Ok(_) => return Err(errors),
+            Err(err) => err,
+        };
+        if errors.len() >= MAX_ERRORS {
+            errors.truncate(MAX_ERRORS);
+            return Err(errors);
+        }
+        match recovery.mark(&err) {
+            Some(true) => errors.push(err),
+            Some(false) => {}
+            None => {
+                // Errors from placeholders only repeat ones already reported
+                if errors.is_empty() {
+                    errors.push(err);
+                }
+                return Err(errors);
+            }
+        }
+    }
+}
+
+/// Deserialize HUML read from `reader`, such as a file or socket
+///
+/// The whole input is read before parsing, since a HUML document can't be
+/// parsed in pieces. Wrapping `reader` in a `BufReader` isn't needed. Failing
+/// reads are reported as [`Error::Io`], and input that isn't UTF-8 as
+/// [`Error::InvalidUtf8`].
+///
+/// # Example
+///
+/// ```rust
+/// use huml_rs::serde::from_reader;
+/// use std::collections::HashMap;
+///
+/// let input: &[u8] = b"port: 8080\n";
+/// let config: HashMap<String, u16> = from_reader(input).unwrap();
+/// assert_eq!(config["port"], 8080);
+/// ```
+pub fn from_reader<R, T>(mut reader: R) -> Result<T>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    let mut input = Vec::new();
+    reader.read_to_end(&mut input)?;
+    from_slice(&input)
+}
+
+/// Deserialize HUML from bytes, such as a network buffer
+///
+/// The bytes must be UTF-8; otherwise [`Error::InvalidUtf8`] gives the offset
+/// of the first bad byte. Strings can borrow from `input` as with
+/// [`from_str`].
+///
+/// # Example
+///
+/// ```rust
+/// use huml_rs::serde::{from_slice, DeError};
+///
+/// let port: u16 = from_slice(b"8080").unwrap();
+/// assert_eq!(port, 8080);
+/// assert!(matches!(
+///     from_slice::<String>(b"\"caf\xe9\""),
+///     Err(DeError::InvalidUtf8(4))
+/// ));
+/// ```
+pub fn from_slice<'a, T>(input: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let input = std::str::from_utf8(input).map_err(|err| Error::InvalidUtf8(err.valid_up_to()))?;
+    from_str(input)
+}
+
+/// Deserialize a type from an already parsed HUML value
+pub fn from_value<T>(value: HumlValue) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(Deserializer::new(value))
+}
+
+/// Deserialize a type from a borrowed HUML value
+///
+/// The value is not cloned, so one parsed document can be read into several
+/// types, and `&str` fields can borrow from it.
+///
+/// # Example
+///
+/// ```rust
+/// use serde::Deserialize;
+/// use huml_rs::serde::from_value_ref;
+///
+/// #[derive(Deserialize)]
+/// struct Name<'a> {
+///     name: &'a str,
+/// }
+///
+/// let (_, doc) = huml_rs::parse_huml("name: \"Alice\"\nage: 30\n").unwrap();
+/// let person: Name = from_value_ref(&doc.root).unwrap();
+/// assert_eq!(person.name, "Alice");
+/// ```
+pub fn from_value_ref<'de, T>(value: &'de HumlValue) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(Deserializer::from_ref(value))
+}
+
+/// Reads an `Option<Option<T>>` field so that `null` and a missing key
+/// differ, for PATCH-style merging where `null` means "unset"
+///
+/// Use with `#[serde(default, deserialize_with = "...")]`: a missing key is
+/// `None` through `default`, `null` is `Some(None)` and a value is
+/// `Some(Some(value))`. Without it, serde reads both `null` and a missing key
+/// as `None`. Serializing `Some(None)` writes `null`, so pair it with
+/// `skip_serializing_if = "Option::is_none"` to round trip.
+///
+/// # Example
+///
+/// ```rust
+/// use huml_rs::serde::from_str;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Patch {
+///     #[serde(default, deserialize_with = "huml_rs::serde::double_option")]
+///     timeout: Option<Option<u32>>,
+/// }
+///
+/// assert_eq!(from_str::<Patch>("timeout: 5").unwrap().timeout, Some(Some(5)));
+/// assert_eq!(from_str::<Patch>("timeout: null").unwrap().timeout, Some(None));
+/// assert_eq!(from_str::<Patch>("{}").unwrap().timeout, None);
+/// ```
+pub fn double_option<'de, T, D>(deserializer: D) -> std::result::Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: de::Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.value.get() {
+            HumlValue::String(_) => self.deserialize_str(visitor),
+            HumlValue::Number(ref n) => match *n {
+                // Non-negative integers are given as `u64` like serde_json does,
+                // so untagged enums and `serde_json::Value` see the same number
+                HumlNumber::Integer(i) | HumlNumber::Prefixed(i, _) => match u64::try_from(i) {
+                    Ok(u) => visitor.visit_u64(u),
+                    Err(_) => visitor.visit_i64(i),
+                },
+                HumlNumber::I128(i) => match u64::try_from(i) {
+                    Ok(u) => visitor.visit_u64(u),
+                    Err(_) => visitor.visit_i128(i),
+                },
+                HumlNumber::U128(u) => visitor.visit_u128(u),
+                HumlNumber::Float(f) => visitor.visit_f64(f),
+                HumlNumber::Nan => visitor.visit_f64(f64::NAN),
+                HumlNumber::Infinity(positive) => {
+                    if positive {
                         visitor.visit_f64(f64::INFINITY)
                     } else {
                         visitor.visit_f64(f64::NEG_INFINITY)
@@ -182,14 +1305,8 @@ impl<'de> de::Deserializer<'de> for Deserializer {
             },
             HumlValue::Boolean(b) => visitor.visit_bool(b),
             HumlValue::Null => visitor.visit_unit(),
-            HumlValue::List(list) => {
-                let seq = SeqDeserializer::new(list);
-                visitor.visit_seq(seq)
-            }
-            HumlValue::Dict(dict) => {
-                let map = MapDeserializer::new(dict);
-                visitor.visit_map(map)
-            }
+            HumlValue::List(_) => self.deserialize_seq(visitor),
+            HumlValue::Dict(_) => self.deserialize_map(visitor),
         }
     }
 
@@ -197,9 +1314,16 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     where
         V: Visitor<'de>,
     {
-        match self.value {
+        if let Some(unquoted) = self.unquoted() {
+            return unquoted.deserialize_bool(visitor);
+        }
+        match *self.value.get() {
             HumlValue::Boolean(b) => visitor.visit_bool(b),
-            _ => Err(Error::InvalidType("Expected boolean")),
+            _ => self
+                .ctx
+                .recover(Error::InvalidType("Expected boolean"), || {
+                    Placeholder.deserialize_bool(visitor)
+                }),
         }
     }
 
@@ -207,31 +1331,59 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_i64(visitor)
+        match self.integer("i8") {
+            Ok(value) => visitor.visit_i8(value),
+            Err(err) => self
+                .ctx
+                .recover(err, || Placeholder.deserialize_i8(visitor)),
+        }
     }
 
     fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_i64(visitor)
+        match self.integer("i16") {
+            Ok(value) => visitor.visit_i16(value),
+            Err(err) => self
+                .ctx
+                .recover(err, || Placeholder.deserialize_i16(visitor)),
+        }
     }
 
     fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_i64(visitor)
+        match self.integer("i32") {
+            Ok(value) => visitor.visit_i32(value),
+            Err(err) => self
+                .ctx
+                .recover(err, || Placeholder.deserialize_i32(visitor)),
+        }
     }
 
     fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        match self.value {
-            HumlValue::Number(HumlNumber::Integer(i)) => visitor.visit_i64(i),
-            HumlValue::Number(HumlNumber::Float(f)) => visitor.visit_i64(f as i64),
-            _ => Err(Error::InvalidType("Expected integer")),
+        match self.integer("i64") {
+            Ok(value) => visitor.visit_i64(value),
+            Err(err) => self
+                .ctx
+                .recover(err, || Placeholder.deserialize_i64(visitor)),
+        }
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.integer("i128") {
+            Ok(value) => visitor.visit_i128(value),
+            Err(err) => self
+                .ctx
+                .recover(err, || Placeholder.deserialize_i128(visitor)),
         }
     }
 
@@ -239,43 +1391,59 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_u64(visitor)
+        match self.integer("u8") {
+            Ok(value) => visitor.visit_u8(value),
+            Err(err) => self
+                .ctx
+                .recover(err, || Placeholder.deserialize_u8(visitor)),
+        }
     }
 
     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_u64(visitor)
+        match self.integer("u16") {
+            Ok(value) => visitor.visit_u16(value),
+            Err(err) => self
+                .ctx
+                .recover(err, || Placeholder.deserialize_u16(visitor)),
+        }
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_u64(visitor)
+        match self.integer("u32") {
+            Ok(value) => visitor.visit_u32(value),
+            Err(err) => self
+                .ctx
+                .recover(err, || Placeholder.deserialize_u32(visitor)),
+        }
     }
 
     fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        match self.value {
-            HumlValue::Number(HumlNumber::Integer(i)) => {
-                if i >= 0 {
-                    visitor.visit_u64(i as u64)
-                } else {
-                    Err(Error::InvalidType("Expected positive integer"))
-                }
-            }
-            HumlValue::Number(HumlNumber::Float(f)) => {
-                if f >= 0.0 {
-                    visitor.visit_u64(f as u64)
-                } else {
-                    Err(Error::InvalidType("Expected positive number"))
-                }
-            }
-            _ => Err(Error::InvalidType("Expected unsigned integer")),
+        match self.integer("u64") {
+            Ok(value) => visitor.visit_u64(value),
+            Err(err) => self
+                .ctx
+                .recover(err, || Placeholder.deserialize_u64(visitor)),
+        }
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.integer("u128") {
+            Ok(value) => visitor.visit_u128(value),
+            Err(err) => self
+                .ctx
+                .recover(err, || Placeholder.deserialize_u128(visitor)),
         }
     }
 
@@ -290,9 +1458,26 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     where
         V: Visitor<'de>,
     {
-        match self.value {
+        if let Some(unquoted) = self.unquoted() {
+            return unquoted.deserialize_f64(visitor);
+        }
+        match *self.value.get() {
             HumlValue::Number(HumlNumber::Float(f)) => visitor.visit_f64(f),
-            HumlValue::Number(HumlNumber::Integer(i)) => visitor.visit_f64(i as f64),
+            HumlValue::Number(
+                HumlNumber::Integer(_)
+                | HumlNumber::Prefixed(..)
+                | HumlNumber::I128(_)
+                | HumlNumber::U128(_),
+            ) if self.ctx.options.strict_numbers => self
+                .ctx
+                .recover(Error::InvalidType("Expected float, found integer"), || {
+                    Placeholder.deserialize_f64(visitor)
+                }),
+            HumlValue::Number(HumlNumber::Integer(i) | HumlNumber::Prefixed(i, _)) => {
+                visitor.visit_f64(i as f64)
+            }
+            HumlValue::Number(HumlNumber::I128(i)) => visitor.visit_f64(i as f64),
+            HumlValue::Number(HumlNumber::U128(u)) => visitor.visit_f64(u as f64),
             HumlValue::Number(HumlNumber::Nan) => visitor.visit_f64(f64::NAN),
             HumlValue::Number(HumlNumber::Infinity(positive)) => {
                 if positive {
@@ -301,7 +1486,9 @@ impl<'de> de::Deserializer<'de> for Deserializer {
                     visitor.visit_f64(f64::NEG_INFINITY)
                 }
             }
-            _ => Err(Error::InvalidType("Expected float")),
+            _ => self.ctx.recover(Error::InvalidType("Expected float"), || {
+                Placeholder.deserialize_f64(visitor)
+            }),
         }
     }
 
@@ -309,16 +1496,18 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     where
         V: Visitor<'de>,
     {
-        match self.value {
+        let err = match self.value.get() {
             HumlValue::String(s) => {
                 let mut chars = s.chars();
                 match (chars.next(), chars.next()) {
-                    (Some(c), None) => visitor.visit_char(c),
-                    _ => Err(Error::InvalidType("Expected single character")),
+                    (Some(c), None) => return visitor.visit_char(c),
+                    _ => Error::InvalidType("Expected single character"),
                 }
             }
-            _ => Err(Error::InvalidType("Expected string")),
-        }
+            _ => Error::InvalidType("Expected string"),
+        };
+        self.ctx
+            .recover(err, || Placeholder.deserialize_char(visitor))
     }
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
@@ -326,8 +1515,17 @@ impl<'de> de::Deserializer<'de> for Deserializer {
         V: Visitor<'de>,
     {
         match self.value {
-            HumlValue::String(s) => visitor.visit_string(s),
-            _ => Err(Error::InvalidType("Expected string")),
+            Content::Owned(HumlValue::String(s)) => match self
+                .spans
+                .and_then(|spans| self.ctx.borrow(&s, spans.span()))
+            {
+                Some(borrowed) => visitor.visit_borrowed_str(borrowed),
+                None => visitor.visit_string(s.into_string()),
+            },
+            Content::Borrowed(HumlValue::String(s)) => visitor.visit_borrowed_str(s),
+            _ => self.ctx.recover(Error::InvalidType("Expected string"), || {
+                Placeholder.deserialize_str(visitor)
+            }),
         }
     }
 
@@ -335,16 +1533,47 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_str(visitor)
+        match self.value {
+            Content::Owned(HumlValue::String(s)) => visitor.visit_string(s.into_string()),
+            value => Deserializer { value, ..self }.deserialize_str(visitor),
+        }
     }
 
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        match self.value {
-            HumlValue::String(s) => visitor.visit_byte_buf(s.into_bytes()),
-            _ => Err(Error::InvalidType("Expected string")),
+        let encoding = self.ctx.options.bytes_encoding;
+        if encoding == BytesEncoding::Raw {
+            match self.value {
+                Content::Owned(HumlValue::String(s)) => {
+                    return visitor.visit_byte_buf(s.into_string().into_bytes());
+                }
+                Content::Borrowed(HumlValue::String(s)) => {
+                    return visitor.visit_borrowed_bytes(s.as_bytes());
+                }
+                _ => {}
+            }
+        }
+        match self.value.get() {
+            HumlValue::String(s) => match encoding.decode(s) {
+                Some(bytes) => visitor.visit_byte_buf(bytes),
+                None => {
+                    let expected = match encoding {
+                        BytesEncoding::Base64 => "Expected base64 string",
+                        BytesEncoding::Hex => "Expected hex string",
+                        BytesEncoding::Raw => "Expected string",
+                    };
+                    self.ctx.recover(Error::InvalidType(expected), || {
+                        Placeholder.deserialize_bytes(visitor)
+                    })
+                }
+            },
+            // A list of byte values
+            HumlValue::List(_) => self.deserialize_seq(visitor),
+            _ => self.ctx.recover(Error::InvalidType("Expected string"), || {
+                Placeholder.deserialize_bytes(visitor)
+            }),
         }
     }
 
@@ -359,7 +1588,7 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     where
         V: Visitor<'de>,
     {
-        match self.value {
+        match self.value.get() {
             HumlValue::Null => visitor.visit_none(),
             _ => visitor.visit_some(self),
         }
@@ -369,9 +1598,15 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     where
         V: Visitor<'de>,
     {
-        match self.value {
+        // Marker sections are often written as `{}`
+        match self.value.get() {
             HumlValue::Null => visitor.visit_unit(),
-            _ => Err(Error::InvalidType("Expected null")),
+            HumlValue::Dict(dict) if dict.is_empty() => visitor.visit_unit(),
+            _ => self
+                .ctx
+                .recover(Error::InvalidType("Expected null or empty dict"), || {
+                    Placeholder.deserialize_unit(visitor)
+                }),
         }
     }
 
@@ -393,81 +1628,135 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     where
         V: Visitor<'de>,
     {
-        match self.value {
-            HumlValue::List(list) => {
-                let seq = SeqDeserializer::new(list);
-                visitor.visit_seq(seq)
-            }
-            _ => Err(Error::InvalidType("Expected list")),
+        match self.value.into_seq(self.ctx, self.spans) {
+            Some(seq) => visitor.visit_seq(seq),
+            None => self.ctx.recover(Error::InvalidType("Expected list"), || {
+                Placeholder.deserialize_seq(visitor)
+            }),
         }
     }
 
-    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        match self.value.into_seq(self.ctx, self.spans) {
+            Some(seq) => seq.tuple(len, visitor),
+            None => self.ctx.recover(Error::InvalidType("Expected list"), || {
+                Placeholder.deserialize_tuple(len, visitor)
+            }),
+        }
     }
 
     fn deserialize_tuple_struct<V>(
         self,
         _name: &'static str,
-        _len: usize,
+        len: usize,
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        self.deserialize_tuple(len, visitor)
     }
-
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        match self.value {
-            HumlValue::Dict(dict) => {
-                let map = MapDeserializer::new(dict);
-                visitor.visit_map(map)
-            }
-            _ => Err(Error::InvalidType("Expected dict")),
+        match self.value.into_map(self.ctx, self.spans) {
+            Some(map) => visitor.visit_map(map),
+            None => self.ctx.recover(Error::InvalidType("Expected dict"), || {
+                Placeholder.deserialize_map(visitor)
+            }),
         }
     }
 
     fn deserialize_struct<V>(
         self,
-        _name: &'static str,
-        _fields: &'static [&'static str],
+        name: &'static str,
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_map(visitor)
+        if name == spanned::NAME {
+            return match self.spans.clone() {
+                Some(spans) => visitor.visit_map(SpannedDeserializer {
+                    span: spans.span(),
+                    value: Some(self),
+                    field: 0,
+                }),
+                None => Err(Error::Message(
+                    "Spanned values can only be read from text, e.g. with from_str".to_string(),
+                )),
+            };
+        }
+        match self.value.into_map(self.ctx, self.spans) {
+            Some(map) => visitor.visit_map(MapDeserializer { fields, ..map }),
+            None => self.ctx.recover(Error::InvalidType("Expected dict"), || {
+                Placeholder.deserialize_struct(name, fields, visitor)
+            }),
+        }
     }
 
     fn deserialize_enum<V>(
         self,
-        _name: &'static str,
-        _variants: &'static [&'static str],
+        name: &'static str,
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
         match self.value {
-            HumlValue::String(s) => {
-                visitor.visit_enum(serde::de::value::StringDeserializer::<Error>::new(s))
+            Content::Owned(HumlValue::String(s)) => {
+                visitor.visit_enum(StringDeserializer::<Error>::new(s.into_string()))
             }
-            HumlValue::Dict(dict) => {
-                if dict.len() == 1 {
-                    let (key, value) = dict.into_iter().next().unwrap();
-                    visitor.visit_enum(EnumDeserializer::new(key, value))
-                } else {
-                    Err(Error::InvalidType("Expected single-key dict for enum"))
+            Content::Borrowed(HumlValue::String(s)) => {
+                visitor.visit_enum(BorrowedStrDeserializer::<Error>::new(s))
+            }
+            // A unit variant by its zero-based position, which is only its
+            // discriminant when the variants are numbered from zero
+            _ if matches!(
+                self.value.get(),
+                HumlValue::Number(
+                    HumlNumber::Integer(_)
+                        | HumlNumber::Prefixed(..)
+                        | HumlNumber::I128(_)
+                        | HumlNumber::U128(_)
+                )
+            ) =>
+            {
+                match self.integer("variant index") {
+                    Ok(index) => visitor.visit_enum(U32Deserializer::<Error>::new(index)),
+                    Err(err) => self.ctx.recover(err, || {
+                        Placeholder.deserialize_enum(name, variants, visitor)
+                    }),
                 }
             }
-            _ => Err(Error::InvalidType("Expected string or dict for enum")),
+            value => match value.into_map(self.ctx, self.spans) {
+                Some(mut map) => {
+                    if map.len == 1 {
+                        let (variant, value) = map.iter.next().unwrap();
+                        visitor.visit_enum(EnumDeserializer {
+                            variant,
+                            value,
+                            ctx: self.ctx,
+                            spans: map.spans,
+                        })
+                    } else {
+                        self.ctx.recover(
+                            Error::InvalidType("Expected single-key dict for enum"),
+                            || Placeholder.deserialize_enum(name, variants, visitor),
+                        )
+                    }
+                }
+                None => self.ctx.recover(
+                    Error::InvalidType("Expected string or dict for enum"),
+                    || Placeholder.deserialize_enum(name, variants, visitor),
+                ),
+            },
         }
     }
 
@@ -475,7 +1764,9 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_str(visitor)
+        // Field names are compared, never kept, so don't look for them in
+        // the input
+        self.deserialize_string(visitor)
     }
 
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
@@ -487,22 +1778,52 @@ impl<'de> de::Deserializer<'de> for Deserializer {
 }
 
 /// Sequence deserializer for HUML lists
-struct SeqDeserializer {
-    iter: std::vec::IntoIter<HumlValue>,
+struct SeqDeserializer<'de> {
+    iter: SeqIter<'de>,
     len: usize,
+    /// The index of the next item
+    index: usize,
+    ctx: Context<'de>,
+    /// The spans of the next item
+    spans: Option<Spans>,
 }
 
-impl SeqDeserializer {
-    fn new(list: Vec<HumlValue>) -> Self {
-        let len = list.len();
-        Self {
-            iter: list.into_iter(),
-            len,
+impl<'de> SeqDeserializer<'de> {
+    /// Visits the list as a tuple of `len` items
+    fn tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.len != len {
+            let err = Error::InvalidLength {
+                expected: len,
+                found: self.len,
+            };
+            return self
+                .ctx
+                .recover(err, || visitor.visit_seq(PlaceholderSeq(len)));
+        }
+        visitor.visit_seq(self)
+    }
+}
+
+enum SeqIter<'de> {
+    Owned(std::vec::IntoIter<HumlValue>),
+    Borrowed(std::slice::Iter<'de, HumlValue>),
+}
+
+impl<'de> Iterator for SeqIter<'de> {
+    type Item = Content<'de>;
+
+    fn next(&mut self) -> Option<Content<'de>> {
+        match self {
+            SeqIter::Owned(iter) => iter.next().map(Content::Owned),
+            SeqIter::Borrowed(iter) => iter.next().map(Content::Borrowed),
         }
     }
 }
 
-impl<'de> de::SeqAccess<'de> for SeqDeserializer {
+impl<'de> de::SeqAccess<'de> for SeqDeserializer<'de> {
     type Error = Error;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
@@ -511,8 +1832,16 @@ impl<'de> de::SeqAccess<'de> for SeqDeserializer {
     {
         match self.iter.next() {
             Some(value) => {
-                let deserializer = Deserializer::new(value);
-                seed.deserialize(deserializer).map(Some)
+                let index = self.index;
+                self.index += 1;
+                let ctx = self.ctx.index(index);
+                let spans = self.spans.as_mut().map(Spans::advance);
+                let start = spans.as_ref().map(Spans::start);
+                let deserializer = Deserializer { value, ctx, spans };
+                ctx.within(PathSegment::Index(index), start, || {
+                    deserializer.deserialize_seed(seed)
+                })
+                .map(Some)
             }
             None => Ok(None),
         }
@@ -524,35 +1853,123 @@ impl<'de> de::SeqAccess<'de> for SeqDeserializer {
 }
 
 /// Map deserializer for HUML dicts
-struct MapDeserializer {
-    iter: std::collections::hash_map::IntoIter<String, HumlValue>,
-    value: Option<HumlValue>,
+struct MapDeserializer<'de> {
+    iter: MapIter<'de>,
+    value: Option<Entry<'de>>,
     len: usize,
+    ctx: Context<'de>,
+    /// The spans of the next value
+    spans: Option<Spans>,
+    /// Fields the dict lacks that [`from_str_all_errors`] fills in with
+    /// placeholders
+    missing: Vec<&'de str>,
+    /// The fields, when the dict is read as a struct
+    fields: &'static [&'static str],
 }
 
-impl MapDeserializer {
-    fn new(dict: std::collections::HashMap<String, HumlValue>) -> Self {
-        let len = dict.len();
-        Self {
-            iter: dict.into_iter(),
-            value: None,
-            len,
+impl MapDeserializer<'_> {
+    /// The struct field `key` stands for, when it only matches one with the
+    /// options applied
+    fn field(&self, key: &str) -> Option<&'static str> {
+        let options = self.ctx.options;
+        if !options.case_insensitive_keys && options.key_case.is_none()
+            || self.fields.contains(&key)
+        {
+            return None;
+        }
+        let lowercase = |s: &str| s.chars().flat_map(char::to_lowercase).collect::<Vec<_>>();
+        self.fields.iter().copied().find(|field| {
+            let field = match options.key_case {
+                Some(case) => case.apply(field),
+                None => Cow::Borrowed(*field),
+            };
+            if options.case_insensitive_keys {
+                lowercase(&field) == lowercase(key)
+            } else {
+                field == key
+            }
+        })
+    }
+}
+
+/// The value of the dict entry whose key was just read
+struct Entry<'de> {
+    key: Cow<'de, str>,
+    value: Content<'de>,
+    ctx: Context<'de>,
+    spans: Option<Spans>,
+}
+
+enum MapIter<'de> {
+    Owned(dict::IntoIter),
+    Borrowed(dict::Iter<'de>),
+}
+
+impl<'de> Iterator for MapIter<'de> {
+    type Item = (Key<'de>, Content<'de>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            MapIter::Owned(iter) => iter
+                .next()
+                .map(|(key, value)| (Key::Owned(key), Content::Owned(value))),
+            MapIter::Borrowed(iter) => iter
+                .next()
+                .map(|(key, value)| (Key::Borrowed(key), Content::Borrowed(value))),
         }
     }
 }
 
-impl<'de> de::MapAccess<'de> for MapDeserializer {
+impl<'de> de::MapAccess<'de> for MapDeserializer<'de> {
     type Error = Error;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
     where
         K: DeserializeSeed<'de>,
     {
-        match self.iter.next() {
-            Some((key, value)) => {
-                self.value = Some(value);
-                let key_deserializer = Deserializer::new(HumlValue::String(key));
-                seed.deserialize(key_deserializer).map(Some)
+        for (key, value) in self.iter.by_ref() {
+            let spans = self.spans.as_mut().map(Spans::advance);
+            let name = key.name();
+            let ctx = self.ctx.key(&name);
+            if ctx.fix() == Some(Fix::Skip) {
+                continue;
+            }
+            if self.ctx.options.null_as_default
+                && !self.fields.is_empty()
+                && matches!(value.get(), HumlValue::Null)
+            {
+                continue;
+            }
+            let key_start = spans.as_ref().and_then(Spans::key_start);
+            let result = match self.field(&name) {
+                Some(field) => seed.deserialize(BorrowedStrDeserializer::new(field)),
+                None => key.deserialize(seed, self.ctx, key_start),
+            };
+            let result = result.map_err(|mut err| {
+                let position = self.ctx.position(key_start);
+                if let Error::UnexpectedKey { position: at, .. } = &mut err {
+                    *at = position;
+                }
+                err.at(key_segment(name.clone()), position)
+            });
+            self.value = Some(Entry {
+                key: name,
+                value,
+                ctx,
+                spans,
+            });
+            return result.map(Some);
+        }
+        match self.missing.pop() {
+            Some(key) => {
+                self.value = Some(Entry {
+                    key: Cow::Borrowed(key),
+                    value: Content::Owned(HumlValue::Null),
+                    ctx: self.ctx.key(key),
+                    spans: None,
+                });
+                seed.deserialize(BorrowedStrDeserializer::new(key))
+                    .map(Some)
             }
             None => Ok(None),
         }
@@ -563,9 +1980,17 @@ impl<'de> de::MapAccess<'de> for MapDeserializer {
         V: DeserializeSeed<'de>,
     {
         match self.value.take() {
-            Some(value) => {
-                let deserializer = Deserializer::new(value);
-                seed.deserialize(deserializer)
+            Some(Entry {
+                key,
+                value,
+                ctx,
+                spans,
+            }) => {
+                let start = spans.as_ref().map(Spans::start);
+                let deserializer = Deserializer { value, ctx, spans };
+                ctx.within(key_segment(key), start, || {
+                    deserializer.deserialize_seed(seed)
+                })
             }
             None => Err(Error::InvalidType("Value is missing")),
         }
@@ -577,49 +2002,62 @@ impl<'de> de::MapAccess<'de> for MapDeserializer {
 }
 
 /// Enum deserializer for HUML enums
-struct EnumDeserializer {
-    variant: String,
-    value: HumlValue,
-}
-
-impl EnumDeserializer {
-    fn new(variant: String, value: HumlValue) -> Self {
-        Self { variant, value }
-    }
+struct EnumDeserializer<'de> {
+    variant: Key<'de>,
+    value: Content<'de>,
+    ctx: Context<'de>,
+    spans: Option<Spans>,
 }
 
-impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+impl<'de> de::EnumAccess<'de> for EnumDeserializer<'de> {
     type Error = Error;
-    type Variant = VariantDeserializer;
+    type Variant = VariantDeserializer<'de>;
 
     fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
     where
         V: DeserializeSeed<'de>,
     {
-        let variant_deserializer = Deserializer::new(HumlValue::String(self.variant));
-        let variant = seed.deserialize(variant_deserializer)?;
-        Ok((variant, VariantDeserializer::new(self.value)))
+        let name = self.variant.name();
+        let start = self.spans.as_ref().and_then(Spans::key_start);
+        let variant = self.variant.deserialize(seed, self.ctx, start)?;
+        Ok((
+            variant,
+            VariantDeserializer {
+                value: self.value,
+                ctx: self.ctx.key(&name),
+                spans: self.spans,
+                name,
+            },
+        ))
     }
 }
 
 /// Variant deserializer for HUML enum variants
-struct VariantDeserializer {
-    value: HumlValue,
-}
-
-impl VariantDeserializer {
-    fn new(value: HumlValue) -> Self {
-        Self { value }
-    }
+struct VariantDeserializer<'de> {
+    value: Content<'de>,
+    ctx: Context<'de>,
+    spans: Option<Spans>,
+    /// The variant name, for errors
+    name: Cow<'de, str>,
 }
 
-impl<'de> de::VariantAccess<'de> for VariantDeserializer {
+impl<'de> de::VariantAccess<'de> for VariantDeserializer<'de> {
     type Error = Error;
 
     fn unit_variant(self) -> Result<()> {
-        match self.value {
+        // `Off: null`, `Off: {}` and `Off: []` all name the variant alone
+        match self.value.get() {
             HumlValue::Null => Ok(()),
-            _ => Err(Error::InvalidType("Expected null for unit variant")),
+            HumlValue::Dict(dict) if dict.is_empty() => Ok(()),
+            HumlValue::List(list) if list.is_empty() => Ok(()),
+            _ if self.ctx.fix().is_some() => Ok(()),
+            _ => self.ctx.recover(
+                Error::InvalidType("Expected null or empty value for unit variant").at(
+                    key_segment(self.name),
+                    self.ctx.position(self.spans.as_ref().map(Spans::start)),
+                ),
+                || Ok(()),
+            ),
         }
     }
 
@@ -627,58 +2065,435 @@ impl<'de> de::VariantAccess<'de> for VariantDeserializer {
     where
         T: DeserializeSeed<'de>,
     {
-        let deserializer = Deserializer::new(self.value);
-        seed.deserialize(deserializer)
+        let start = self.spans.as_ref().map(Spans::start);
+        let deserializer = Deserializer {
+            value: self.value,
+            ctx: self.ctx,
+            spans: self.spans,
+        };
+        self.ctx.within(key_segment(self.name), start, || {
+            deserializer.deserialize_seed(seed)
+        })
     }
 
-    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        match self.value {
-            HumlValue::List(list) => {
-                let seq = SeqDeserializer::new(list);
-                visitor.visit_seq(seq)
-            }
-            _ => Err(Error::InvalidType("Expected list for tuple variant")),
+        if self.ctx.fix().is_some() {
+            return Placeholder.tuple_variant(len, visitor);
         }
+        let ctx = self.ctx;
+        let start = self.spans.as_ref().map(Spans::start);
+        ctx.within(key_segment(self.name), start, || {
+            match self.value.into_seq(ctx, self.spans) {
+                Some(seq) => seq.tuple(len, visitor),
+                None => ctx.recover(
+                    Error::InvalidType("Expected list for tuple variant"),
+                    || Placeholder.tuple_variant(len, visitor),
+                ),
+            }
+        })
     }
 
-    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        match self.value {
-            HumlValue::Dict(dict) => {
-                let map = MapDeserializer::new(dict);
-                visitor.visit_map(map)
-            }
-            _ => Err(Error::InvalidType("Expected dict for struct variant")),
+        if self.ctx.fix().is_some() {
+            return Placeholder.struct_variant(fields, visitor);
         }
+        let ctx = self.ctx;
+        let start = self.spans.as_ref().map(Spans::start);
+        ctx.within(key_segment(self.name), start, || {
+            match self.value.into_map(ctx, self.spans) {
+                Some(map) => visitor.visit_map(MapDeserializer { fields, ..map }),
+                None => ctx.recover(
+                    Error::InvalidType("Expected dict for struct variant"),
+                    || Placeholder.struct_variant(fields, visitor),
+                ),
+            }
+        })
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde::Deserialize;
-    use std::collections::HashMap;
+/// Hands a [`Spanned`](super::Spanned) value its span and the value itself
+struct SpannedDeserializer<'de> {
+    span: Range<usize>,
+    value: Option<Deserializer<'de>>,
+    /// Index into [`spanned::FIELDS`] of the next field
+    field: usize,
+}
 
-    #[derive(Debug, Deserialize, PartialEq)]
-    struct Person {
-        name: String,
-        age: u32,
-        active: bool,
-    }
+impl<'de> de::MapAccess<'de> for SpannedDeserializer<'de> {
+    type Error = Error;
 
-    #[derive(Debug, Deserialize, PartialEq)]
-    struct PersonWithOptional {
-        name: String,
-        age: Option<u32>,
-        email: Option<String>,
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match spanned::FIELDS.get(self.field) {
+            Some(field) => seed
+                .deserialize(BorrowedStrDeserializer::new(field))
+                .map(Some),
+            None => Ok(None),
+        }
     }
 
-    #[derive(Debug, Deserialize, PartialEq)]
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.field += 1;
+        match self.field {
+            1 => seed.deserialize(self.span.start.into_deserializer()),
+            2 => seed.deserialize(self.span.end.into_deserializer()),
+            _ => match self.value.take() {
+                Some(value) => seed.deserialize(value),
+                None => Err(Error::InvalidType("Value is missing")),
+            },
+        }
+    }
+}
+
+/// Stands in for a value [`from_str_all_errors`] couldn't read, with
+/// zero, empty or the first variant for whatever type is asked for
+struct Placeholder;
+
+impl<'de> de::Deserializer<'de> for Placeholder {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_bool(false)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(0)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u64(0)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_f64(0.0)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_char('\0')
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_str("")
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_bytes(&[])
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_none()
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(0, visitor)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(PlaceholderSeq(len))
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(PlaceholderFields(&[]))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(PlaceholderFields(fields))
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match variants.first() {
+            Some(variant) => visitor.visit_enum(PlaceholderVariant(variant)),
+            None => Err(Error::InvalidType("Enum has no variants")),
+        }
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i8(0)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i16(0)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i32(0)
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i128(0)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u8(0)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u16(0)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u32(0)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u128(0)
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_f32(0.0)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        unit unit_struct ignored_any
+    }
+}
+
+/// Items for a placeholder list or tuple
+struct PlaceholderSeq(usize);
+
+impl<'de> de::SeqAccess<'de> for PlaceholderSeq {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.0 == 0 {
+            return Ok(None);
+        }
+        self.0 -= 1;
+        seed.deserialize(Placeholder).map(Some)
+    }
+}
+
+/// Fields for a placeholder struct
+struct PlaceholderFields(&'static [&'static str]);
+
+impl<'de> de::MapAccess<'de> for PlaceholderFields {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.0.split_first() {
+            Some((field, rest)) => {
+                self.0 = rest;
+                seed.deserialize(BorrowedStrDeserializer::new(field))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(Placeholder)
+    }
+}
+
+/// The variant of a placeholder enum
+struct PlaceholderVariant(&'static str);
+
+impl<'de> de::EnumAccess<'de> for PlaceholderVariant {
+    type Error = Error;
+    type Variant = Placeholder;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Placeholder)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(BorrowedStrDeserializer::<Error>::new(self.0))?;
+        Ok((variant, Placeholder))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Placeholder {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(Placeholder)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(PlaceholderSeq(len))
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(PlaceholderFields(fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Person {
+        name: String,
+        age: u32,
+        active: bool,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct PersonWithOptional {
+        name: String,
+        age: Option<u32>,
+        email: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
     struct PersonWithList {
         name: String,
         hobbies: Vec<String>,
@@ -781,33 +2596,312 @@ metadata:: role: "admin", department: "engineering"
     }
 
     #[test]
-    fn test_deserialize_enum_unit_variant() {
-        let huml = r#""Active""#;
-        let status: Status = from_str(huml).unwrap();
-        assert_eq!(status, Status::Active);
+    fn test_deserialize_enum_unit_variant() {
+        let huml = r#""Active""#;
+        let status: Status = from_str(huml).unwrap();
+        assert_eq!(status, Status::Active);
+    }
+
+    #[test]
+    fn test_deserialize_enum_struct_variant() {
+        let huml = r#"
+Inactive:: reason: "maintenance"
+"#;
+        let status: Status = from_str(huml).unwrap();
+        assert_eq!(
+            status,
+            Status::Inactive {
+                reason: "maintenance".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_deserialize_enum_tuple_variant() {
+        let huml = r#"
+Pending: 42
+"#;
+        let status: Status = from_str(huml).unwrap();
+        assert_eq!(status, Status::Pending(42));
+    }
+
+    #[test]
+    fn test_deserialize_enum_forms() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum Mode {
+            Off,
+            Level(u8),
+            Pair(u8, u8),
+            Range { low: u8, high: u8 },
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Config {
+            mode: Mode,
+        }
+
+        let mode = |input: &str| from_str::<Config>(input).map(|config| config.mode);
+        assert_eq!(mode(r#"mode: "Off""#).unwrap(), Mode::Off);
+        assert_eq!(mode("mode::\n  Off: null\n").unwrap(), Mode::Off);
+        assert_eq!(mode("mode::\n  Off: {}\n").unwrap(), Mode::Off);
+        assert_eq!(mode("mode::\n  Off: []\n").unwrap(), Mode::Off);
+        assert_eq!(mode("mode: 0").unwrap(), Mode::Off);
+        assert_eq!(mode("mode:: Level: 3").unwrap(), Mode::Level(3));
+        assert_eq!(mode("mode::\n  Pair:: 1, 2\n").unwrap(), Mode::Pair(1, 2));
+        assert_eq!(
+            mode("mode::\n  Range:: low: 1, high: 2\n").unwrap(),
+            Mode::Range { low: 1, high: 2 }
+        );
+
+        let err = mode("mode:: Off: 1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid type: Expected null or empty value for unit variant at `mode.Off` on line 1:13"
+        );
+        assert!(mode("mode::\n  Off:: x: 1\n").is_err());
+        assert!(mode(r#"mode: "Level""#).is_err());
+    }
+
+    /// Asserts that `huml` deserializes to the same value as `json` does
+    /// with serde_json, or fails where that fails
+    fn assert_parity<T>(huml: &str, json: &str)
+    where
+        T: DeserializeOwned + PartialEq + fmt::Debug,
+    {
+        let from_huml = from_str::<T>(huml);
+        match serde_json::from_str::<T>(json) {
+            Ok(expected) => assert_eq!(from_huml.unwrap(), expected, "{huml}"),
+            Err(_) => assert!(from_huml.is_err(), "{huml}"),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_adjacently_tagged_enum() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Endpoint {
+            host: String,
+            ports: Vec<u16>,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(tag = "type", content = "value")]
+        enum Source {
+            Stdin,
+            Fd(u8),
+            Pair(u8, u8),
+            File { path: String, endpoint: Endpoint },
+            Remote(Endpoint),
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(tag = "type", content = "value", deny_unknown_fields)]
+        enum Strict {
+            Fd(u8),
+        }
+
+        assert_parity::<Source>(r#"type: "Stdin""#, r#"{"type": "Stdin"}"#);
+        assert_parity::<Source>(
+            "type: \"Stdin\"\nvalue: null",
+            r#"{"type": "Stdin", "value": null}"#,
+        );
+        assert_parity::<Source>("value: 3\ntype: \"Fd\"", r#"{"value": 3, "type": "Fd"}"#);
+        assert_parity::<Source>(
+            "type: \"Pair\"\nvalue:: 1, 2",
+            r#"{"type": "Pair", "value": [1, 2]}"#,
+        );
+        assert_parity::<Source>(
+            r#"
+type: "File"
+value::
+  path: "/tmp/in"
+  endpoint::
+    host: "a"
+    ports:: 80, 443
+"#,
+            r#"{"type": "File", "value": {"path": "/tmp/in", "endpoint": {"host": "a", "ports": [80, 443]}}}"#,
+        );
+        assert_parity::<Source>(
+            "type: \"Remote\"\nvalue::\n  host: \"b\"\n  ports: []\n",
+            r#"{"type": "Remote", "value": {"host": "b", "ports": []}}"#,
+        );
+        assert_parity::<Source>(
+            "type: \"Fd\"\nvalue: 3\nextra: 1",
+            r#"{"type": "Fd", "value": 3, "extra": 1}"#,
+        );
+
+        assert_parity::<Source>(r#"type: "Pipe""#, r#"{"type": "Pipe"}"#);
+        assert_parity::<Source>(r#"type: "Fd""#, r#"{"type": "Fd"}"#);
+        assert_parity::<Source>("value: 3", r#"{"value": 3}"#);
+        assert_parity::<Source>(
+            "type: \"Stdin\"\nvalue: 1",
+            r#"{"type": "Stdin", "value": 1}"#,
+        );
+        assert_parity::<Strict>(
+            "type: \"Fd\"\nvalue: 3\nextra: 1",
+            r#"{"type": "Fd", "value": 3, "extra": 1}"#,
+        );
+    }
+
+    #[test]
+    fn test_deserialize_untagged_enum() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(untagged)]
+        enum Setting {
+            Unset,
+            Flag(bool),
+            Count(u64),
+            Offset(i64),
+            Ratio(f64),
+            Name(String),
+            List(Vec<Setting>),
+            Table(HashMap<String, Setting>),
+        }
+
+        assert_parity::<Setting>("null", "null");
+        assert_parity::<Setting>("true", "true");
+        assert_parity::<Setting>("5", "5");
+        assert_parity::<Setting>("18446744073709551615", "18446744073709551615");
+        assert_parity::<Setting>("-3", "-3");
+        assert_parity::<Setting>("1.5", "1.5");
+        assert_parity::<Setting>(r#""fast""#, r#""fast""#);
+        assert_parity::<Setting>(r#"1, -2, "x""#, r#"[1, -2, "x"]"#);
+        assert_parity::<Setting>("a: 1\nb:: c: 2.5", r#"{"a": 1, "b": {"c": 2.5}}"#);
+        assert_eq!(from_str::<Setting>("0xff").unwrap(), Setting::Count(255));
+
+        // Strings are still borrowed from the input through an untagged enum
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(untagged)]
+        enum Label<'a> {
+            Id(u32),
+            Name(&'a str),
+        }
+
+        assert_eq!(from_str::<Label>(r#""web""#).unwrap(), Label::Name("web"));
+        assert_eq!(from_str::<Label>("7").unwrap(), Label::Id(7));
+    }
+
+    #[test]
+    fn test_deserialize_enum_by_index() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum Level {
+            Debug = 0,
+            Info = 1,
+            Warn = 2,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Config {
+            level: Level,
+        }
+
+        assert_eq!(from_str::<Level>("2").unwrap(), Level::Warn);
+        assert_eq!(from_str::<Config>("level: 0x1").unwrap().level, Level::Info);
+        assert_eq!(
+            from_str::<Config>(r#"level: "Debug""#).unwrap().level,
+            Level::Debug
+        );
+
+        let err = from_str::<Config>("level: 3").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid value: integer `3`, expected variant index 0 <= i < 3 at `level` on line 1:8"
+        );
+        let err = from_str::<Config>("level: -1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "value -1 out of range for variant index at `level` on line 1:8"
+        );
+        assert!(from_str::<Config>("level: 1.0").is_err());
+        // Only unit variants can be given by index
+        assert!(from_str::<Status>("2").is_err());
+    }
+
+    #[test]
+    fn test_enum_index_is_position_not_discriminant() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum Level {
+            Low = 1,
+            High = 5,
+        }
+
+        assert_eq!(from_str::<Level>("0").unwrap(), Level::Low);
+        assert_eq!(from_str::<Level>("1").unwrap(), Level::High);
+        let err = from_str::<Level>("5").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid value: integer `5`, expected variant index 0 <= i < 2"
+        );
     }
 
     #[test]
-    fn test_deserialize_enum_struct_variant() {
-        let huml = r#"
-Inactive:: reason: "maintenance"
-"#;
-        let status: Status = from_str(huml).unwrap();
+    fn test_tuple_length() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Rgb(u8, u8, u8);
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Theme {
+            point: (i32, i32, i32),
+            color: Rgb,
+        }
+
+        let theme: Theme = from_str("point:: 1, 2, 3\ncolor:: 4, 5, 6").unwrap();
+        assert_eq!(theme.point, (1, 2, 3));
+        assert_eq!(theme.color, Rgb(4, 5, 6));
+
+        let err = from_str::<Theme>("point:: 1, 2, 3, 4\ncolor:: 4, 5, 6").unwrap_err();
         assert_eq!(
-            status,
-            Status::Inactive {
-                reason: "maintenance".to_string()
-            }
+            err.to_string(),
+            "expected 3 elements, found 4 at `point` on line 1:9"
+        );
+        let err = from_str::<Theme>("point:: 1, 2, 3\ncolor:: 4, 5").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected 3 elements, found 2 at `color` on line 2:9"
+        );
+        let err = from_str::<[u8; 2]>("1, 2, 3").unwrap_err();
+        assert_eq!(err.to_string(), "expected 2 elements, found 3");
+
+        #[derive(Debug, Deserialize)]
+        enum Shape {
+            Line(#[allow(dead_code)] u8, #[allow(dead_code)] u8),
+        }
+        let err = from_str::<Shape>("Line:: 1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected 2 elements, found 1 at `Line` on line 1:8"
         );
     }
 
     #[test]
-    fn test_deserialize_enum_tuple_variant() {
-        let huml = r#"
-Pending: 42
-"#;
-        let status: Status = from_str(huml).unwrap();
-        assert_eq!(status, Status::Pending(42));
+    fn test_unit_from_empty_dict() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Marker;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Sections {
+            cache: Marker,
+            debug: (),
+            legacy: Option<Marker>,
+        }
+
+        let sections: Sections = from_str("cache: {}\ndebug: {}\nlegacy: {}").unwrap();
+        assert_eq!(
+            sections,
+            Sections {
+                cache: Marker,
+                debug: (),
+                legacy: Some(Marker),
+            }
+        );
+        assert!(from_str::<Sections>("cache: null\ndebug: null\nlegacy: null").is_ok());
+        from_str::<()>("{}").unwrap();
+
+        let err = from_str::<Sections>("cache:: a: 1\ndebug: {}\nlegacy: {}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid type: Expected null or empty dict at `cache` on line 1:9"
+        );
+        assert!(from_str::<()>("[]").is_err());
     }
 
     #[test]
@@ -821,18 +2915,58 @@ Pending: 42
         assert_eq!(i, 42);
 
         // Test float
-        let f: f64 = from_str("3.14").unwrap();
-        assert_eq!(f, 3.14);
+        let f: f64 = from_str("2.5").unwrap();
+        assert_eq!(f, 2.5);
 
         // Test boolean
         let b: bool = from_str("true").unwrap();
-        assert_eq!(b, true);
+        assert!(b);
 
         // Test list
         let list: Vec<i32> = from_str("1, 2, 3").unwrap();
         assert_eq!(list, vec![1, 2, 3]);
     }
 
+    #[test]
+    fn test_deserialize_128_bit_integers() {
+        let big: i128 = from_str("-170141183460469231731687303715884105728").unwrap();
+        assert_eq!(big, i128::MIN);
+        let big: u128 = from_str("0xffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff").unwrap();
+        assert_eq!(big, u128::MAX);
+        let small: i128 = from_str("-42").unwrap();
+        assert_eq!(small, -42);
+        let wide: u64 = from_str("18446744073709551615").unwrap();
+        assert_eq!(wide, u64::MAX);
+
+        assert!(from_str::<i64>("9223372036854775808").is_err());
+        assert!(from_str::<u128>("-1").is_err());
+    }
+
+    #[test]
+    fn test_number_and_bool_map_keys() {
+        use std::collections::BTreeMap;
+
+        let ports = BTreeMap::from([(22u8, "ssh".to_string()), (80, "http".to_string())]);
+        let huml = crate::serde::to_string(&ports).unwrap();
+        assert_eq!(from_str::<BTreeMap<u8, String>>(&huml).unwrap(), ports);
+        let (_, doc) = crate::parse_huml(&huml).unwrap();
+        assert_eq!(from_value::<BTreeMap<u8, String>>(doc.root).unwrap(), ports);
+
+        let offsets = BTreeMap::from([(-1i64, 1.5), (7, 0.0)]);
+        let huml = crate::serde::to_string(&offsets).unwrap();
+        assert_eq!(from_str::<BTreeMap<i64, f64>>(&huml).unwrap(), offsets);
+
+        let flags = BTreeMap::from([(false, 0u32), (true, 1)]);
+        let huml = crate::serde::to_string(&flags).unwrap();
+        assert_eq!(from_str::<BTreeMap<bool, u32>>(&huml).unwrap(), flags);
+
+        let err = from_str::<BTreeMap<u8, String>>("\"300\": \"x\"\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid type: string \"300\", expected u8 at `300` on line 1:1"
+        );
+    }
+
     #[test]
     fn test_deserialize_error_cases() {
         // Test invalid type
@@ -870,7 +3004,591 @@ features:: "auth", "logging", "metrics"
 
         assert_eq!(config.app_name, "My App");
         assert_eq!(config.port, 8080);
-        assert_eq!(config.debug, true);
+        assert!(config.debug);
         assert_eq!(config.features, vec!["auth", "logging", "metrics"]);
     }
+
+    #[test]
+    fn test_deserialize_from_borrowed_value() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Names<'a> {
+            name: &'a str,
+            #[serde(borrow)]
+            hobbies: Vec<&'a str>,
+        }
+
+        let (_, doc) =
+            parse_huml("name: \"Alice\"\nage: 30\nactive: true\nhobbies:: \"chess\", \"go\"\n")
+                .unwrap();
+        let person: Person = from_value_ref(&doc.root).unwrap();
+        let names: Names = from_value_ref(&doc.root).unwrap();
+        assert_eq!(person.name, names.name);
+        assert_eq!(names.hobbies, ["chess", "go"]);
+
+        assert!(from_value_ref::<Status>(&doc.root).is_err());
+
+        let (_, doc) = parse_huml("Inactive::\n  reason: \"moved\"\n").unwrap();
+        let status: Status = from_value_ref(&doc.root).unwrap();
+        assert_eq!(status, from_value(doc.root).unwrap());
+    }
+
+    #[test]
+    fn test_borrow_strings_from_input() {
+        use std::borrow::Cow;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Entry<'a> {
+            name: &'a str,
+            #[serde(borrow)]
+            note: Cow<'a, str>,
+            #[serde(borrow)]
+            tags: HashMap<&'a str, &'a str>,
+        }
+
+        let input = "name: \"web\"\nnote: \"tab\\there\"\ntags::\n  \"tier\": \"front\"\n";
+        let entry: Entry = from_str(input).unwrap();
+        assert_eq!(entry.name, "web");
+        assert_eq!(entry.tags["tier"], "front");
+        let span = input.as_bytes().as_ptr_range();
+        assert!(span.contains(&entry.name.as_ptr()));
+        // Escaped text isn't in the input as is, so it's copied
+        assert_eq!(entry.note, "tab\there");
+        assert!(matches!(entry.note, Cow::Owned(_)));
+    }
+
+    #[test]
+    fn test_borrow_strings_from_large_input() {
+        let input: String = (0..40_000)
+            .map(|i| format!("key{i}: \"value{i}\"\n"))
+            .collect();
+        let start = std::time::Instant::now();
+        let map: HashMap<&str, &str> = from_str(&input).unwrap();
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+        assert_eq!(map.len(), 40_000);
+        assert_eq!(map["key39999"], "value39999");
+        let span = input.as_bytes().as_ptr_range();
+        assert!(map
+            .iter()
+            .all(|(k, v)| span.contains(&k.as_ptr()) && span.contains(&v.as_ptr())));
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let person: Person =
+            from_reader(&b"name: \"Alice\"\nage: 30\nactive: true\nhobbies:: []\n"[..]).unwrap();
+        assert_eq!(person.age, 30);
+
+        let err = from_reader::<_, Person>(&b"name: \"\xff\"\n"[..]).unwrap_err();
+        assert!(matches!(err, Error::InvalidUtf8(7)), "{err}");
+        let err = from_reader::<_, Person>(&b"name: \"Alice\"\n"[..]).unwrap_err();
+        assert!(!matches!(err, Error::Io(_)), "{err}");
+
+        struct Failing;
+        impl io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk on fire"))
+            }
+        }
+        let err = from_reader::<_, Person>(Failing).unwrap_err();
+        assert_eq!(err.to_string(), "IO error: disk on fire");
+    }
+
+    #[test]
+    fn test_from_slice() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Name<'a> {
+            name: &'a str,
+        }
+        let input = b"name: \"Alice\"\n";
+        let name: Name = from_slice(input).unwrap();
+        assert_eq!(name.name, "Alice");
+
+        let err = from_slice::<Name>(b"name: \"Al\xc3\x28ce\"\n").unwrap_err();
+        assert!(matches!(err, Error::InvalidUtf8(9)));
+        assert_eq!(err.to_string(), "Invalid UTF-8 at byte 9");
+    }
+
+    #[test]
+    fn test_parse_errors_give_the_position_in_the_input() {
+        let err = from_str::<HashMap<String, i32>>("\n\na: 1\nb: @\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error: line 4:4 unexpected character '@' when parsing value"
+        );
+    }
+
+    #[test]
+    fn test_all_errors() {
+        #[derive(Debug, Deserialize)]
+        #[serde(deny_unknown_fields)]
+        #[allow(dead_code)]
+        struct Server {
+            name: String,
+            weight: f64,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Config {
+            name: String,
+            servers: Vec<Server>,
+            status: Status,
+            #[serde(default)]
+            retries: u32,
+        }
+
+        let input = r#"
+servers::
+  - ::
+    name: "a"
+    weight: 1.5
+  - ::
+    name: 3
+    colour: "red"
+status::
+  Pending: "soon"
+retries: "x"
+"#;
+        let errors = from_str_all_errors::<Config>(input).unwrap_err();
+        let errors: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+        assert_eq!(
+            errors,
+            [
+                "Invalid type: Expected string at `servers[1].name` on line 7:11",
+                "Unknown field `servers[1].colour` at line 8:5, expected `name` or `weight`",
+                "Missing field: weight at `servers[1]` on line 7:5",
+                "Invalid type: Expected unsigned integer at `status.Pending` on line 10:12",
+                "Invalid type: Expected unsigned integer at `retries` on line 11:10",
+                "Missing field: name",
+            ]
+        );
+
+        let input = "name: \"x\"\nservers: []\nstatus: \"Active\"\n";
+        let config = from_str_all_errors::<Config>(input).unwrap();
+        assert_eq!(config.status, Status::Active);
+        assert_eq!((config.name.as_str(), config.retries), ("x", 0));
+        assert!(config.servers.is_empty());
+
+        // Errors that can't be got around end the list
+        let errors = from_str_all_errors::<Config>("1, 2").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        let errors = from_str_all_errors::<Vec<u8>>("1, \"a\", 3, true").unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_all_errors_stops_at_limit() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Server {
+            name: String,
+            weight: f64,
+        }
+
+        // Read past in one go
+        let input: String = (0..10_000).map(|_| "- \"a\"\n").collect();
+        let start = std::time::Instant::now();
+        let errors = from_str_all_errors::<Vec<u8>>(&input).unwrap_err();
+        assert_eq!(errors.len(), MAX_ERRORS);
+        assert_eq!(
+            errors[99].to_string(),
+            "Invalid type: Expected unsigned integer at `[99]` on line 100:3"
+        );
+
+        // Read again for each missing field
+        let input: String = (0..10_000).map(|_| "- ::\n  name: \"a\"\n").collect();
+        let errors = from_str_all_errors::<Vec<Server>>(&input).unwrap_err();
+        assert_eq!(errors.len(), MAX_ERRORS);
+        assert_eq!(
+            errors[99].to_string(),
+            "Missing field: weight at `[99]` on line 200:3"
+        );
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_unknown_fields() {
+        #[derive(Debug, Deserialize)]
+        #[serde(deny_unknown_fields)]
+        #[allow(dead_code)]
+        struct Limits {
+            max_connections: u32,
+            timeout: u32,
+        }
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Config {
+            limits: Limits,
+        }
+
+        let err =
+            from_str::<Config>("limits::\n  timeout: 5\n  max-connections: 10\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown field `limits.max-connections` at line 3:3, did you mean `max_connections`?"
+        );
+        assert!(matches!(
+            err,
+            Error::At { ref error, .. } if matches!(**error, Error::UnexpectedKey { position: Some((3, 3)), .. })
+        ));
+
+        let err = from_str::<Config>("limits:: timeout: 5, retries: 2\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown field `limits.retries` at line 1:22, expected `max_connections` or `timeout`"
+        );
+
+        // No position without the input
+        let (_, doc) = crate::parse_huml("limits:: timeot: 5\n").unwrap();
+        let err = from_value::<Config>(doc.root).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown field `limits.timeot`, did you mean `timeout`?"
+        );
+    }
+
+    #[test]
+    fn test_integer_range_errors() {
+        #[derive(Debug, Deserialize)]
+        struct Config {
+            retries: u8,
+            offset: i16,
+        }
+
+        let err = from_str::<Config>("retries: 300\noffset: 0\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "value 300 out of range for u8 at `retries` on line 1:10"
+        );
+        let err = from_str::<Config>("retries: 3\noffset: -40000\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "value -40000 out of range for i16 at `offset` on line 2:9"
+        );
+        let err = from_str::<Config>("retries: -1\noffset: 0\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "value -1 out of range for u8 at `retries` on line 1:10"
+        );
+
+        let config = from_str::<Config>("retries: 0xff\noffset: -2.5\n").unwrap();
+        assert_eq!((config.retries, config.offset), (255, -2));
+
+        let err = from_str::<u32>("1e10").unwrap_err();
+        assert_eq!(err.to_string(), "value 10000000000 out of range for u32");
+        let err = from_str::<u64>("170141183460469231731687303715884105727").unwrap_err();
+        assert!(matches!(err, Error::OutOfRange { ty: "u64", .. }), "{err}");
+        assert_eq!(from_str::<i8>("-128").unwrap(), -128);
+    }
+
+    #[test]
+    fn test_strict_numbers() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Limits {
+            count: i64,
+            ratio: f64,
+        }
+
+        let strict = DeserializeOptions::default().with_strict_numbers(true);
+        let limits: Limits = from_str_with("count: 3\nratio: 0.5\n", strict).unwrap();
+        assert_eq!(
+            limits,
+            Limits {
+                count: 3,
+                ratio: 0.5
+            }
+        );
+        let limits: Limits = from_str_with("count: 0x10\nratio: inf\n", strict).unwrap();
+        assert_eq!(limits.count, 16);
+
+        let err = from_str_with::<Limits>("count: 3.0\nratio: 0.5\n", strict).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid type: Expected integer, found float at `count` on line 1:8"
+        );
+        let err = from_str_with::<Limits>("count: 3\nratio: 1\n", strict).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid type: Expected float, found integer at `ratio` on line 2:8"
+        );
+
+        // Converted by default
+        let limits: Limits = from_str("count: 3.0\nratio: 1\n").unwrap();
+        assert_eq!(
+            limits,
+            Limits {
+                count: 3,
+                ratio: 1.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_lenient_scalars() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Server {
+            port: u16,
+            debug: bool,
+            ratio: f32,
+            name: String,
+        }
+
+        let input = "port: \"8080\"\ndebug: \"true\"\nratio: \" 0.5 \"\nname: \"42\"\n";
+        let lenient = DeserializeOptions::default().with_lenient_scalars(true);
+        let server: Server = from_str_with(input, lenient).unwrap();
+        assert_eq!(
+            server,
+            Server {
+                port: 8080,
+                debug: true,
+                ratio: 0.5,
+                name: "42".to_string(),
+            }
+        );
+
+        // Values that aren't numbers or booleans still fail
+        let input = "port: \"80a\"\ndebug: true\nratio: 1.0\nname: \"x\"\n";
+        let err = from_str_with::<Server>(input, lenient).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid type: Expected unsigned integer at `port` on line 1:7"
+        );
+        let err = from_str_with::<Server>("port: \"70000\"\n", lenient).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "value 70000 out of range for u16 at `port` on line 1:7"
+        );
+
+        // Off by default
+        assert!(
+            from_str::<Server>("port: \"8080\"\ndebug: true\nratio: 1.0\nname: \"x\"\n").is_err()
+        );
+    }
+
+    #[test]
+    fn test_case_insensitive_keys() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Server {
+            port: u16,
+            #[serde(rename = "hostName")]
+            host_name: String,
+            mode: Mode,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum Mode {
+            Fast { level: u8 },
+        }
+
+        let input = "PORT: 80\nHostname: \"a\"\nmode::\n  Fast::\n    Level: 2\n";
+        let options = DeserializeOptions::default().with_case_insensitive_keys(true);
+        let server: Server = from_str_with(input, options).unwrap();
+        assert_eq!(
+            server,
+            Server {
+                port: 80,
+                host_name: "a".to_string(),
+                mode: Mode::Fast { level: 2 },
+            }
+        );
+
+        // Map keys keep their case
+        let map: HashMap<String, u8> = from_str_with("A: 1\n", options).unwrap();
+        assert_eq!(map["A"], 1);
+
+        let err = from_str::<Server>(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Missing field: level at `mode.Fast` on line 5:5"
+        );
+    }
+
+    #[test]
+    fn test_key_case() {
+        #[derive(Debug, Deserialize, serde::Serialize, PartialEq)]
+        struct Limits {
+            max_connections: u32,
+            idle_timeout: Option<u32>,
+            tags: HashMap<String, u8>,
+        }
+
+        let limits = Limits {
+            max_connections: 10,
+            idle_timeout: Some(30),
+            tags: HashMap::from([("some_tag".to_string(), 1)]),
+        };
+        let options = crate::serde::SerializeOptions::default().with_key_case(KeyCase::Kebab);
+        let huml = crate::serde::to_string_with(&limits, options).unwrap();
+        assert_eq!(
+            huml,
+            "max-connections: 10\nidle-timeout: 30\ntags::\n  some_tag: 1\n"
+        );
+
+        let kebab = DeserializeOptions::default().with_key_case(KeyCase::Kebab);
+        assert_eq!(from_str_with::<Limits>(&huml, kebab).unwrap(), limits);
+
+        // Exact field names still match
+        let input = "max_connections: 10\nidle-timeout: 30\ntags:: some_tag: 1\n";
+        assert_eq!(from_str_with::<Limits>(input, kebab).unwrap(), limits);
+
+        let input = "MaxConnections: 10\ntags: {}\n";
+        let pascal = DeserializeOptions::default().with_key_case(KeyCase::Pascal);
+        let read = from_str_with::<Limits>(input, pascal).unwrap();
+        assert_eq!((read.max_connections, read.idle_timeout), (10, None));
+    }
+
+    #[test]
+    fn test_null_as_default() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Pool {
+            #[serde(default = "default_size")]
+            size: u32,
+            #[serde(default)]
+            hosts: Vec<String>,
+            name: Option<String>,
+        }
+
+        fn default_size() -> u32 {
+            4
+        }
+
+        let input = "size: null\nhosts: null\nname: null\n";
+        assert!(from_str::<Pool>(input).is_err());
+        let options = DeserializeOptions::default().with_null_as_default(true);
+        let pool: Pool = from_str_with(input, options).unwrap();
+        assert_eq!(
+            pool,
+            Pool {
+                size: 4,
+                hosts: vec![],
+                name: None,
+            }
+        );
+
+        // Map entries keep their nulls
+        let map: HashMap<String, Option<u8>> = from_str_with("a: null\n", options).unwrap();
+        assert_eq!(map["a"], None);
+
+        #[derive(Debug, Deserialize)]
+        struct Required {
+            #[allow(dead_code)]
+            size: u32,
+        }
+        let err = from_str_with::<Required>("size: null\n", options).unwrap_err();
+        assert_eq!(err.to_string(), "Missing field: size");
+    }
+
+    #[test]
+    fn test_double_option() {
+        #[derive(Debug, Default, Deserialize, serde::Serialize, PartialEq)]
+        struct Patch {
+            #[serde(
+                default,
+                deserialize_with = "double_option",
+                skip_serializing_if = "Option::is_none"
+            )]
+            timeout: Option<Option<u32>>,
+            #[serde(
+                default,
+                deserialize_with = "double_option",
+                skip_serializing_if = "Option::is_none"
+            )]
+            name: Option<Option<String>>,
+        }
+
+        let patch: Patch = from_str("timeout: null\nname: \"x\"\n").unwrap();
+        assert_eq!(patch.timeout, Some(None));
+        assert_eq!(patch.name, Some(Some("x".to_string())));
+        let patch: Patch = from_str("timeout: 3\n").unwrap();
+        assert_eq!((patch.timeout, patch.name), (Some(Some(3)), None));
+
+        for patch in [
+            Patch::default(),
+            Patch {
+                timeout: Some(None),
+                name: Some(Some("y".to_string())),
+            },
+        ] {
+            assert_eq!(crate::serde::round_trip(&patch).unwrap(), patch);
+        }
+    }
+
+    #[test]
+    fn test_root_lists() {
+        let input = r#"
+# Servers
+- ::
+  name: "Alice"
+  age: 30
+  active: true
+- :: name: "Bob", age: 25, active: false
+"#;
+        let expected = vec![
+            Person {
+                name: "Alice".to_string(),
+                age: 30,
+                active: true,
+            },
+            Person {
+                name: "Bob".to_string(),
+                age: 25,
+                active: false,
+            },
+        ];
+        assert_eq!(from_str::<Vec<Person>>(input).unwrap(), expected);
+        let deserializer: Deserializer = input.parse().unwrap();
+        assert_eq!(Vec::<Person>::deserialize(deserializer).unwrap(), expected);
+
+        let input = "- ::\n  - 2\n  - 3\n- :: 4, 5\n- []\n";
+        assert_eq!(
+            from_str::<Vec<Vec<u8>>>(input).unwrap(),
+            [vec![2, 3], vec![4, 5], vec![]]
+        );
+
+        let input = "- \"a\"  # first\n\n- \"b\"\n";
+        assert_eq!(from_str::<Vec<String>>(input).unwrap(), ["a", "b"]);
+        let err = from_str::<Vec<Person>>("- ::\n  name: \"Alice\"\n").unwrap_err();
+        assert_eq!(err.to_string(), "Missing field: age at `[0]` on line 2:3");
+    }
+
+    #[test]
+    fn test_from_str_at() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Tls<'a> {
+            cert: &'a str,
+            port: crate::serde::Spanned<u16>,
+        }
+
+        let input = r#"
+server::
+  host: "a"
+  tls::
+    cert: "a.pem"
+    port: 443
+replicas::
+  - :: cert: "b.pem", port: 8443
+"#;
+        let tls: Tls = from_str_at(input, "server.tls").unwrap();
+        assert_eq!(tls.cert, "a.pem");
+        assert_eq!(&input[tls.port.span()], "443");
+        let tls: Tls = from_str_at(input, "replicas[0]").unwrap();
+        assert_eq!(&input[tls.port.span()], "8443");
+        assert_eq!(from_str_at::<String>(input, "server.host").unwrap(), "a");
+
+        let err = from_str_at::<Tls>(input, "server").unwrap_err();
+        assert_eq!(err.to_string(), "Missing field: cert at `server`");
+        let err = from_str_at::<u8>(input, "server.tls.port").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "value 443 out of range for u8 at `server.tls.port`"
+        );
+        let err = from_str_at::<Tls>(input, "server.tls.key").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Nothing at `server.tls.key` in the document"
+        );
+        assert!(from_str_at::<Tls>(input, "replicas[1]").is_err());
+        assert!(from_str_at::<Tls>(input, "server..tls").is_err());
+    }
 }
@@ -0,0 +1,166 @@
+//! Reading and writing HUML files
+
+use super::{de, from_slice, ser, to_string_with, Error, SerializeOptions};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Error from [`from_file`] or [`to_file`], naming the file it happened in
+///
+/// Parse errors, and errors in values nested in the document, give the line
+/// and column as well.
+#[derive(Debug)]
+pub struct FileError {
+    /// The file that was being read or written
+    pub path: PathBuf,
+    /// What went wrong
+    pub error: Error,
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error {
+            Error::De(e) => write!(f, "{}: {e}", self.path.display()),
+            Error::Ser(e) => write!(f, "{}: {e}", self.path.display()),
+        }
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Read the HUML file at `path` into a type
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use huml_rs::serde::from_file;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     port: u16,
+/// }
+///
+/// let config: Config = from_file("config.huml").unwrap_or_else(|err| {
+///     // e.g. "config.huml: Parse error: line 3:7 unclosed string"
+///     panic!("{err}")
+/// });
+/// ```
+pub fn from_file<T>(path: impl AsRef<Path>) -> Result<T, FileError>
+where
+    T: DeserializeOwned,
+{
+    let path = path.as_ref();
+    let read = || -> de::Result<T> {
+        let bytes = fs::read(path)?;
+        from_slice(&bytes)
+    };
+    read().map_err(|err| FileError {
+        path: path.to_path_buf(),
+        error: Error::De(err),
+    })
+}
+
+/// Write a value to the file at `path` as HUML, replacing its contents
+pub fn to_file<T>(path: impl AsRef<Path>, value: &T) -> Result<(), FileError>
+where
+    T: Serialize,
+{
+    to_file_with(path, value, SerializeOptions::default())
+}
+
+/// Write a value to the file at `path` as HUML using the given options
+pub fn to_file_with<T>(
+    path: impl AsRef<Path>,
+    value: &T,
+    options: SerializeOptions,
+) -> Result<(), FileError>
+where
+    T: Serialize,
+{
+    let path = path.as_ref();
+    let write = || -> ser::Result<()> {
+        let text = to_string_with(value, options)?;
+        fs::write(path, text)?;
+        Ok(())
+    };
+    write().map_err(|err| FileError {
+        path: path.to_path_buf(),
+        error: Error::Ser(err),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn files_round_trip_and_errors_name_the_file() {
+        let dir = std::env::temp_dir().join(format!("huml-file-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("config.huml");
+        let config = BTreeMap::from([("port".to_string(), 8080)]);
+        to_file(&path, &config).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "port: 8080\n");
+        assert_eq!(from_file::<BTreeMap<String, u16>>(&path).unwrap(), config);
+
+        fs::write(&path, "port: 8080\nhost: \"x\n").unwrap();
+        let err = from_file::<BTreeMap<String, u16>>(&path).unwrap_err();
+        assert_eq!(err.path, path);
+        assert!(err
+            .to_string()
+            .starts_with(&format!("{}: ", path.display())));
+        assert!(err.to_string().contains("line 2:"), "{err}");
+
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Server {
+            host: String,
+            port: u16,
+        }
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Config {
+            servers: Vec<Server>,
+        }
+        fs::write(
+            &path,
+            "servers::\n  - ::\n    host: \"a\"\n    port: \"80\"\n",
+        )
+        .unwrap();
+        let err = from_file::<Config>(&path).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{}: Invalid type: Expected unsigned integer at `servers[0].port` on line 4:11",
+                path.display()
+            )
+        );
+        fs::write(&path, "servers::\n  - ::\n    port: 80\n").unwrap();
+        let err = from_file::<Config>(&path).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{}: Missing field: host at `servers[0]` on line 3:5",
+                path.display()
+            )
+        );
+
+        let missing = dir.join("missing.huml");
+        let err = from_file::<BTreeMap<String, u16>>(&missing).unwrap_err();
+        assert!(matches!(err.error, Error::De(de::Error::Io(_))));
+
+        let err = to_file(dir.join("no/such/dir.huml"), &config).unwrap_err();
+        assert!(matches!(err.error, Error::Ser(ser::Error::Io(_))));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
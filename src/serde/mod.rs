@@ -56,12 +56,32 @@
 //! println!("{}", huml);
 //! ```
 
+mod bytes;
+mod case;
 pub mod de;
+mod file;
 pub mod ser;
+mod spanned;
+#[cfg(feature = "tokio")]
+mod tokio;
+mod value;
 
 // Re-export common functions for convenience
-pub use de::{from_str, Deserializer, Error as DeError};
-pub use ser::{to_string, Error as SerError, Serializer};
+#[cfg(feature = "tokio")]
+pub use self::tokio::{from_async_reader, to_async_writer, to_async_writer_with};
+pub use bytes::BytesEncoding;
+pub use case::KeyCase;
+pub use de::{
+    double_option, from_reader, from_slice, from_str, from_str_all_errors, from_str_at,
+    from_str_with, from_value, from_value_ref, DeserializeOptions, Deserializer, Error as DeError,
+};
+pub use file::{from_file, to_file, to_file_with, FileError};
+pub use ser::{
+    to_canonical_string, to_document, to_document_with, to_string, to_string_compact,
+    to_string_pretty, to_string_with, Binary, Commented, Error as SerError, EscapeProfile, Hex,
+    Octal, Secret, SerializeOptions, Serializer, UnicodeEscape,
+};
+pub use spanned::Spanned;
 
 pub use de::Result as DeResult;
 
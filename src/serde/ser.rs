@@ -33,9 +33,14 @@
 //! // features:: "auth", "logging"
 //! ```
 
+use super::{BytesEncoding, KeyCase};
+use crate::{HumlDocument, Radix};
 use serde::ser::{self, Serialize};
-use std::fmt;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt::{self, Write as _};
 use std::io;
+use std::sync::Arc;
 
 /// Error type for HUML serialization
 #[derive(Debug, Clone)]
@@ -46,6 +51,20 @@ pub enum Error {
     Io(String),
     /// Unsupported type
     UnsupportedType(&'static str),
+    /// `nan` or an infinity, with [`SerializeOptions::reject_non_finite`] set
+    NonFiniteFloat(f64),
+    /// The serializer wrote text that doesn't parse, or that reads back as a
+    /// different value, caught by [`SerializeOptions::validate_output`]. This
+    /// is a bug in the serializer.
+    InvalidOutput {
+        /// What the parser reported
+        message: String,
+        /// The offending text
+        text: String,
+    },
+    /// A map had two entries with the same key, e.g. a `#[serde(flatten)]`
+    /// field repeating a key of its parent
+    DuplicateKey(String),
 }
 
 impl fmt::Display for Error {
@@ -54,6 +73,11 @@ impl fmt::Display for Error {
             Error::Message(msg) => f.write_str(msg),
             Error::Io(msg) => write!(f, "IO error: {msg}"),
             Error::UnsupportedType(msg) => write!(f, "Unsupported type: {msg}"),
+            Error::NonFiniteFloat(v) => write!(f, "Non-finite float: {v}"),
+            Error::InvalidOutput { message, text } => {
+                write!(f, "Serializer wrote invalid HUML ({message}):\n{text}")
+            }
+            Error::DuplicateKey(key) => write!(f, "Duplicate key: {key}"),
         }
     }
 }
@@ -75,21 +99,377 @@ impl From<io::Error> for Error {
 /// Result type for HUML serialization
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// How characters that have to be escaped by code point are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnicodeEscape {
+    /// Four hex digits (`\u001b`), the form shared with JSON
+    #[default]
+    Fixed,
+    /// Braced hex digits (`\u{1b}`), which can express any code point directly
+    Braced,
+}
+
+/// Which characters are escaped in quoted strings, beyond the ones that have
+/// to be
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EscapeProfile {
+    /// Only escape quotes, backslashes and control characters
+    #[default]
+    Minimal,
+    /// Also escape `/` as `\/`, like JSON encoders that guard against `</script>`
+    Json,
+}
+
+/// Formatting choices made by the [`Serializer`]
+///
+/// Indentation is always two spaces and lines always end in `\n`, as the
+/// specification requires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerializeOptions {
+    /// How characters that have to be escaped by code point are written
+    pub unicode_escape: UnicodeEscape,
+    /// Which characters are escaped in quoted strings
+    pub escape_profile: EscapeProfile,
+    /// Escape every non-ASCII character in quoted strings and keys, for
+    /// output that has to pass through ASCII-only channels. Strings that need
+    /// escapes are never written as `"""` blocks. Comments are written as
+    /// given.
+    pub ascii_only: bool,
+    /// How byte strings are written as strings
+    pub bytes_encoding: BytesEncoding,
+    /// Write finite floats with this many digits after the decimal point.
+    /// When `None`, the shortest text that reads back as the same value is
+    /// used. Either way a float always has a decimal point or exponent, so it
+    /// doesn't read back as an integer.
+    pub float_precision: Option<usize>,
+    /// Write [`Secret`] values as `"<redacted>"`, for dumping configuration
+    /// to logs or support bundles
+    pub redact_secrets: bool,
+    /// Leave out map entries and struct fields whose value is `None`, rather
+    /// than writing them as `null`
+    pub skip_none: bool,
+    /// Leave out map entries and struct fields whose value is an empty list
+    /// or map, rather than writing `[]` or `{}`. A map left empty by skipped
+    /// entries is itself skipped. Reading the output back needs
+    /// `#[serde(default)]` on those fields.
+    pub skip_empty: bool,
+    /// Return [`Error::NonFiniteFloat`] for `nan` and infinities instead of
+    /// writing them, for output that has to convert to JSON later
+    pub reject_non_finite: bool,
+    /// Sequences with more elements than this are written as `- ` items, one
+    /// per line, instead of inline. When `None`, sequence length doesn't
+    /// matter; `Some(0)` writes every non-empty sequence as a block.
+    pub max_inline_items: Option<usize>,
+    /// Sequences that would make their line longer than this many bytes,
+    /// counting the indentation and key in front of them, are written as `- `
+    /// items instead. Defaults to 80; when `None`, width doesn't matter.
+    pub max_inline_width: Option<usize>,
+    /// Maps and structs with at most this many entries, all of them scalars
+    /// on one line, are written inline as `key:: a: 1, b: 2`, as long as the
+    /// line fits in [`max_inline_width`](Self::max_inline_width). When
+    /// `None`, maps are always written as blocks.
+    pub max_inline_fields: Option<usize>,
+    /// Write the entries of maps in lexicographic key order, so that output
+    /// doesn't depend on `HashMap` iteration order. Struct fields always keep
+    /// their declaration order.
+    pub sort_keys: bool,
+    /// Keys written first in every map and struct that has them, in this
+    /// order, such as `["name", "version"]`. The other entries follow in
+    /// their usual order.
+    pub key_order: Vec<String>,
+    /// Write strings containing newlines as `"""` blocks rather than quoted
+    /// strings with `\n` escapes, where the text allows it. Strings inside
+    /// inline lists are always quoted.
+    pub multiline_strings: bool,
+    /// Text written as `# ` comment lines at the top of the document, one per
+    /// line of text, and set off from it by a blank line. For banners such
+    /// as "Generated by tool X, do not edit".
+    pub header_comment: Option<String>,
+    /// End the document with a single `\n`, as most editors and linters
+    /// expect of text files
+    pub trailing_newline: bool,
+    /// Parse the output again before returning it, and fail with
+    /// [`Error::InvalidOutput`] if it doesn't parse or reads back as a
+    /// different value, so layout bugs show up when writing rather than when
+    /// reading. The value is compared with the same value written in the
+    /// plainest layout, every collection a block and every string quoted,
+    /// so that options such as [`skip_none`](Self::skip_none) apply to both.
+    /// This serializes the value twice and parses it twice.
+    pub validate_output: bool,
+    /// Normalize the output beyond what the other options control: struct
+    /// fields are sorted along with map keys and integers are always decimal.
+    /// Set by [`SerializeOptions::canonical`].
+    pub canonical: bool,
+    /// The naming convention to write struct field keys in, such as
+    /// `max-connections` for a `max_connections` field. Map keys are written
+    /// as they are.
+    pub key_case: Option<KeyCase>,
+}
+
+impl SerializeOptions {
+    /// The options used by [`to_canonical_string`]
+    ///
+    /// Keys are sorted, every string is quoted on one line, lists are inline
+    /// unless they hold structs or other lists, and the document ends with a
+    /// newline. Equal values always produce the same bytes.
+    pub fn canonical() -> Self {
+        Self {
+            max_inline_width: None,
+            sort_keys: true,
+            multiline_strings: false,
+            trailing_newline: true,
+            canonical: true,
+            ..Self::default()
+        }
+    }
+
+    /// The options used by [`to_string_pretty`]
+    ///
+    /// Every non-empty list is written as `- ` items and every non-empty map
+    /// as a block, one value per line, with strings containing newlines
+    /// written as `"""` blocks. [`Commented`] values keep their comments.
+    pub fn pretty() -> Self {
+        Self {
+            max_inline_items: Some(0),
+            max_inline_fields: None,
+            multiline_strings: true,
+            ..Self::default()
+        }
+    }
+
+    /// The options used by [`to_string_compact`]
+    ///
+    /// Lists and maps of scalars are written inline whatever their length,
+    /// and strings stay quoted on one line, giving the fewest lines HUML
+    /// allows. Nested collections and the root map are still blocks.
+    pub fn compact() -> Self {
+        Self {
+            max_inline_items: None,
+            max_inline_width: None,
+            max_inline_fields: Some(usize::MAX),
+            multiline_strings: false,
+            ..Self::default()
+        }
+    }
+
+    /// Choose how code point escapes are written
+    pub fn with_unicode_escape(mut self, style: UnicodeEscape) -> Self {
+        self.unicode_escape = style;
+        self
+    }
+
+    /// Choose which characters are escaped in quoted strings
+    pub fn with_escape_profile(mut self, profile: EscapeProfile) -> Self {
+        self.escape_profile = profile;
+        self
+    }
+
+    /// Escape non-ASCII characters so the output is pure ASCII
+    pub fn with_ascii_only(mut self) -> Self {
+        self.ascii_only = true;
+        self
+    }
+
+    /// Choose how byte strings are written
+    pub fn with_bytes_encoding(mut self, encoding: BytesEncoding) -> Self {
+        self.bytes_encoding = encoding;
+        self
+    }
+
+    /// Write floats with `digits` digits after the decimal point
+    pub fn with_float_precision(mut self, digits: usize) -> Self {
+        self.float_precision = Some(digits);
+        self
+    }
+
+    /// Write [`Secret`] values as `"<redacted>"`
+    pub fn with_redact_secrets(mut self) -> Self {
+        self.redact_secrets = true;
+        self
+    }
+
+    /// Leave out entries whose value is `None`
+    pub fn with_skip_none(mut self) -> Self {
+        self.skip_none = true;
+        self
+    }
+
+    /// Leave out entries whose value is an empty list or map
+    pub fn with_skip_empty(mut self) -> Self {
+        self.skip_empty = true;
+        self
+    }
+
+    /// Fail on `nan` and infinities rather than writing them
+    pub fn with_reject_non_finite(mut self) -> Self {
+        self.reject_non_finite = true;
+        self
+    }
+
+    /// Write sequences longer than `max` elements as `- ` items
+    pub fn with_max_inline_items(mut self, max: usize) -> Self {
+        self.max_inline_items = Some(max);
+        self
+    }
+
+    /// Write sequences whose inline text is longer than `width` as `- ` items
+    pub fn with_max_inline_width(mut self, width: usize) -> Self {
+        self.max_inline_width = Some(width);
+        self
+    }
+
+    /// Write struct field keys in the `case` naming convention
+    pub fn with_key_case(mut self, case: KeyCase) -> Self {
+        self.key_case = Some(case);
+        self
+    }
+
+    /// Write the keys in `keys` first, in that order, wherever they appear
+    pub fn with_key_order<I>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.key_order = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Write maps and structs of up to `fields` scalar entries inline
+    pub fn with_max_inline_fields(mut self, fields: usize) -> Self {
+        self.max_inline_fields = Some(fields);
+        self
+    }
+
+    /// Write every non-empty sequence as `- ` items
+    pub fn with_block_sequences(self) -> Self {
+        self.with_max_inline_items(0)
+    }
+
+    /// Write map entries in key order
+    pub fn with_sort_keys(mut self) -> Self {
+        self.sort_keys = true;
+        self
+    }
+
+    /// Write `comment` as `# ` lines at the top of the document
+    pub fn with_header_comment(mut self, comment: impl Into<String>) -> Self {
+        self.header_comment = Some(comment.into());
+        self
+    }
+
+    /// End the document right after its last value
+    pub fn without_trailing_newline(mut self) -> Self {
+        self.trailing_newline = false;
+        self
+    }
+
+    /// Check that the output parses back to the value before returning it
+    pub fn with_validate_output(mut self, validate: bool) -> Self {
+        self.validate_output = validate;
+        self
+    }
+
+    /// Always write strings as quoted strings, escaping newlines
+    pub fn without_multiline_strings(mut self) -> Self {
+        self.multiline_strings = false;
+        self
+    }
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        Self {
+            unicode_escape: UnicodeEscape::default(),
+            escape_profile: EscapeProfile::default(),
+            ascii_only: false,
+            bytes_encoding: BytesEncoding::default(),
+            float_precision: None,
+            redact_secrets: false,
+            skip_none: false,
+            skip_empty: false,
+            reject_non_finite: false,
+            max_inline_items: None,
+            max_inline_width: Some(80),
+            max_inline_fields: None,
+            sort_keys: false,
+            key_order: Vec::new(),
+            multiline_strings: true,
+            header_comment: None,
+            trailing_newline: true,
+            validate_output: false,
+            canonical: false,
+            key_case: None,
+        }
+    }
+}
+
+/// How the value written last was laid out, which decides the `:`/`::`
+/// indicator in front of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    /// A scalar, multiline string or empty collection
+    Scalar,
+    /// A non-empty inline list or dict
+    Inline,
+    /// A block list or dict spanning its own lines
+    Block,
+}
+
 /// HUML serializer that writes to a string
 pub struct Serializer {
     output: String,
     indent_level: usize,
+    /// Nesting depth of inline contexts (inline lists and keys), where values
+    /// have to stay on one line
+    inline_depth: usize,
+    /// Layout of the value written last
+    layout: Layout,
+    /// Width of the text in front of the value on its line
+    line_prefix: usize,
+    /// Comment of the [`Commented`] value written last, waiting to be placed
+    /// above its entry
+    pending_comment: Option<String>,
+    /// Base to write the next unsigned integer in, set by [`Hex`], [`Octal`]
+    /// and [`Binary`]
+    radix: Option<Radix>,
+    /// Whether the value written was `None`
+    wrote_none: bool,
+    /// Shared with the serializers of nested values
+    options: Arc<SerializeOptions>,
 }
 
 impl Serializer {
     /// Create a new serializer
     pub fn new() -> Self {
+        Self::with_options(SerializeOptions::default())
+    }
+
+    /// Create a serializer with the given formatting options
+    pub fn with_options(options: SerializeOptions) -> Self {
         Self {
             output: String::new(),
             indent_level: 0,
+            inline_depth: 0,
+            layout: Layout::Scalar,
+            line_prefix: 0,
+            pending_comment: None,
+            radix: None,
+            wrote_none: false,
+            options: Arc::new(options),
         }
     }
 
+    /// Choose how code point escapes are written
+    pub fn with_unicode_escape(mut self, style: UnicodeEscape) -> Self {
+        Arc::make_mut(&mut self.options).unicode_escape = style;
+        self
+    }
+
+    /// The formatting options in use
+    pub fn options(&self) -> &SerializeOptions {
+        &self.options
+    }
+
     /// Get the current indentation string
     fn indent(&self) -> String {
         "  ".repeat(self.indent_level)
@@ -112,30 +492,86 @@ impl Serializer {
         }
     }
 
+    /// A serializer for a value nested one level below the current one
+    fn child(&self) -> Serializer {
+        Serializer {
+            output: String::new(),
+            indent_level: self.indent_level + 1,
+            inline_depth: self.inline_depth,
+            layout: Layout::Scalar,
+            line_prefix: 0,
+            pending_comment: None,
+            radix: None,
+            wrote_none: false,
+            options: self.options.clone(),
+        }
+    }
+
+    /// A serializer for a value that has to stay on one line, like a key
+    fn inline_child(&self) -> Serializer {
+        let mut child = self.child();
+        child.inline_depth += 1;
+        child
+    }
+
+    /// Writes `comment` to `out` as `# ` lines, indented to the current level
+    fn write_comment(&self, out: &mut String, comment: &str) {
+        let indent = self.indent();
+        for line in comment.lines() {
+            out.push_str(&indent);
+            out.push('#');
+            if !line.is_empty() {
+                out.push(' ');
+                out.push_str(line);
+            }
+            out.push('\n');
+        }
+    }
+
     /// Write a string value with proper HUML escaping
     fn write_string(&mut self, s: &str) -> Result<()> {
-        self.output.push('"');
-        for ch in s.chars() {
-            match ch {
-                '"' => self.output.push_str("\\\""),
-                '\\' => self.output.push_str("\\\\"),
-                '\n' => self.output.push_str("\\n"),
-                '\t' => self.output.push_str("\\t"),
-                '\r' => self.output.push_str("\\r"),
-                '\x08' => self.output.push_str("\\b"),
-                '\x0C' => self.output.push_str("\\f"),
-                '/' => self.output.push_str("\\/"),
-                c if c.is_control() => {
-                    self.output.push_str(&format!("\\u{:04x}", c as u32));
-                }
-                c => self.output.push(c),
+        crate::emit::write_quoted(
+            &mut self.output,
+            s,
+            self.options.unicode_escape,
+            self.options.escape_profile,
+            self.options.ascii_only,
+        );
+        Ok(())
+    }
+
+    /// Write a float so that it reads back as a float. `v` is formatted at its
+    /// own width, so an `f32` doesn't pick up digits from widening.
+    fn write_float<F>(&mut self, v: F) -> Result<()>
+    where
+        F: Copy + fmt::Debug + Into<f64>,
+    {
+        let wide: f64 = v.into();
+        if self.options.reject_non_finite && !wide.is_finite() {
+            return Err(Error::NonFiniteFloat(wide));
+        }
+        if wide.is_nan() {
+            self.output.push_str("nan");
+        } else if wide.is_infinite() {
+            self.output.push_str(if wide.is_sign_positive() {
+                "inf"
+            } else {
+                "-inf"
+            });
+        } else if let Some(digits) = self.options.float_precision {
+            let _ = write!(self.output, "{wide:.digits$}");
+            if digits == 0 {
+                self.output.push_str(".0");
             }
+        } else {
+            // `Debug` always includes a `.` or an exponent
+            let _ = write!(self.output, "{v:?}");
         }
-        self.output.push('"');
         Ok(())
     }
 
-    /// Finish serialization and return the result
+    /// Finish serialization and return the result, without the trailing
+    /// newline [`to_string_with`] adds
     pub fn into_string(self) -> String {
         self.output
     }
@@ -152,9 +588,169 @@ pub fn to_string<T>(value: &T) -> Result<String>
 where
     T: Serialize,
 {
-    let mut serializer = Serializer::new();
+    to_string_with(value, SerializeOptions::default())
+}
+
+/// Serialize a value into a HUML string using the given formatting options
+pub fn to_string_with<T>(value: &T, options: SerializeOptions) -> Result<String>
+where
+    T: Serialize,
+{
+    let mut serializer = Serializer::with_options(options);
     value.serialize(&mut serializer)?;
-    Ok(serializer.into_string())
+    let options = serializer.options.clone();
+    let mut out = String::new();
+    if let Some(header) = &options.header_comment
+        && !header.is_empty()
+    {
+        serializer.write_comment(&mut out, header);
+        out.push('\n');
+    }
+    if let Some(comment) = serializer.pending_comment.take() {
+        serializer.write_comment(&mut out, &comment);
+    }
+    out.push_str(&serializer.output);
+    if options.trailing_newline {
+        out.push('\n');
+    }
+    if options.validate_output {
+        validate(value, &out, &options)?;
+    }
+    Ok(out)
+}
+
+/// Checks that `out`, written with `options`, reads back as `value` written
+/// in the plainest layout
+fn validate<T>(value: &T, out: &str, options: &SerializeOptions) -> Result<()>
+where
+    T: Serialize,
+{
+    let invalid = |message: String| Error::InvalidOutput {
+        message,
+        text: out.to_string(),
+    };
+    let (_, written) = crate::parse_huml(out).map_err(|err| invalid(err.to_string()))?;
+
+    let plain_options = SerializeOptions {
+        max_inline_items: Some(0),
+        max_inline_fields: None,
+        multiline_strings: false,
+        header_comment: None,
+        validate_output: false,
+        ..options.clone()
+    };
+    let plain = to_string_with(value, plain_options)?;
+    let (_, expected) = crate::parse_huml(&plain)
+        .map_err(|err| invalid(format!("{err} in the plain layout:\n{plain}")))?;
+    if !written.root.semantically_equals(&expected.root) {
+        return Err(invalid(format!(
+            "reads back differently from the plain layout:\n{plain}"
+        )));
+    }
+    Ok(())
+}
+
+/// Serialize a value into a [`HumlDocument`], to post-process the value tree
+/// (merge, annotate, validate) before writing it out
+///
+/// The document has no version; set [`HumlDocument::version`] to have
+/// [`HumlDocument::to_huml_string`] write a `%HUML` line.
+///
+/// ```
+/// use huml_rs::serde::to_document;
+/// use std::collections::BTreeMap;
+///
+/// let mut doc = to_document(&BTreeMap::from([("port", 8080)])).unwrap();
+/// let dict = doc.root.as_dict_mut().unwrap();
+/// dict.insert("host".to_string(), "localhost".into());
+/// doc.version = Some("0.2.0".to_string());
+/// assert_eq!(doc.to_huml_string(), "%HUML v0.2.0\nport: 8080\nhost: \"localhost\"\n");
+/// ```
+pub fn to_document<T>(value: &T) -> Result<HumlDocument>
+where
+    T: Serialize,
+{
+    to_document_with(value, SerializeOptions::default())
+}
+
+/// Serialize a value into a [`HumlDocument`] using the given options
+///
+/// Options that affect the value, like [`SerializeOptions::skip_none`] and
+/// [`Hex`], carry over; layout options only matter for how multiline strings
+/// remember their style. Comments are not part of the value tree, so
+/// [`Commented`] values lose their comments.
+pub fn to_document_with<T>(value: &T, options: SerializeOptions) -> Result<HumlDocument>
+where
+    T: Serialize,
+{
+    let text = to_string_with(value, options)?;
+    match crate::parse_huml(&text) {
+        Ok((_, document)) => Ok(document),
+        Err(err) => Err(Error::Message(format!(
+            "serialized text did not parse back: {err}"
+        ))),
+    }
+}
+
+/// Serialize a value into its canonical HUML text, for hashing, signing or
+/// golden files
+///
+/// The same value always produces the same bytes, whatever the iteration
+/// order of its maps or the field order of its structs. See
+/// [`SerializeOptions::canonical`] for the exact form.
+///
+/// ```
+/// use huml_rs::serde::to_canonical_string;
+/// use std::collections::HashMap;
+///
+/// let map = HashMap::from([("b", 2.0), ("a", 1.0)]);
+/// assert_eq!(to_canonical_string(&map).unwrap(), "a: 1.0\nb: 2.0\n");
+/// ```
+pub fn to_canonical_string<T>(value: &T) -> Result<String>
+where
+    T: Serialize,
+{
+    to_string_with(value, SerializeOptions::canonical())
+}
+
+/// Serialize a value to HUML with one value per line
+///
+/// See [`SerializeOptions::pretty`].
+///
+/// ```
+/// use huml_rs::serde::to_string_pretty;
+/// use std::collections::BTreeMap;
+///
+/// let map = BTreeMap::from([("ports", vec![80, 443])]);
+/// assert_eq!(
+///     to_string_pretty(&map).unwrap(),
+///     "ports::\n  - 80\n  - 443\n"
+/// );
+/// ```
+pub fn to_string_pretty<T>(value: &T) -> Result<String>
+where
+    T: Serialize,
+{
+    to_string_with(value, SerializeOptions::pretty())
+}
+
+/// Serialize a value to HUML on as few lines as possible
+///
+/// See [`SerializeOptions::compact`].
+///
+/// ```
+/// use huml_rs::serde::to_string_compact;
+/// use std::collections::BTreeMap;
+///
+/// let point = BTreeMap::from([("x", 1), ("y", 2)]);
+/// let map = BTreeMap::from([("origin", point)]);
+/// assert_eq!(to_string_compact(&map).unwrap(), "origin:: x: 1, y: 2\n");
+/// ```
+pub fn to_string_compact<T>(value: &T) -> Result<String>
+where
+    T: Serialize,
+{
+    to_string_with(value, SerializeOptions::compact())
 }
 
 impl<'a> ser::Serializer for &'a mut Serializer {
@@ -191,6 +787,11 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         Ok(())
     }
 
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        self.output.push_str(&v.to_string());
+        Ok(())
+    }
+
     fn serialize_u8(self, v: u8) -> Result<()> {
         self.serialize_u64(v as u64)
     }
@@ -204,27 +805,26 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_u64(self, v: u64) -> Result<()> {
+        let _ = match self.radix.take() {
+            None => write!(self.output, "{v}"),
+            Some(Radix::Hexadecimal) => write!(self.output, "0x{v:x}"),
+            Some(Radix::Octal) => write!(self.output, "0o{v:o}"),
+            Some(Radix::Binary) => write!(self.output, "0b{v:b}"),
+        };
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
         self.output.push_str(&v.to_string());
         Ok(())
     }
 
     fn serialize_f32(self, v: f32) -> Result<()> {
-        self.serialize_f64(v as f64)
+        self.write_float(v)
     }
 
     fn serialize_f64(self, v: f64) -> Result<()> {
-        if v.is_nan() {
-            self.output.push_str("nan");
-        } else if v.is_infinite() {
-            if v.is_sign_positive() {
-                self.output.push_str("inf");
-            } else {
-                self.output.push_str("-inf");
-            }
-        } else {
-            self.output.push_str(&v.to_string());
-        }
-        Ok(())
+        self.write_float(v)
     }
 
     fn serialize_char(self, v: char) -> Result<()> {
@@ -232,19 +832,40 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_str(self, v: &str) -> Result<()> {
+        if self.options.multiline_strings
+            && self.inline_depth == 0
+            && v.contains('\n')
+            && crate::emit::fits_block(v)
+            && (v.is_ascii() || !self.options.ascii_only)
+        {
+            crate::emit::write_block_string(
+                &mut self.output,
+                v,
+                "\"\"\"",
+                self.indent_level.saturating_sub(1),
+            );
+            self.output.pop();
+            return Ok(());
+        }
         self.write_string(v)
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<()> {
-        use ser::SerializeSeq;
-        let mut seq = self.serialize_seq(Some(v.len()))?;
-        for byte in v {
-            seq.serialize_element(byte)?;
+        match self.options.bytes_encoding.encode(v) {
+            Some(encoded) => self.write_string(&encoded),
+            None => {
+                use ser::SerializeSeq;
+                let mut seq = self.serialize_seq(Some(v.len()))?;
+                for byte in v {
+                    seq.serialize_element(byte)?;
+                }
+                seq.end()
+            }
         }
-        seq.end()
     }
 
     fn serialize_none(self) -> Result<()> {
+        self.wrote_none = true;
         self.serialize_unit()
     }
 
@@ -273,11 +894,23 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.serialize_str(variant)
     }
 
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(self)
+        if name == SECRET_NAME && self.options.redact_secrets {
+            return self.write_string(REDACTED);
+        }
+        self.radix = match name {
+            _ if self.options.canonical => None,
+            HEX_NAME => Some(Radix::Hexadecimal),
+            OCTAL_NAME => Some(Radix::Octal),
+            BINARY_NAME => Some(Radix::Binary),
+            _ => None,
+        };
+        let result = value.serialize(&mut *self);
+        self.radix = None;
+        result
     }
 
     fn serialize_newtype_variant<T>(
@@ -290,18 +923,20 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        self.output.push_str(variant);
-        self.output.push_str(": ");
-        value.serialize(self)?;
-        Ok(())
+        let mut map = MapSerializer::new(self, false);
+        ser::SerializeMap::serialize_entry(&mut map, variant, value)?;
+        ser::SerializeMap::end(map)
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
         if len == Some(0) {
-            self.output.push_str("[]");
             Ok(SeqSerializer::empty(self))
         } else {
-            Ok(SeqSerializer::new(self))
+            let block = matches!(
+                (len, self.options.max_inline_items),
+                (Some(len), Some(max)) if len > max
+            );
+            Ok(SeqSerializer::new(self, block))
         }
     }
 
@@ -324,9 +959,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        self.output.push_str(variant);
-        self.output.push_str(": ");
-        Ok(TupleVariantSerializer::new(self))
+        Ok(TupleVariantSerializer::new(self, variant))
     }
 
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
@@ -334,12 +967,22 @@ impl<'a> ser::Serializer for &'a mut Serializer {
             self.output.push_str("{}");
             Ok(MapSerializer::empty(self))
         } else {
-            Ok(MapSerializer::new(self, false))
+            let sort = self.options.sort_keys;
+            Ok(MapSerializer::new(self, sort))
         }
     }
 
-    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
-        self.serialize_map(Some(len))
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        if name == COMMENTED_NAME {
+            return Ok(MapSerializer::commented(self));
+        }
+        if len == 0 {
+            self.output.push_str("{}");
+            Ok(MapSerializer::empty(self))
+        } else {
+            let sort = self.options.canonical;
+            Ok(MapSerializer::new(self, sort))
+        }
     }
 
     fn serialize_struct_variant(
@@ -349,34 +992,35 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        self.output.push_str(variant);
-        self.output.push_str("::");
-        self.output.push('\n');
-        Ok(StructVariantSerializer::new(self))
+        Ok(StructVariantSerializer::new(self, variant))
     }
 }
 
 /// Serializer for sequences (lists, tuples)
 pub struct SeqSerializer<'a> {
     ser: &'a mut Serializer,
-    first: bool,
     empty: bool,
+    block: bool,
+    /// Serialized items, written out by `end` once the layout is known
+    items: Vec<Serializer>,
 }
 
 impl<'a> SeqSerializer<'a> {
-    fn new(ser: &'a mut Serializer) -> Self {
+    fn new(ser: &'a mut Serializer, block: bool) -> Self {
         Self {
             ser,
-            first: true,
             empty: false,
+            block,
+            items: Vec::new(),
         }
     }
 
     fn empty(ser: &'a mut Serializer) -> Self {
         Self {
             ser,
-            first: true,
             empty: true,
+            block: false,
+            items: Vec::new(),
         }
     }
 }
@@ -393,21 +1037,90 @@ impl<'a> ser::SerializeSeq for SeqSerializer<'a> {
             return Ok(());
         }
 
-        if self.first {
-            self.first = false;
+        let mut child = if self.block {
+            self.ser.child()
         } else {
-            self.ser.output.push_str(", ");
+            self.ser.inline_child()
+        };
+        child.line_prefix = self.ser.indent().len() + "- :: ".len();
+        value.serialize(&mut child)?;
+        // Structs and nested lists can only be items of a block list
+        if child.layout != Layout::Scalar {
+            self.block = true;
         }
-
-        value.serialize(&mut *self.ser)?;
+        self.items.push(child);
         Ok(())
     }
 
-    fn end(self) -> Result<()> {
+    fn end(mut self) -> Result<()> {
+        if self.items.is_empty() {
+            // Also covers sequences of unknown length that turned out empty
+            self.ser.output.push_str("[]");
+            self.ser.layout = Layout::Scalar;
+            return Ok(());
+        }
+        self.choose_layout();
+        self.write_items();
         Ok(())
     }
 }
 
+impl<'a> SeqSerializer<'a> {
+    /// Decides between an inline and a block list once all items are known
+    fn choose_layout(&mut self) {
+        if !self.block
+            && let Some(width) = self.ser.options.max_inline_width
+        {
+            let inline_width = self
+                .items
+                .iter()
+                .map(|item| item.output.len())
+                .sum::<usize>()
+                + ", ".len() * (self.items.len() - 1);
+            self.block = self.ser.line_prefix + inline_width > width;
+        }
+        // At the root there's no `::` to mark a one-item inline list, so it
+        // would read back as a scalar
+        if self.items.len() == 1 && self.ser.indent_level == 0 && self.ser.output.is_empty() {
+            self.block = true;
+        }
+    }
+
+    fn write_items(&mut self) {
+        for (i, item) in self.items.iter().enumerate() {
+            if !self.block {
+                // Inline lists have nowhere to put comments
+                if i > 0 {
+                    self.ser.output.push_str(", ");
+                }
+                self.ser.output.push_str(&item.output);
+                continue;
+            }
+
+            if i > 0 {
+                self.ser.newline();
+            }
+            if let Some(comment) = &item.pending_comment {
+                let mut lines = String::new();
+                self.ser.write_comment(&mut lines, comment);
+                self.ser.output.push_str(&lines);
+            }
+            self.ser.output.push_str(&self.ser.indent());
+            self.ser.output.push_str(match item.layout {
+                Layout::Block => "- ::\n",
+                Layout::Inline => "- :: ",
+                Layout::Scalar => "- ",
+            });
+            self.ser.output.push_str(&item.output);
+        }
+        self.ser.layout = if self.block {
+            Layout::Block
+        } else {
+            Layout::Inline
+        };
+    }
+}
+
 impl<'a> ser::SerializeTuple for SeqSerializer<'a> {
     type Ok = ();
     type Error = Error;
@@ -440,15 +1153,21 @@ impl<'a> ser::SerializeTupleStruct for SeqSerializer<'a> {
     }
 }
 
-/// Serializer for tuple variants
+/// Serializer for tuple variants, written as a one-entry map from the
+/// variant name to the list of fields
 pub struct TupleVariantSerializer<'a> {
-    ser: &'a mut Serializer,
-    first: bool,
+    seq: SeqSerializer<'a>,
+    variant: &'static str,
 }
 
 impl<'a> TupleVariantSerializer<'a> {
-    fn new(ser: &'a mut Serializer) -> Self {
-        Self { ser, first: true }
+    fn new(ser: &'a mut Serializer, variant: &'static str) -> Self {
+        ser.line_prefix = ser.indent().len() + variant.len() + ":: ".len();
+        ser.increase_indent();
+        Self {
+            seq: SeqSerializer::new(ser, false),
+            variant,
+        }
     }
 }
 
@@ -460,46 +1179,166 @@ impl<'a> ser::SerializeTupleVariant for TupleVariantSerializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        if self.first {
-            self.first = false;
-        } else {
-            self.ser.output.push_str(", ");
-        }
-        value.serialize(&mut *self.ser)?;
-        Ok(())
+        ser::SerializeSeq::serialize_element(&mut self.seq, value)
     }
 
-    fn end(self) -> Result<()> {
+    fn end(mut self) -> Result<()> {
+        let seq = &mut self.seq;
+        seq.ser.decrease_indent();
+        seq.ser.output.push_str(&seq.ser.indent());
+        seq.ser.output.push_str(self.variant);
+        if seq.items.is_empty() {
+            seq.ser.output.push_str(":: []");
+        } else {
+            seq.choose_layout();
+            seq.ser
+                .output
+                .push_str(if seq.block { "::\n" } else { ":: " });
+            seq.ser.increase_indent();
+            seq.write_items();
+            seq.ser.decrease_indent();
+        }
+        seq.ser.layout = Layout::Block;
         Ok(())
     }
 }
 
 /// Serializer for maps and structs
+///
+/// Each value is serialized into a child serializer one level deeper, so the
+/// `:`/`::` indicator can be chosen from its layout before the entry is
+/// written.
 pub struct MapSerializer<'a> {
     ser: &'a mut Serializer,
-    first: bool,
     empty: bool,
+    sort: bool,
+    /// The written key of the entry in progress, and the key it sorts by
+    key: Option<(String, String)>,
+    /// Finished entries, written out by `end` once the layout is known
+    entries: Vec<MapEntry>,
+    /// Whether the entries are written as an inline dict
     inline: bool,
+    /// Keys written so far, which HUML doesn't allow twice
+    seen: HashSet<String>,
+    /// Whether this serializes the fields of a [`Commented`] wrapper rather
+    /// than a map
+    wrapper: bool,
+    /// The comment of a [`Commented`] wrapper
+    comment: Option<String>,
 }
 
 impl<'a> MapSerializer<'a> {
-    fn new(ser: &'a mut Serializer, inline: bool) -> Self {
+    fn new(ser: &'a mut Serializer, sort: bool) -> Self {
         Self {
             ser,
-            first: true,
             empty: false,
-            inline,
+            sort,
+            key: None,
+            entries: Vec::new(),
+            inline: false,
+            seen: HashSet::new(),
+            wrapper: false,
+            comment: None,
         }
     }
 
     fn empty(ser: &'a mut Serializer) -> Self {
         Self {
-            ser,
-            first: true,
             empty: true,
-            inline: false,
+            ..Self::new(ser, false)
+        }
+    }
+
+    fn commented(ser: &'a mut Serializer) -> Self {
+        Self {
+            wrapper: true,
+            ..Self::new(ser, false)
         }
     }
+
+    /// Sorts the entries if asked to, and decides between an inline and a
+    /// block dict
+    fn choose_layout(&mut self) {
+        let options = self.ser.options.clone();
+        let order = &options.key_order;
+        if self.sort || !order.is_empty() {
+            // Unlisted keys rank after every listed one
+            let rank = |key: &str| order.iter().position(|k| *k == key).unwrap_or(order.len());
+            let sort = self.sort;
+            self.entries.sort_by(|a, b| {
+                let by_rank = rank(&a.sort_key).cmp(&rank(&b.sort_key));
+                if sort {
+                    by_rank.then_with(|| a.sort_key.cmp(&b.sort_key))
+                } else {
+                    by_rank
+                }
+            });
+        }
+        let Some(max) = self.ser.options.max_inline_fields else {
+            return;
+        };
+        // At the root an inline dict has no `::` in front of it
+        if self.entries.is_empty()
+            || self.entries.len() > max
+            || (self.ser.indent_level == 0 && self.ser.output.is_empty())
+        {
+            return;
+        }
+        let Some(width) = self
+            .entries
+            .iter()
+            .map(|entry| entry.inline.as_ref().map(String::len))
+            .sum::<Option<usize>>()
+        else {
+            return;
+        };
+        let width = width + ", ".len() * (self.entries.len() - 1);
+        self.inline = self
+            .ser
+            .options
+            .max_inline_width
+            .is_none_or(|max_width| self.ser.line_prefix + width <= max_width);
+    }
+
+    fn write_entries(&mut self) {
+        if self.entries.is_empty() {
+            if !self.empty {
+                // Every entry was skipped
+                self.ser.output.push_str("{}");
+            }
+            self.ser.layout = Layout::Scalar;
+            return;
+        }
+        for (i, entry) in self.entries.iter().enumerate() {
+            if self.inline {
+                if i > 0 {
+                    self.ser.output.push_str(", ");
+                }
+                if let Some(inline) = &entry.inline {
+                    self.ser.output.push_str(inline);
+                }
+                continue;
+            }
+            if i > 0 {
+                self.ser.newline();
+            }
+            self.ser.output.push_str(&entry.block);
+        }
+        self.ser.layout = if self.inline {
+            Layout::Inline
+        } else {
+            Layout::Block
+        };
+    }
+}
+
+/// A serialized map entry
+struct MapEntry {
+    sort_key: String,
+    /// The entry on its own lines, with any comment above it
+    block: String,
+    /// The entry as `key: value` for an inline dict, if its value allows it
+    inline: Option<String>,
 }
 
 impl<'a> ser::SerializeMap for MapSerializer<'a> {
@@ -514,33 +1353,32 @@ impl<'a> ser::SerializeMap for MapSerializer<'a> {
             return Ok(());
         }
 
-        if self.first {
-            self.first = false;
-        } else if self.inline {
-            self.ser.output.push_str(", ");
-        } else {
-            self.ser.newline();
-        }
-
-        if !self.inline {
-            self.ser.output.push_str(&self.ser.indent());
-        }
-
-        // Serialize the key - for HUML, keys should be unquoted if possible
-        let start_pos = self.ser.output.len();
-        key.serialize(&mut *self.ser)?;
-
-        // Check if we need to unquote the key (if it's a simple string)
-        let key_str = self.ser.output[start_pos..].to_string();
-        if key_str.starts_with('"') && key_str.ends_with('"') {
-            let unquoted = &key_str[1..key_str.len() - 1];
-            if is_valid_unquoted_key(unquoted) {
-                // Replace the quoted key with unquoted version
-                self.ser.output.truncate(start_pos);
-                self.ser.output.push_str(unquoted);
-            }
+        let mut child = self.ser.inline_child();
+        key.serialize(&mut child)?;
+        if child.layout != Layout::Scalar || child.output == "{}" || child.output == "[]" {
+            return Err(Error::Message("map keys must be scalars".to_string()));
         }
 
+        // Strings come out quoted; read them back to get the key text. Other
+        // scalars such as numbers are used as written.
+        let text = match crate::parse_scalar(&child.output) {
+            Ok((_, crate::HumlValue::String(s))) => s.into_string(),
+            _ => child.output,
+        };
+        let written = if is_valid_unquoted_key(&text) {
+            text.clone()
+        } else {
+            let mut quoted = String::new();
+            crate::emit::write_quoted(
+                &mut quoted,
+                &text,
+                self.ser.options.unicode_escape,
+                self.ser.options.escape_profile,
+                self.ser.options.ascii_only,
+            );
+            quoted
+        };
+        self.key = Some((written, text));
         Ok(())
     }
 
@@ -551,53 +1389,61 @@ impl<'a> ser::SerializeMap for MapSerializer<'a> {
         if self.empty {
             return Ok(());
         }
-
-        // Check what kind of value we're serializing
-        let start_pos = self.ser.output.len();
-
-        // Serialize the value to see what it looks like
-        let value_start = self.ser.output.len();
-        value.serialize(&mut *self.ser)?;
-        let value_str = self.ser.output[value_start..].to_string();
-
-        // Determine if we need special HUML syntax
-        if value_str.contains('\n') {
-            // Multi-line value - use :: syntax
-            self.ser.output.insert_str(start_pos, "::");
-            self.ser.output.insert(start_pos + 2, '\n');
-            // Re-indent all lines in the value
-            let lines: Vec<&str> = value_str.lines().collect();
-            if lines.len() > 1 {
-                self.ser.output.truncate(value_start + 3); // Keep "::\n"
-                self.ser.increase_indent();
-                for (i, line) in lines.iter().enumerate() {
-                    if i > 0 {
-                        self.ser.newline();
-                    }
-                    if !line.trim().is_empty() {
-                        self.ser.output.push_str(&self.ser.indent());
-                        self.ser.output.push_str(line.trim());
-                    }
-                }
-                self.ser.decrease_indent();
-            }
-        } else if value_str.contains(", ")
-            && !value_str.starts_with('{')
-            && !value_str.is_empty()
-            && value_str != "[]"
-            && value_str != "{}"
+        let (key, sort_key) = self
+            .key
+            .take()
+            .ok_or_else(|| Error::Message("map value without a key".to_string()))?;
+
+        // Serialize the value to see how it is laid out
+        let mut child = self.ser.child();
+        child.line_prefix = self.ser.indent().len() + key.len() + ":: ".len();
+        value.serialize(&mut child)?;
+        if child.wrote_none && self.ser.options.skip_none {
+            return Ok(());
+        }
+        if self.ser.options.skip_empty
+            && child.layout == Layout::Scalar
+            && (child.output == "[]" || child.output == "{}")
         {
-            // Inline list - use :: syntax
-            self.ser.output.insert_str(start_pos, ":: ");
-        } else {
-            // Regular scalar value - use : syntax
-            self.ser.output.insert_str(start_pos, ": ");
+            return Ok(());
         }
 
+        let mut entry = String::new();
+        if let Some(comment) = &child.pending_comment {
+            self.ser.write_comment(&mut entry, comment);
+        }
+        entry.push_str(&self.ser.indent());
+        entry.push_str(&key);
+        entry.push_str(match child.layout {
+            // Multi-line value - use :: syntax, children are already indented
+            Layout::Block => "::\n",
+            Layout::Inline => ":: ",
+            // Scalars and multiline strings start on the key's line
+            Layout::Scalar => ": ",
+        });
+        entry.push_str(&child.output);
+        if !self.seen.insert(sort_key.clone()) {
+            return Err(Error::DuplicateKey(sort_key));
+        }
+        // Inline dicts hold scalars on one line, and have nowhere to put
+        // comments
+        let inline = (child.layout == Layout::Scalar
+            && child.pending_comment.is_none()
+            && !child.output.contains('\n')
+            && child.output != "[]"
+            && child.output != "{}")
+            .then(|| format!("{key}: {}", child.output));
+        self.entries.push(MapEntry {
+            sort_key,
+            block: entry,
+            inline,
+        });
         Ok(())
     }
 
-    fn end(self) -> Result<()> {
+    fn end(mut self) -> Result<()> {
+        self.choose_layout();
+        self.write_entries();
         Ok(())
     }
 }
@@ -610,24 +1456,53 @@ impl<'a> ser::SerializeStruct for MapSerializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        ser::SerializeMap::serialize_entry(self, key, value)
+        if !self.wrapper {
+            let key = match self.ser.options.key_case {
+                Some(case) => case.apply(key),
+                None => Cow::Borrowed(key),
+            };
+            return ser::SerializeMap::serialize_entry(self, &*key, value);
+        }
+        if key != COMMENTED_COMMENT {
+            return value.serialize(&mut *self.ser);
+        }
+        // Write the comment as a quoted string and read it back
+        let mut child = self.ser.inline_child();
+        value.serialize(&mut child)?;
+        match crate::parse_scalar(&child.output) {
+            Ok((_, crate::HumlValue::String(text))) => {
+                self.comment = Some(text.into_string());
+                Ok(())
+            }
+            _ => Err(Error::Message("comment must be a string".to_string())),
+        }
     }
 
     fn end(self) -> Result<()> {
+        if self.wrapper {
+            self.ser.pending_comment = self.comment;
+            return Ok(());
+        }
         ser::SerializeMap::end(self)
     }
 }
 
-/// Serializer for struct variants
+/// Serializer for struct variants, written as a one-entry map from the
+/// variant name to the fields
 pub struct StructVariantSerializer<'a> {
-    ser: &'a mut Serializer,
-    first: bool,
+    map: MapSerializer<'a>,
+    variant: &'static str,
 }
 
 impl<'a> StructVariantSerializer<'a> {
-    fn new(ser: &'a mut Serializer) -> Self {
+    fn new(ser: &'a mut Serializer, variant: &'static str) -> Self {
+        ser.line_prefix = ser.indent().len() + variant.len() + ":: ".len();
         ser.increase_indent();
-        Self { ser, first: true }
+        let sort = ser.options.canonical;
+        Self {
+            map: MapSerializer::new(ser, sort),
+            variant,
+        }
     }
 }
 
@@ -639,22 +1514,208 @@ impl<'a> ser::SerializeStructVariant for StructVariantSerializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        if self.first {
-            self.first = false;
+        ser::SerializeStruct::serialize_field(&mut self.map, key, value)
+    }
+
+    fn end(mut self) -> Result<()> {
+        let map = &mut self.map;
+        map.choose_layout();
+        map.ser.decrease_indent();
+        map.ser.output.push_str(&map.ser.indent());
+        map.ser.output.push_str(self.variant);
+        map.ser.output.push_str(if map.entries.is_empty() {
+            ": "
+        } else if map.inline {
+            ":: "
         } else {
-            self.ser.newline();
+            "::\n"
+        });
+        map.ser.increase_indent();
+        map.write_entries();
+        map.ser.decrease_indent();
+        map.ser.layout = Layout::Block;
+        Ok(())
+    }
+}
+
+const COMMENTED_NAME: &str = "$huml::private::Commented";
+const COMMENTED_COMMENT: &str = "comment";
+const COMMENTED_VALUE: &str = "value";
+
+/// Attaches a comment to a value, written as `# ` lines above its entry
+///
+/// The comment goes above the key of a map entry or struct field, above the
+/// `- ` of a block list item, or at the top of the document for the root.
+/// Values inside inline lists have nowhere to put a comment, so it's dropped.
+/// Other serializers see a struct with `comment` and `value` fields.
+///
+/// ```
+/// use huml_rs::serde::{to_string, Commented};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Server {
+///     port: Commented<u16>,
+/// }
+///
+/// let server = Server { port: Commented::new("Public port", 8080) };
+/// assert_eq!(to_string(&server).unwrap(), "# Public port\nport: 8080\n");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commented<T> {
+    /// The comment text; each line becomes a `# ` line
+    pub comment: String,
+    /// The commented value
+    pub value: T,
+}
+
+impl<T> Commented<T> {
+    /// Attach `comment` to `value`
+    pub fn new(comment: impl Into<String>, value: T) -> Self {
+        Self {
+            comment: comment.into(),
+            value,
         }
+    }
+}
 
-        self.ser.output.push_str(&self.ser.indent());
-        self.ser.output.push_str(key);
-        self.ser.output.push_str(": ");
-        value.serialize(&mut *self.ser)?;
-        Ok(())
+impl<T: Serialize> Serialize for Commented<T> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        use ser::SerializeStruct;
+        let mut state = serializer.serialize_struct(COMMENTED_NAME, 2)?;
+        state.serialize_field(COMMENTED_COMMENT, &self.comment)?;
+        state.serialize_field(COMMENTED_VALUE, &self.value)?;
+        state.end()
     }
+}
 
-    fn end(self) -> Result<()> {
-        self.ser.decrease_indent();
-        Ok(())
+const HEX_NAME: &str = "$huml::private::Hex";
+const OCTAL_NAME: &str = "$huml::private::Octal";
+const BINARY_NAME: &str = "$huml::private::Binary";
+
+macro_rules! prefixed_integer {
+    ($(#[$doc:meta])* $ty:ident, $name:ident, $example:literal) => {
+        $(#[$doc])*
+        ///
+        /// Other serializers see the plain integer, and it deserializes from
+        /// any integer, prefixed or not.
+        ///
+        /// ```
+        /// use huml_rs::serde::{to_string, Binary, Hex, Octal};
+        ///
+        #[doc = concat!("assert_eq!(to_string(&", $example, ");")]
+        /// ```
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $ty(pub u64);
+
+        impl From<u64> for $ty {
+            fn from(value: u64) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$ty> for u64 {
+            fn from(value: $ty) -> Self {
+                value.0
+            }
+        }
+
+        impl Serialize for $ty {
+            fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+            where
+                S: ser::Serializer,
+            {
+                serializer.serialize_newtype_struct($name, &self.0)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                u64::deserialize(deserializer).map(Self)
+            }
+        }
+    };
+}
+
+prefixed_integer!(
+    /// An unsigned integer written in hexadecimal, like `0xff`
+    Hex,
+    HEX_NAME,
+    "Hex(255)).unwrap(), \"0xff\\n\""
+);
+prefixed_integer!(
+    /// An unsigned integer written in octal, like `0o755`
+    Octal,
+    OCTAL_NAME,
+    "Octal(0o755)).unwrap(), \"0o755\\n\""
+);
+prefixed_integer!(
+    /// An unsigned integer written in binary, like `0b101`
+    Binary,
+    BINARY_NAME,
+    "Binary(5)).unwrap(), \"0b101\\n\""
+);
+
+const SECRET_NAME: &str = "$huml::private::Secret";
+const REDACTED: &str = "<redacted>";
+
+/// A value that is written as `"<redacted>"` when
+/// [`SerializeOptions::redact_secrets`] is set
+///
+/// Otherwise it serializes and deserializes as the wrapped value, with every
+/// serializer. Its `Debug` output is redacted too.
+///
+/// ```
+/// use huml_rs::serde::{to_string, to_string_with, Secret, SerializeOptions};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Database {
+///     user: String,
+///     password: Secret<String>,
+/// }
+///
+/// let db = Database {
+///     user: "app".to_string(),
+///     password: Secret("hunter2".to_string()),
+/// };
+/// let options = SerializeOptions::default().with_redact_secrets();
+/// assert_eq!(
+///     to_string_with(&db, options).unwrap(),
+///     "user: \"app\"\npassword: \"<redacted>\"\n"
+/// );
+/// assert_eq!(to_string(&db).unwrap(), "user: \"app\"\npassword: \"hunter2\"\n");
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Secret<T>(pub T);
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Secret").field(&REDACTED).finish()
+    }
+}
+
+impl<T: Serialize> Serialize for Secret<T> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_newtype_struct(SECRET_NAME, &self.0)
+    }
+}
+
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Secret<T> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Secret)
     }
 }
 
@@ -664,22 +1725,24 @@ fn is_valid_unquoted_key(s: &str) -> bool {
         return false;
     }
 
-    // First character must be alphabetic or underscore
+    // First character must be an ASCII letter or underscore, so that keys
+    // like `123` are not mistaken for numbers by readers
     let mut chars = s.chars();
     match chars.next() {
-        Some(c) if c.is_alphabetic() || c == '_' => {}
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
         _ => return false,
     }
 
-    // Remaining characters must be alphanumeric, underscore, or hyphen
-    chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    // Remaining characters must be ASCII alphanumeric, underscore, or hyphen.
+    // Parsers only accept Unicode bare keys when asked to.
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde::Serialize;
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
 
     #[derive(Serialize)]
     struct Person {
@@ -736,7 +1799,7 @@ mod tests {
     fn test_serialize_enum_variants() {
         let active = Status::Active;
         let huml = to_string(&active).unwrap();
-        assert_eq!(huml, "\"Active\"");
+        assert_eq!(huml, "\"Active\"\n");
 
         let inactive = Status::Inactive {
             reason: "maintenance".to_string(),
@@ -752,33 +1815,1031 @@ mod tests {
 
     #[test]
     fn test_serialize_primitive_types() {
-        assert_eq!(to_string(&"hello").unwrap(), "\"hello\"");
-        assert_eq!(to_string(&42).unwrap(), "42");
-        assert_eq!(to_string(&3.14).unwrap(), "3.14");
-        assert_eq!(to_string(&true).unwrap(), "true");
-        assert_eq!(to_string(&false).unwrap(), "false");
+        assert_eq!(to_string(&"hello").unwrap(), "\"hello\"\n");
+        assert_eq!(to_string(&42).unwrap(), "42\n");
+        assert_eq!(to_string(&2.5).unwrap(), "2.5\n");
+        assert_eq!(to_string(&true).unwrap(), "true\n");
+        assert_eq!(to_string(&false).unwrap(), "false\n");
 
         let empty_list: Vec<i32> = vec![];
-        assert_eq!(to_string(&empty_list).unwrap(), "[]");
+        assert_eq!(to_string(&empty_list).unwrap(), "[]\n");
 
         let list = vec![1, 2, 3];
-        assert_eq!(to_string(&list).unwrap(), "1, 2, 3");
+        assert_eq!(to_string(&list).unwrap(), "1, 2, 3\n");
+    }
+
+    #[test]
+    fn test_serialize_128_bit_integers() {
+        assert_eq!(to_string(&i128::MIN).unwrap(), format!("{}\n", i128::MIN));
+        assert_eq!(to_string(&u128::MAX).unwrap(), format!("{}\n", u128::MAX));
+
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Ids {
+            signed: i128,
+            unsigned: u128,
+        }
+        let ids = Ids {
+            signed: -(1 << 100),
+            unsigned: u128::MAX - 1,
+        };
+        assert_eq!(crate::serde::round_trip(&ids).unwrap(), ids);
     }
 
     #[test]
     fn test_serialize_special_numbers() {
-        assert_eq!(to_string(&f64::NAN).unwrap(), "nan");
-        assert_eq!(to_string(&f64::INFINITY).unwrap(), "inf");
-        assert_eq!(to_string(&f64::NEG_INFINITY).unwrap(), "-inf");
+        assert_eq!(to_string(&f64::NAN).unwrap(), "nan\n");
+        assert_eq!(to_string(&f64::INFINITY).unwrap(), "inf\n");
+        assert_eq!(to_string(&f64::NEG_INFINITY).unwrap(), "-inf\n");
+
+        let strict = SerializeOptions::default().with_reject_non_finite();
+        assert_eq!(
+            to_string_with(&vec![1.5, 2.0], strict.clone()).unwrap(),
+            "1.5, 2.0\n"
+        );
+        let err = to_string_with(&vec![1.5, f64::NEG_INFINITY], strict.clone()).unwrap_err();
+        assert!(matches!(err, Error::NonFiniteFloat(v) if v == f64::NEG_INFINITY));
+        assert!(to_string_with(&f32::NAN, strict).is_err());
+    }
+
+    #[test]
+    fn test_floats_keep_their_type() {
+        assert_eq!(to_string(&1.0).unwrap(), "1.0\n");
+        assert_eq!(to_string(&-0.0).unwrap(), "-0.0\n");
+        assert_eq!(to_string(&1e100).unwrap(), "1e100\n");
+        assert_eq!(to_string(&0.1f32).unwrap(), "0.1\n");
+        assert_eq!(
+            crate::parse_scalar(&to_string(&3.0).unwrap()).unwrap().1,
+            crate::HumlValue::Number(crate::HumlNumber::Float(3.0))
+        );
+
+        let options = SerializeOptions::default().with_float_precision(2);
+        assert_eq!(to_string_with(&2.0, options).unwrap(), "2.00\n");
+        let options = SerializeOptions::default().with_float_precision(0);
+        assert_eq!(to_string_with(&2.6, options).unwrap(), "3.0\n");
+    }
+
+    #[test]
+    fn test_prefixed_integer_wrappers() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct File {
+            mode: Octal,
+            mask: Hex,
+            flags: Vec<Binary>,
+            size: u64,
+        }
+        let file = File {
+            mode: Octal(0o644),
+            mask: Hex(0xdead_beef),
+            flags: vec![Binary(0b1010), Binary(0)],
+            size: 16,
+        };
+        let huml = to_string(&file).unwrap();
+        assert_eq!(
+            huml,
+            "mode: 0o644\nmask: 0xdeadbeef\nflags:: 0b1010, 0b0\nsize: 16\n"
+        );
+        assert_eq!(crate::serde::from_str::<File>(&huml).unwrap(), file);
+    }
+
+    #[test]
+    fn test_skip_none() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Limits {
+            cpu: Option<u32>,
+            memory: Option<u32>,
+        }
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Service {
+            name: Option<String>,
+            limits: Limits,
+            tags: Vec<Option<String>>,
+        }
+        let service = Service {
+            name: None,
+            limits: Limits {
+                cpu: Some(2),
+                memory: None,
+            },
+            tags: vec![None, Some("a".to_string())],
+        };
+        assert_eq!(
+            to_string(&service).unwrap(),
+            "name: null\nlimits::\n  cpu: 2\n  memory: null\ntags:: null, \"a\"\n"
+        );
+
+        let options = SerializeOptions::default().with_skip_none();
+        let huml = to_string_with(&service, options.clone()).unwrap();
+        // List items keep their place, so `None` items are still written
+        assert_eq!(huml, "limits::\n  cpu: 2\ntags:: null, \"a\"\n");
+        assert_eq!(crate::serde::from_str::<Service>(&huml).unwrap(), service);
+
+        let empty = Limits {
+            cpu: None,
+            memory: None,
+        };
+        let huml = to_string_with(&empty, options).unwrap();
+        assert_eq!(huml, "{}\n");
+        assert_eq!(crate::serde::from_str::<Limits>(&huml).unwrap(), empty);
+    }
+
+    #[test]
+    fn test_skip_empty() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug, Default)]
+        #[serde(default)]
+        struct Limits {
+            cpu: Option<u32>,
+            labels: HashMap<String, String>,
+        }
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Service {
+            name: String,
+            args: Vec<String>,
+            #[serde(default)]
+            ports: Vec<u16>,
+            #[serde(default)]
+            limits: Limits,
+            matrix: Vec<Vec<u16>>,
+        }
+        let service = Service {
+            name: "web".to_string(),
+            args: vec!["-v".to_string()],
+            ports: vec![],
+            limits: Limits::default(),
+            matrix: vec![vec![], vec![1]],
+        };
+        let options = SerializeOptions::default()
+            .with_skip_empty()
+            .with_skip_none();
+        let huml = to_string_with(&service, options).unwrap();
+        // Empty list items keep their place
+        assert_eq!(
+            huml,
+            "name: \"web\"\nargs:: \"-v\"\nmatrix::\n  - []\n  - :: 1\n"
+        );
+        assert_eq!(crate::serde::from_str::<Service>(&huml).unwrap(), service);
+
+        let huml = to_string_with(&service, SerializeOptions::default().with_skip_empty()).unwrap();
+        assert!(huml.contains("limits::\n  cpu: null\n"));
+        assert!(!huml.contains("labels"));
+    }
+
+    #[test]
+    fn test_empty_sequence_of_unknown_length() {
+        fn collect<S: serde::Serializer>(
+            ports: &[u16],
+            serializer: S,
+        ) -> std::result::Result<S::Ok, S::Error> {
+            // An iterator without an exact size, so the length is unknown up front
+            serializer.collect_seq(ports.iter().filter(|port| **port != 0))
+        }
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Service {
+            #[serde(serialize_with = "collect")]
+            ports: Vec<u16>,
+            name: String,
+        }
+        let service = Service {
+            ports: vec![],
+            name: "web".to_string(),
+        };
+        let huml = to_string(&service).unwrap();
+        assert_eq!(huml, "ports: []\nname: \"web\"\n");
+        assert_eq!(crate::serde::from_str::<Service>(&huml).unwrap(), service);
+
+        let options = SerializeOptions::default().with_skip_empty();
+        assert_eq!(
+            to_string_with(&service, options).unwrap(),
+            "name: \"web\"\n"
+        );
+    }
+
+    #[test]
+    fn test_secrets_are_redacted_on_request() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Api {
+            tokens: Vec<Secret<String>>,
+            key: Secret<Option<u64>>,
+        }
+        let api = Api {
+            tokens: vec![Secret("a".to_string())],
+            key: Secret(Some(42)),
+        };
+        let huml = to_string(&api).unwrap();
+        assert_eq!(huml, "tokens:: \"a\"\nkey: 42\n");
+        assert_eq!(crate::serde::from_str::<Api>(&huml).unwrap(), api);
+
+        let options = SerializeOptions::default().with_redact_secrets();
+        assert_eq!(
+            to_string_with(&api, options).unwrap(),
+            "tokens:: \"<redacted>\"\nkey: \"<redacted>\"\n"
+        );
+        assert_eq!(format!("{:?}", api.key), "Secret(\"<redacted>\")");
+    }
+
+    #[test]
+    fn test_pretty_and_compact_output() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Server {
+            host: String,
+            ports: Vec<u16>,
+            limits: HashMap<String, u32>,
+            motd: String,
+        }
+        let server = Server {
+            host: "example.com".to_string(),
+            ports: vec![80],
+            limits: HashMap::from([("cpu".to_string(), 2)]),
+            motd: "hello\nworld".to_string(),
+        };
+
+        let pretty = to_string_pretty(&server).unwrap();
+        assert_eq!(
+            pretty,
+            concat!(
+                "host: \"example.com\"\n",
+                "ports::\n",
+                "  - 80\n",
+                "limits::\n",
+                "  cpu: 2\n",
+                "motd: \"\"\"\n",
+                "  hello\n",
+                "  world\n",
+                "\"\"\"\n",
+            )
+        );
+        assert_eq!(crate::serde::from_str::<Server>(&pretty).unwrap(), server);
+
+        let compact = to_string_compact(&server).unwrap();
+        assert_eq!(
+            compact,
+            "host: \"example.com\"\nports:: 80\nlimits:: cpu: 2\nmotd: \"hello\\nworld\"\n"
+        );
+        assert_eq!(crate::serde::from_str::<Server>(&compact).unwrap(), server);
+    }
+
+    #[test]
+    fn test_header_comment() {
+        let map = HashMap::from([("port", Commented::new("listen here", 8080))]);
+        let source = "app.toml";
+        let options = SerializeOptions::default().with_header_comment(format!(
+            "Generated by confgen, do not edit.\n\nSource: {source}"
+        ));
+        let huml = to_string_with(&map, options.clone()).unwrap();
+        assert_eq!(
+            huml,
+            concat!(
+                "# Generated by confgen, do not edit.\n",
+                "#\n",
+                "# Source: app.toml\n",
+                "\n",
+                "# listen here\n",
+                "port: 8080\n",
+            )
+        );
+        assert_eq!(
+            crate::serde::from_str::<HashMap<String, u16>>(&huml).unwrap()["port"],
+            8080
+        );
+
+        let options = options.with_header_comment("");
+        assert_eq!(to_string_with(&7, options).unwrap(), "7\n");
+    }
+
+    #[test]
+    fn test_key_order() {
+        #[derive(Serialize)]
+        struct Package {
+            license: &'static str,
+            version: &'static str,
+            authors: Vec<&'static str>,
+            name: &'static str,
+        }
+        let package = Package {
+            license: "MIT",
+            version: "1.0.0",
+            authors: vec!["a"],
+            name: "demo",
+        };
+        let options = SerializeOptions::default().with_key_order(["name", "version"]);
+        assert_eq!(
+            to_string_with(&package, options.clone()).unwrap(),
+            "name: \"demo\"\nversion: \"1.0.0\"\nlicense: \"MIT\"\nauthors:: \"a\"\n"
+        );
+
+        let map = HashMap::from([("z", 1), ("version", 2), ("a", 3), ("b", 4)]);
+        let options = options.with_sort_keys();
+        assert_eq!(
+            to_string_with(&map, options).unwrap(),
+            "version: 2\na: 3\nb: 4\nz: 1\n"
+        );
+
+        // Keys chosen at run time
+        let first: Vec<String> = "b,z".split(',').map(String::from).collect();
+        let options = SerializeOptions::default()
+            .with_key_order(first)
+            .with_sort_keys();
+        assert_eq!(
+            to_string_with(&map, options).unwrap(),
+            "b: 4\nz: 1\na: 3\nversion: 2\n"
+        );
+    }
+
+    #[test]
+    fn test_canonical_output() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Release {
+            version: String,
+            notes: String,
+            mask: Hex,
+            env: HashMap<String, String>,
+        }
+        let release = Release {
+            version: "1.2".to_string(),
+            notes: "fixes\nmore fixes".to_string(),
+            mask: Hex(255),
+            env: HashMap::from([
+                ("PATH".to_string(), "/bin".to_string()),
+                ("HOME".to_string(), "/root".to_string()),
+            ]),
+        };
+        let huml = to_canonical_string(&release).unwrap();
+        assert_eq!(
+            huml,
+            "env::\n  HOME: \"/root\"\n  PATH: \"/bin\"\nmask: 255\nnotes: \"fixes\\nmore fixes\"\nversion: \"1.2\"\n"
+        );
+        assert_eq!(crate::serde::from_str::<Release>(&huml).unwrap(), release);
+    }
+
+    #[test]
+    fn test_bytes_are_encoded_as_strings() {
+        #[derive(Debug, PartialEq)]
+        struct Blob(Vec<u8>);
+
+        impl Serialize for Blob {
+            fn serialize<S: ser::Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                serializer.serialize_bytes(&self.0)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for Blob {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                struct BlobVisitor;
+                impl<'de> serde::de::Visitor<'de> for BlobVisitor {
+                    type Value = Blob;
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.write_str("bytes")
+                    }
+                    fn visit_byte_buf<E>(self, v: Vec<u8>) -> std::result::Result<Blob, E> {
+                        Ok(Blob(v))
+                    }
+                    fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                        self,
+                        mut seq: A,
+                    ) -> std::result::Result<Blob, A::Error> {
+                        let mut bytes = Vec::new();
+                        while let Some(byte) = seq.next_element()? {
+                            bytes.push(byte);
+                        }
+                        Ok(Blob(bytes))
+                    }
+                }
+                deserializer.deserialize_byte_buf(BlobVisitor)
+            }
+        }
+
+        let blob = Blob(b"hello\xff".to_vec());
+        let huml = to_string(&HashMap::from([("key", &blob)])).unwrap();
+        assert_eq!(huml, "key: \"aGVsbG//\"\n");
+        let back: HashMap<String, Blob> = crate::serde::from_str(&huml).unwrap();
+        assert_eq!(back["key"], blob);
+
+        let options = SerializeOptions::default().with_bytes_encoding(BytesEncoding::Hex);
+        let huml = to_string_with(&blob, options).unwrap();
+        assert_eq!(huml, "\"68656c6c6fff\"\n");
+        let de_options =
+            crate::serde::DeserializeOptions::default().with_bytes_encoding(BytesEncoding::Hex);
+        assert_eq!(
+            crate::serde::from_str_with::<Blob>(&huml, de_options).unwrap(),
+            blob
+        );
+        assert!(crate::serde::from_str::<Blob>("\"not base64\"").is_err());
+
+        let options = SerializeOptions::default().with_bytes_encoding(BytesEncoding::Raw);
+        let huml = to_string_with(&blob, options).unwrap();
+        assert_eq!(huml, "104, 101, 108, 108, 111, 255\n");
+        let de_options =
+            crate::serde::DeserializeOptions::default().with_bytes_encoding(BytesEncoding::Raw);
+        assert_eq!(
+            crate::serde::from_str_with::<Blob>(&huml, de_options).unwrap(),
+            blob
+        );
+        assert_eq!(
+            crate::serde::from_str_with::<Blob>("\"hi\"", de_options).unwrap(),
+            Blob(b"hi".to_vec())
+        );
     }
 
     #[test]
     fn test_serialize_empty_containers() {
         let empty_map: HashMap<String, String> = HashMap::new();
-        assert_eq!(to_string(&empty_map).unwrap(), "{}");
+        assert_eq!(to_string(&empty_map).unwrap(), "{}\n");
 
         let empty_vec: Vec<String> = Vec::new();
-        assert_eq!(to_string(&empty_vec).unwrap(), "[]");
+        assert_eq!(to_string(&empty_vec).unwrap(), "[]\n");
+    }
+
+    #[test]
+    fn test_braced_unicode_escapes() {
+        let mut serializer = Serializer::new().with_unicode_escape(UnicodeEscape::Braced);
+        "bell\u{7}".serialize(&mut serializer).unwrap();
+        let huml = serializer.into_string();
+        assert_eq!(huml, "\"bell\\u{7}\"");
+
+        let (_, value) = crate::parse_scalar(&huml).unwrap();
+        assert_eq!(value, crate::HumlValue::String("bell\u{7}".into()));
+        assert_eq!(to_string(&"bell\u{7}").unwrap(), "\"bell\\u0007\"\n");
+    }
+
+    #[test]
+    fn test_escape_profiles() {
+        let url = "https://example.com/a/b";
+        assert_eq!(to_string(&url).unwrap(), "\"https://example.com/a/b\"\n");
+
+        let options = SerializeOptions::default().with_escape_profile(EscapeProfile::Json);
+        let huml = to_string_with(&url, options).unwrap();
+        assert_eq!(huml, "\"https:\\/\\/example.com\\/a\\/b\"\n");
+        let (_, value) = crate::parse_scalar(&huml).unwrap();
+        assert_eq!(value, crate::HumlValue::String(url.into()));
+    }
+
+    #[test]
+    fn test_ascii_only() {
+        let text = "caf\u{e9} \u{1F600}\n\u{20000}";
+        let map = HashMap::from([("cl\u{e9}", text)]);
+        assert_eq!(
+            to_string(&map).unwrap(),
+            "\"cl\u{e9}\": \"\"\"\n  caf\u{e9} \u{1F600}\n  \u{20000}\n\"\"\"\n"
+        );
+
+        let options = SerializeOptions::default().with_ascii_only();
+        let huml = to_string_with(&map, options.clone()).unwrap();
+        assert_eq!(
+            huml,
+            "\"cl\\u00e9\": \"caf\\u00e9 \\ud83d\\ude00\\n\\ud840\\udc00\"\n"
+        );
+        assert!(huml.is_ascii());
+        assert_eq!(
+            crate::serde::from_str::<HashMap<String, String>>(&huml).unwrap()["cl\u{e9}"],
+            text
+        );
+
+        let options = options.with_unicode_escape(UnicodeEscape::Braced);
+        let huml = to_string_with(&text, options).unwrap();
+        assert_eq!(huml, "\"caf\\u{e9} \\u{1f600}\\n\\u{20000}\"\n");
+        assert_eq!(crate::serde::from_str::<String>(&huml).unwrap(), text);
+    }
+
+    #[test]
+    fn test_serialize_with_options() {
+        let person = PersonWithList {
+            name: "Bob".to_string(),
+            hobbies: vec!["reading".to_string(), "coding".to_string()],
+        };
+        let options = SerializeOptions::default().with_max_inline_items(1);
+        let huml = to_string_with(&person, options.clone()).unwrap();
+        assert_eq!(
+            huml,
+            "name: \"Bob\"\nhobbies::\n  - \"reading\"\n  - \"coding\"\n"
+        );
+        let (_, doc) = crate::parse_huml(&huml).unwrap();
+        assert_eq!(doc.root["hobbies"][1], "coding");
+
+        let one = HashMap::from([("a", [1])]);
+        assert_eq!(to_string_with(&one, options.clone()).unwrap(), "a:: 1\n");
+        assert_eq!(to_string_with(&[1, 2], options).unwrap(), "- 1\n- 2\n");
+
+        let options = SerializeOptions::default().without_trailing_newline();
+        assert_eq!(
+            to_string_with(&person, options).unwrap(),
+            "name: \"Bob\"\nhobbies:: \"reading\", \"coding\""
+        );
+    }
+
+    #[test]
+    fn test_sort_keys_orders_map_entries() {
+        #[derive(Serialize)]
+        struct Config {
+            zone: &'static str,
+            labels: HashMap<&'static str, Vec<u32>>,
+        }
+        let config = Config {
+            zone: "eu",
+            labels: HashMap::from([("b", vec![1, 2]), ("c", vec![]), ("a", vec![3, 4])]),
+        };
+        let options = SerializeOptions::default().with_sort_keys();
+        let huml = to_string_with(&config, options).unwrap();
+        assert_eq!(
+            huml,
+            "zone: \"eu\"\nlabels::\n  a:: 3, 4\n  b:: 1, 2\n  c: []\n"
+        );
+    }
+
+    #[test]
+    fn test_multiline_strings_use_blocks() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Script {
+            name: String,
+            body: String,
+            tags: Vec<String>,
+        }
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Job {
+            script: Script,
+        }
+        let job = Job {
+            script: Script {
+                name: "build".to_string(),
+                body: "set -e\n\nif true; then\n  make\nfi\n".to_string(),
+                tags: vec!["a\nb".to_string(), "c".to_string()],
+            },
+        };
+        let huml = to_string(&job).unwrap();
+        assert_eq!(
+            huml,
+            "script::\n  name: \"build\"\n  body: \"\"\"\n    set -e\n\n    if true; then\n      make\n    fi\n\n  \"\"\"\n  tags:: \"a\\nb\", \"c\"\n"
+        );
+        assert_eq!(crate::serde::round_trip(&job).unwrap(), job);
+
+        let options = SerializeOptions::default().without_multiline_strings();
+        assert_eq!(to_string_with(&"a\nb", options).unwrap(), "\"a\\nb\"\n");
+    }
+
+    #[test]
+    fn test_nested_values_are_indented_by_depth() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Leaf {
+            note: String,
+            ports: Vec<u16>,
+        }
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Middle {
+            leaf: Leaf,
+            lines: Vec<String>,
+        }
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Top {
+            middle: Middle,
+            name: String,
+        }
+        let top = Top {
+            middle: Middle {
+                leaf: Leaf {
+                    note: "one\ntwo".to_string(),
+                    ports: vec![80, 443],
+                },
+                lines: vec!["x\ny".to_string()],
+            },
+            name: "top".to_string(),
+        };
+        let options = SerializeOptions::default().with_block_sequences();
+        let huml = to_string_with(&top, options).unwrap();
+        assert_eq!(
+            huml,
+            "middle::\n  leaf::\n    note: \"\"\"\n      one\n      two\n    \"\"\"\n    ports::\n      - 80\n      - 443\n  lines::\n    - \"\"\"\n      x\n      y\n    \"\"\"\nname: \"top\"\n"
+        );
+        assert_eq!(crate::serde::from_str::<Top>(&huml).unwrap(), top);
+    }
+
+    #[test]
+    fn test_sequence_of_structs() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Server {
+            host: String,
+            ports: Vec<u16>,
+        }
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Config {
+            servers: Vec<Server>,
+        }
+        let config = Config {
+            servers: vec![
+                Server {
+                    host: "a".to_string(),
+                    ports: vec![80, 443],
+                },
+                Server {
+                    host: "b".to_string(),
+                    ports: vec![],
+                },
+            ],
+        };
+        let huml = to_string(&config).unwrap();
+        assert_eq!(
+            huml,
+            "servers::\n  - ::\n    host: \"a\"\n    ports:: 80, 443\n  - ::\n    host: \"b\"\n    ports: []\n"
+        );
+        assert_eq!(crate::serde::round_trip(&config).unwrap(), config);
+    }
+
+    #[test]
+    fn test_nested_sequences_keep_their_shape() {
+        let nested = vec![vec![1, 2], vec![], vec![3]];
+        let huml = to_string(&nested).unwrap();
+        assert_eq!(huml, "- :: 1, 2\n- []\n- :: 3\n");
+        assert_eq!(crate::serde::round_trip(&nested).unwrap(), nested);
+
+        let deeper = HashMap::from([("grid".to_string(), vec![vec![vec![1, 2], vec![3]]])]);
+        let huml = to_string(&deeper).unwrap();
+        assert_eq!(huml, "grid::\n  - ::\n    - :: 1, 2\n    - :: 3\n");
+        assert_eq!(crate::serde::round_trip(&deeper).unwrap(), deeper);
+    }
+
+    #[test]
+    fn test_root_sequences() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Step {
+            run: String,
+            args: Vec<String>,
+        }
+        let steps = vec![
+            Step {
+                run: "build".to_string(),
+                args: vec!["--release".to_string()],
+            },
+            Step {
+                run: "test".to_string(),
+                args: vec![],
+            },
+        ];
+        let huml = to_string(&steps).unwrap();
+        assert_eq!(
+            huml,
+            "- ::\n  run: \"build\"\n  args:: \"--release\"\n- ::\n  run: \"test\"\n  args: []\n"
+        );
+        assert_eq!(crate::serde::round_trip(&steps).unwrap(), steps);
+
+        let tuple = (1, "two".to_string(), vec![3.0, 4.0]);
+        assert_eq!(
+            to_string(&tuple).unwrap(),
+            "- 1\n- \"two\"\n- :: 3.0, 4.0\n"
+        );
+        assert_eq!(crate::serde::round_trip(&tuple).unwrap(), tuple);
+
+        // A single item would read back as a scalar if written inline
+        assert_eq!(to_string(&vec![5]).unwrap(), "- 5\n");
+        assert_eq!(crate::serde::round_trip(&vec![5]).unwrap(), vec![5]);
+        assert_eq!(to_string(&vec![5, 6]).unwrap(), "5, 6\n");
+    }
+
+    #[test]
+    fn test_to_document() {
+        #[derive(Serialize)]
+        struct Job {
+            mask: Hex,
+            script: String,
+            retries: Option<u32>,
+        }
+        let job = Job {
+            mask: Hex(255),
+            script: "a\nb".to_string(),
+            retries: None,
+        };
+        let doc = to_document(&job).unwrap();
+        assert_eq!(doc.version, None);
+        assert_eq!(
+            doc.root["mask"],
+            crate::HumlValue::Number(crate::HumlNumber::Prefixed(255, Radix::Hexadecimal))
+        );
+        assert!(matches!(
+            &doc.root["script"],
+            crate::HumlValue::String(s) if s.style() == crate::StringStyle::MultilineQuoted
+        ));
+        assert_eq!(doc.root["retries"], crate::HumlValue::Null);
+        assert_eq!(doc.to_huml_string(), to_string(&job).unwrap());
+
+        let options = SerializeOptions::default().with_skip_none();
+        let doc = to_document_with(&job, options).unwrap();
+        assert!(!doc.root.as_dict().unwrap().contains_key("retries"));
+        assert_eq!(to_document(&vec![1]).unwrap().root, huml!([1]));
+    }
+
+    #[test]
+    fn test_output_is_validated() {
+        assert_eq!(to_string(&-5).unwrap(), "-5\n");
+        assert_eq!(to_string(&vec![-1.5]).unwrap(), "- -1.5\n");
+
+        let err = Error::InvalidOutput {
+            message: "bad".to_string(),
+            text: "a:: :\n".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Serializer wrote invalid HUML (bad):\na:: :\n"
+        );
+        assert!(!SerializeOptions::default().validate_output);
+        let options = SerializeOptions::default().with_validate_output(true);
+        assert_eq!(to_string_with(&-0.0, options.clone()).unwrap(), "-0.0\n");
+
+        let mut value = BTreeMap::new();
+        value.insert("ports", vec![vec![80, 443], vec![], vec![8080]]);
+        value.insert("one", vec![vec![1]]);
+        let text = to_string_with(&value, options).unwrap();
+        let (_, doc) = crate::parse_huml(&text).unwrap();
+        assert_eq!(
+            doc.root,
+            huml!({ one: [[1]], ports: [[80, 443], [], [8080]] })
+        );
+    }
+
+    #[test]
+    fn test_flattened_fields_merge_into_the_parent() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Limits {
+            cpu: u32,
+            zones: Vec<String>,
+        }
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Service {
+            name: String,
+            #[serde(flatten)]
+            limits: Limits,
+            #[serde(flatten)]
+            labels: BTreeMap<String, String>,
+        }
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Deployment {
+            service: Service,
+        }
+        let deployment = Deployment {
+            service: Service {
+                name: "api".to_string(),
+                limits: Limits {
+                    cpu: 2,
+                    zones: vec!["a".to_string(), "b".to_string()],
+                },
+                labels: BTreeMap::from([("team".to_string(), "core".to_string())]),
+            },
+        };
+        let huml = to_string(&deployment).unwrap();
+        assert_eq!(
+            huml,
+            "service::\n  name: \"api\"\n  cpu: 2\n  zones:: \"a\", \"b\"\n  team: \"core\"\n"
+        );
+        assert_eq!(crate::serde::round_trip(&deployment).unwrap(), deployment);
+
+        let mut clash = deployment.service;
+        clash.labels.insert("name".to_string(), "other".to_string());
+        assert!(matches!(to_string(&clash), Err(Error::DuplicateKey(key)) if key == "name"));
+    }
+
+    #[test]
+    fn test_tagged_enums() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Tls {
+            cert: String,
+            ciphers: Vec<String>,
+        }
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        #[serde(tag = "type")]
+        enum Internal {
+            Tcp { port: u16, tls: Tls },
+            Unix(Tls),
+            Stdio,
+        }
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        #[serde(tag = "t", content = "c")]
+        enum Adjacent {
+            Tcp { port: u16, tls: Tls },
+            Fd(i32),
+            Pair(i32, String),
+            Stdio,
+        }
+        let tls = || Tls {
+            cert: "a.pem".to_string(),
+            ciphers: vec!["x".to_string()],
+        };
+
+        let internal = Internal::Tcp {
+            port: 443,
+            tls: tls(),
+        };
+        let huml = to_string(&internal).unwrap();
+        assert_eq!(
+            huml,
+            "type: \"Tcp\"\nport: 443\ntls::\n  cert: \"a.pem\"\n  ciphers:: \"x\"\n"
+        );
+        let all = vec![internal, Internal::Unix(tls()), Internal::Stdio];
+        assert_eq!(crate::serde::round_trip(&all).unwrap(), all);
+
+        let adjacent = Adjacent::Tcp {
+            port: 443,
+            tls: tls(),
+        };
+        let huml = to_string(&HashMap::from([("listen", &adjacent)])).unwrap();
+        assert_eq!(
+            huml,
+            "listen::\n  t: \"Tcp\"\n  c::\n    port: 443\n    tls::\n      cert: \"a.pem\"\n      ciphers:: \"x\"\n"
+        );
+        let all = vec![
+            adjacent,
+            Adjacent::Fd(3),
+            Adjacent::Pair(1, "s".to_string()),
+            Adjacent::Stdio,
+        ];
+        assert_eq!(crate::serde::round_trip(&all).unwrap(), all);
+    }
+
+    #[test]
+    fn test_nested_values_under_variants() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Tls {
+            cert: String,
+            notes: String,
+        }
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        enum Listen {
+            Tcp { port: u16, tls: Tls },
+            Unix(Tls),
+            Pair(u16, Tls),
+            Fd(i32),
+            Empty {},
+        }
+        let tls = || Tls {
+            cert: "a.pem".to_string(),
+            notes: "line one\nline two\n".to_string(),
+        };
+
+        let tcp = Listen::Tcp {
+            port: 443,
+            tls: tls(),
+        };
+        let huml = to_string(&HashMap::from([("listen", &tcp)])).unwrap();
+        assert_eq!(
+            huml,
+            concat!(
+                "listen::\n",
+                "  Tcp::\n",
+                "    port: 443\n",
+                "    tls::\n",
+                "      cert: \"a.pem\"\n",
+                "      notes: \"\"\"\n",
+                "        line one\n",
+                "        line two\n",
+                "\n",
+                "      \"\"\"\n",
+            )
+        );
+
+        let all = vec![
+            tcp,
+            Listen::Unix(tls()),
+            Listen::Pair(80, tls()),
+            Listen::Fd(3),
+            Listen::Empty {},
+        ];
+        assert_eq!(crate::serde::round_trip(&all).unwrap(), all);
+        assert_eq!(to_string(&Listen::Fd(3)).unwrap(), "Fd: 3\n");
+        assert_eq!(to_string(&Listen::Empty {}).unwrap(), "Empty: {}\n");
+    }
+
+    #[test]
+    fn test_inline_dicts() {
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Shape {
+            origin: Point,
+            path: Vec<Point>,
+            style: Style,
+        }
+        #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Style {
+            color: String,
+            dash: Vec<u8>,
+        }
+        let shape = Shape {
+            origin: Point { x: 0, y: -1 },
+            path: vec![Point { x: 1, y: 2 }],
+            style: Style {
+                color: "red".to_string(),
+                dash: vec![1, 2],
+            },
+        };
+        assert!(to_string(&shape).unwrap().starts_with("origin::\n  x: 0\n"));
+
+        let options = SerializeOptions::default().with_max_inline_fields(2);
+        let huml = to_string_with(&shape, options).unwrap();
+        // Structs holding lists stay blocks
+        assert_eq!(
+            huml,
+            concat!(
+                "origin:: x: 0, y: -1\n",
+                "path::\n",
+                "  - :: x: 1, y: 2\n",
+                "style::\n",
+                "  color: \"red\"\n",
+                "  dash:: 1, 2\n",
+            )
+        );
+        assert_eq!(crate::serde::from_str::<Shape>(&huml).unwrap(), shape);
+
+        // Too many fields, or too wide for the line
+        let options = SerializeOptions::default().with_max_inline_fields(1);
+        assert!(to_string_with(&shape, options.clone())
+            .unwrap()
+            .starts_with("origin::\n"));
+        let options = options.with_max_inline_fields(2).with_max_inline_width(16);
+        assert!(to_string_with(&shape, options)
+            .unwrap()
+            .starts_with("origin::\n"));
+
+        // The root is always a block
+        let options = SerializeOptions::default().with_max_inline_fields(2);
+        let point = Point { x: 1, y: 2 };
+        assert_eq!(to_string_with(&point, options).unwrap(), "x: 1\ny: 2\n");
+    }
+
+    #[test]
+    fn test_inline_layout_thresholds() {
+        #[derive(Serialize)]
+        struct Lists {
+            short: Vec<u32>,
+            long: Vec<&'static str>,
+            text: &'static str,
+        }
+        let lists = Lists {
+            short: vec![1],
+            long: vec!["alpha", "beta", "gamma"],
+            text: "a, b",
+        };
+        assert_eq!(
+            to_string(&lists).unwrap(),
+            "short:: 1\nlong:: \"alpha\", \"beta\", \"gamma\"\ntext: \"a, b\"\n"
+        );
+
+        let options = SerializeOptions::default().with_max_inline_width(16);
+        assert_eq!(
+            to_string_with(&lists, options).unwrap(),
+            "short:: 1\nlong::\n  - \"alpha\"\n  - \"beta\"\n  - \"gamma\"\ntext: \"a, b\"\n"
+        );
+
+        // Long lists wrap by default, counting the key and indentation
+        let names: Vec<String> = (0..200).map(|i| format!("name{i}")).collect();
+        let huml = to_string(&HashMap::from([("names", &names)])).unwrap();
+        assert!(huml.starts_with("names::\n  - \"name0\"\n  - \"name1\"\n"));
+        assert_eq!(huml.lines().count(), 201);
+        let row = vec![1234567; 9];
+        assert_eq!(to_string(&row).unwrap().lines().count(), 1);
+        let nested = HashMap::from([("outer", HashMap::from([("row", &row)]))]);
+        assert_eq!(to_string(&nested).unwrap().lines().count(), 11);
+
+        let options = SerializeOptions::default().with_block_sequences();
+        let huml = to_string_with(&lists, options).unwrap();
+        assert!(huml.starts_with("short::\n  - 1\nlong::\n"), "{huml}");
+        let (_, doc) = crate::parse_huml(&huml).unwrap();
+        assert_eq!(doc.root["short"], huml!([1]));
+    }
+
+    #[test]
+    fn test_commented_values() {
+        #[derive(Serialize)]
+        struct Database {
+            host: Commented<&'static str>,
+            replicas: Vec<Commented<u32>>,
+        }
+        #[derive(Serialize)]
+        struct Config {
+            database: Commented<Database>,
+        }
+        let config = Config {
+            database: Commented::new(
+                "Connection settings\n\nEdit with care",
+                Database {
+                    host: Commented::new("Primary", "db"),
+                    replicas: vec![Commented::new("dropped", 1), Commented::new("dropped", 2)],
+                },
+            ),
+        };
+        let huml = to_string(&config).unwrap();
+        assert_eq!(
+            huml,
+            "# Connection settings\n#\n# Edit with care\ndatabase::\n  # Primary\n  host: \"db\"\n  replicas:: 1, 2\n"
+        );
+        crate::parse_huml(&huml).unwrap();
+
+        let options = SerializeOptions::default().with_block_sequences();
+        let items = vec![Commented::new("first", 1), Commented::new("second", 2)];
+        let huml = to_string_with(&Commented::new("top", items), options).unwrap();
+        assert_eq!(huml, "# top\n# first\n- 1\n# second\n- 2\n");
+        crate::parse_huml(&huml).unwrap();
     }
 
     #[test]
@@ -794,6 +2855,34 @@ mod tests {
         assert!(!is_valid_unquoted_key("with spaces"));
         assert!(!is_valid_unquoted_key("with.dot"));
         assert!(!is_valid_unquoted_key("with:colon"));
+        assert!(!is_valid_unquoted_key("ключ"));
+    }
+
+    #[test]
+    fn test_invalid_bare_keys_are_quoted() {
+        let map = BTreeMap::from([
+            ("my key".to_string(), 1),
+            ("a.b".to_string(), 2),
+            ("123".to_string(), 3),
+            ("ключ".to_string(), 4),
+            ("say \"hi\"".to_string(), 5),
+            ("plain".to_string(), 6),
+        ]);
+        let huml = to_string(&map).unwrap();
+        assert_eq!(
+            huml,
+            "\"123\": 3\n\"a.b\": 2\n\"my key\": 1\nplain: 6\n\"say \\\"hi\\\"\": 5\n\"ключ\": 4\n"
+        );
+        assert_eq!(crate::serde::round_trip(&map).unwrap(), map);
+
+        let numbers = BTreeMap::from([(1, "one"), (-2, "minus two")]);
+        assert_eq!(
+            to_string(&numbers).unwrap(),
+            "\"-2\": \"minus two\"\n\"1\": \"one\"\n"
+        );
+
+        let bad = BTreeMap::from([(vec![1], 1)]);
+        assert!(to_string(&bad).is_err());
     }
 
     #[test]
@@ -846,7 +2935,7 @@ mod tests {
         let result: NestedExample = crate::serde::from_str(&huml).unwrap();
         assert_eq!(result.name, "test");
         assert_eq!(result.scores, vec![1, 2, 3]);
-        assert_eq!(result.config.enabled, true);
+        assert!(result.config.enabled);
         assert_eq!(result.config.timeout, 30);
 
         // Should use proper HUML formatting with :: syntax and indentation
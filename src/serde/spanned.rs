@@ -0,0 +1,198 @@
+//! Values that remember where they were in the input
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::Range;
+
+pub(crate) const NAME: &str = "$huml::private::Spanned";
+pub(crate) const START: &str = "$huml::private::Spanned::start";
+pub(crate) const END: &str = "$huml::private::Spanned::end";
+pub(crate) const VALUE: &str = "$huml::private::Spanned::value";
+pub(crate) const FIELDS: &[&str] = &[START, END, VALUE];
+
+/// A deserialized value along with the byte range of the input it was read
+/// from, for reporting problems found after deserializing against the source
+///
+/// Only the HUML deserializer can fill one in, and only when reading text
+/// (`from_str`, `from_slice`, `from_reader`, ...). Spans cover the value's
+/// text without the key, indicator or trailing comment: `8080` in
+/// `port: 8080`, and all entry lines of a block dict. Serializes as the
+/// plain value. Comparisons and hashing ignore the span.
+///
+/// ```
+/// use huml_rs::Spanned;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     port: Spanned<u16>,
+/// }
+///
+/// let input = "host: \"localhost\"\nport: 80\n";
+/// let config: Config = huml_rs::serde::from_str(input).unwrap();
+/// assert_eq!(*config.port.get_ref(), 80);
+/// assert_eq!(&input[config.port.span()], "80");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Spanned<T> {
+    span: Range<usize>,
+    value: T,
+}
+
+impl<T> Spanned<T> {
+    /// Pairs a value with a span, e.g. to build test fixtures
+    pub fn new(span: Range<usize>, value: T) -> Self {
+        Self { span, value }
+    }
+
+    /// The byte range of the input the value was read from
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// The value
+    pub fn get_ref(&self) -> &T {
+        &self.value
+    }
+
+    /// The value, mutably
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    /// Drops the span and returns the value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: PartialEq> PartialEq for Spanned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Eq> Eq for Spanned<T> {}
+
+impl<T: Hash> Hash for Spanned<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+impl<T: Serialize> Serialize for Spanned<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Spanned<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct SpannedVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for SpannedVisitor<T> {
+            type Value = Spanned<T>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a value read by the HUML deserializer")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Spanned<T>, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                expect_key(&mut map, START)?;
+                let start = map.next_value()?;
+                expect_key(&mut map, END)?;
+                let end = map.next_value()?;
+                expect_key(&mut map, VALUE)?;
+                let value = map.next_value()?;
+                Ok(Spanned::new(start..end, value))
+            }
+        }
+
+        deserializer.deserialize_struct(NAME, FIELDS, SpannedVisitor(PhantomData))
+    }
+}
+
+/// Fails unless the next key of `map` is `name`, as other deserializers
+/// don't know to produce the span fields
+fn expect_key<'de, A>(map: &mut A, name: &str) -> Result<(), A::Error>
+where
+    A: MapAccess<'de>,
+{
+    match map.next_key::<&str>()? {
+        Some(key) if key == name => Ok(()),
+        _ => Err(de::Error::custom(
+            "Spanned values can only be read by the HUML deserializer",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde::{from_str, from_value};
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Deserialize, Debug)]
+    struct Server {
+        host: Spanned<String>,
+        ports: Vec<Spanned<u16>>,
+        limits: Spanned<BTreeMap<String, Spanned<u32>>>,
+        mode: Spanned<Mode>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Mode {
+        Fast { level: Spanned<u8> },
+    }
+
+    #[test]
+    fn spans_point_into_the_input() {
+        let input = concat!(
+            "\n",
+            "host: \"localhost\"  # primary\n",
+            "ports:: 80, 443\n",
+            "limits::\n",
+            "  cpu: 2\n",
+            "  memory: 512\n",
+            "mode::\n",
+            "  Fast::\n",
+            "    level: 3\n",
+        );
+        let server: Server = from_str(input).unwrap();
+        let text = |span: Range<usize>| &input[span];
+        assert_eq!(text(server.host.span()), "\"localhost\"");
+        assert_eq!(server.host.get_ref(), "localhost");
+        assert_eq!(text(server.ports[1].span()), "443");
+        assert_eq!(text(server.limits.span()), "cpu: 2\n  memory: 512");
+        assert_eq!(text(server.limits.get_ref()["memory"].span()), "512");
+        assert_eq!(text(server.mode.span()), "Fast::\n    level: 3");
+        let Mode::Fast { level } = server.mode.into_inner();
+        assert_eq!(text(level.span()), "3");
+
+        // Spans don't take part in comparisons
+        assert_eq!(server.host, Spanned::new(0..0, "localhost".to_string()));
+    }
+
+    #[test]
+    fn spans_need_the_input() {
+        let (_, doc) = crate::parse_huml("a: 1\n").unwrap();
+        let err = from_value::<BTreeMap<String, Spanned<i32>>>(doc.root).unwrap_err();
+        assert!(err.to_string().contains("from_str"), "{err}");
+
+        let value = Spanned::new(3..5, vec![1, 2]);
+        assert_eq!(crate::serde::to_string(&value).unwrap(), "1, 2\n");
+    }
+}
@@ -0,0 +1,85 @@
+//! Reading and writing HUML on tokio's async IO traits
+
+use super::{de, from_slice, ser, to_string_with, SerializeOptions};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Deserialize HUML read from an async `reader`, without blocking the runtime
+///
+/// As with [`from_reader`](super::from_reader), the whole input is read
+/// before parsing.
+///
+/// # Example
+///
+/// ```rust
+/// use huml_rs::serde::{from_async_reader, DeResult};
+/// use std::collections::HashMap;
+/// use tokio::io::AsyncRead;
+///
+/// // `file` could be a `tokio::fs::File` or a socket
+/// async fn load(file: impl AsyncRead + Unpin) -> DeResult<HashMap<String, u16>> {
+///     from_async_reader(file).await
+/// }
+/// ```
+pub async fn from_async_reader<R, T>(mut reader: R) -> de::Result<T>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut input = Vec::new();
+    reader.read_to_end(&mut input).await?;
+    from_slice(&input)
+}
+
+/// Serialize a value as HUML to an async `writer`, and flush it
+pub async fn to_async_writer<W, T>(writer: W, value: &T) -> ser::Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    to_async_writer_with(writer, value, SerializeOptions::default()).await
+}
+
+/// Serialize a value as HUML to an async `writer` using the given options
+pub async fn to_async_writer_with<W, T>(
+    mut writer: W,
+    value: &T,
+    options: SerializeOptions,
+) -> ser::Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let text = to_string_with(value, options)?;
+    writer.write_all(text.as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn async_round_trip() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let config = BTreeMap::from([("port".to_string(), 8080)]);
+            let mut out = Vec::new();
+            to_async_writer(&mut out, &config).await.unwrap();
+            assert_eq!(out, b"port: 8080\n");
+
+            let read: BTreeMap<String, u16> = from_async_reader(&out[..]).await.unwrap();
+            assert_eq!(read, config);
+
+            let err = from_async_reader::<_, BTreeMap<String, u16>>(&b"port: \xff\n"[..])
+                .await
+                .unwrap_err();
+            assert!(matches!(err, de::Error::InvalidUtf8(6)));
+        });
+    }
+}
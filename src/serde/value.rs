@@ -0,0 +1,247 @@
+//! `Serialize` and `Deserialize` for [`HumlValue`] itself.
+//!
+//! These let a `HumlValue` field hold free-form data inside a typed struct, and
+//! carry HUML values through any other serde format.
+
+use super::de::Error as DeError;
+use crate::{HumlDict, HumlList, HumlNumber, HumlValue};
+use serde::de::{self, Deserialize, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use std::fmt;
+
+impl Serialize for HumlValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            HumlValue::String(s) => serializer.serialize_str(s),
+            HumlValue::Number(n) => n.serialize(serializer),
+            HumlValue::Boolean(b) => serializer.serialize_bool(*b),
+            HumlValue::Null => serializer.serialize_unit(),
+            HumlValue::List(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            HumlValue::Dict(dict) => {
+                let mut map = serializer.serialize_map(Some(dict.len()))?;
+                for (key, value) in dict {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+/// Prefixed integers are serialized as plain `i64`s; serde has no way to
+/// carry their base, so it is dropped.
+impl Serialize for HumlNumber {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            HumlNumber::Integer(i) | HumlNumber::Prefixed(i, _) => serializer.serialize_i64(i),
+            HumlNumber::I128(i) => serializer.serialize_i128(i),
+            HumlNumber::U128(u) => serializer.serialize_u128(u),
+            HumlNumber::Float(f) => serializer.serialize_f64(f),
+            HumlNumber::Nan => serializer.serialize_f64(f64::NAN),
+            HumlNumber::Infinity(true) => serializer.serialize_f64(f64::INFINITY),
+            HumlNumber::Infinity(false) => serializer.serialize_f64(f64::NEG_INFINITY),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for HumlValue {
+    fn deserialize<D>(deserializer: D) -> Result<HumlValue, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DeError> for HumlValue {
+    type Deserializer = super::Deserializer<'de>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        super::Deserializer::new(self)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DeError> for &'de HumlValue {
+    type Deserializer = super::Deserializer<'de>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        super::Deserializer::from_ref(self)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = HumlValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any HUML value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<HumlValue, E> {
+        Ok(HumlValue::Boolean(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<HumlValue, E> {
+        Ok(HumlValue::Number(HumlNumber::Integer(v)))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<HumlValue, E> {
+        Ok(HumlValue::Number(match i64::try_from(v) {
+            Ok(i) => HumlNumber::Integer(i),
+            Err(_) => HumlNumber::I128(v.into()),
+        }))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<HumlValue, E> {
+        Ok(HumlValue::Number(match i64::try_from(v) {
+            Ok(i) => HumlNumber::Integer(i),
+            Err(_) => HumlNumber::I128(v),
+        }))
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<HumlValue, E> {
+        match i128::try_from(v) {
+            Ok(i) => self.visit_i128(i),
+            Err(_) => Ok(HumlValue::Number(HumlNumber::U128(v))),
+        }
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<HumlValue, E> {
+        Ok(HumlValue::Number(if v.is_nan() {
+            HumlNumber::Nan
+        } else if v.is_infinite() {
+            HumlNumber::Infinity(v.is_sign_positive())
+        } else {
+            HumlNumber::Float(v)
+        }))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<HumlValue, E> {
+        Ok(HumlValue::String(v.into()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<HumlValue, E> {
+        Ok(HumlValue::String(v.into()))
+    }
+
+    fn visit_none<E>(self) -> Result<HumlValue, E> {
+        Ok(HumlValue::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<HumlValue, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_unit<E>(self) -> Result<HumlValue, E> {
+        Ok(HumlValue::Null)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<HumlValue, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<HumlValue, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut items = HumlList::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(HumlValue::List(items))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<HumlValue, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut dict = HumlDict::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((key, value)) = map.next_entry::<String, HumlValue>()? {
+            if dict.contains_key(&key) {
+                return Err(de::Error::custom(format!("duplicate key '{key}'")));
+            }
+            dict.insert(key, value);
+        }
+        Ok(HumlValue::Dict(dict))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::serde::{from_str, to_string};
+    use crate::{HumlNumber, HumlValue};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        name: String,
+        extra: HumlValue,
+    }
+
+    #[test]
+    fn value_fields_round_trip_through_huml() {
+        let huml = "name: \"app\"\nextra::\n  ratio: 2.5\n  tags:: \"a\", \"b\"\n  big: 170141183460469231731687303715884105727\n  nothing: null\n";
+        let config: Config = from_str(huml).unwrap();
+        let HumlValue::Dict(extra) = &config.extra else {
+            panic!("expected dict");
+        };
+        assert_eq!(
+            extra.get("big"),
+            Some(&HumlValue::Number(HumlNumber::I128(i128::MAX)))
+        );
+
+        let back: Config = from_str(&to_string(&config).unwrap()).unwrap();
+        assert_eq!(back, config);
+    }
+
+    #[test]
+    fn values_plug_into_serde_value_adapters() {
+        use serde::de::value::{MapDeserializer, SeqDeserializer};
+        use serde::de::IntoDeserializer;
+
+        let (_, doc) = crate::parse_huml("name: \"app\"\nextra:: 1, 2\n").unwrap();
+        let config = Config::deserialize((&doc.root).into_deserializer()).unwrap();
+        assert_eq!(
+            config,
+            Config::deserialize(doc.root.clone().into_deserializer()).unwrap()
+        );
+
+        let entries = vec![
+            ("name", HumlValue::String("app".into())),
+            ("extra", HumlValue::Null),
+        ];
+        let config = Config::deserialize(MapDeserializer::new(entries.into_iter())).unwrap();
+        assert_eq!(config.extra, HumlValue::Null);
+
+        let items = [HumlValue::Boolean(true), HumlValue::Boolean(false)];
+        let flags = Vec::<bool>::deserialize(SeqDeserializer::new(items.iter())).unwrap();
+        assert_eq!(flags, [true, false]);
+    }
+
+    #[test]
+    fn values_round_trip_through_other_formats() {
+        let (_, doc) = crate::parse_huml("a:: 1, 2.5, \"x\", true, null\nb:: {}\n").unwrap();
+        let json = serde_json::to_string(&doc.root).unwrap();
+        let back: HumlValue = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, doc.root);
+    }
+}
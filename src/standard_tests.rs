@@ -22,9 +22,19 @@ struct AssertionTest {
 #[cfg(test)]
 fn huml_to_json(value: &HumlValue) -> JsonValue {
     match value {
-        HumlValue::String(s) => JsonValue::String(s.clone()),
+        HumlValue::String(s) => JsonValue::String(s.to_string()),
         HumlValue::Number(n) => match n {
-            HumlNumber::Integer(i) => JsonValue::Number(serde_json::Number::from(*i)),
+            HumlNumber::Integer(i) | HumlNumber::Prefixed(i, _) => {
+                JsonValue::Number(serde_json::Number::from(*i))
+            }
+            HumlNumber::I128(i) => match u64::try_from(*i) {
+                Ok(u) => JsonValue::Number(serde_json::Number::from(u)),
+                Err(_) => serde_json::Number::from_f64(*i as f64)
+                    .map_or(JsonValue::Null, JsonValue::Number),
+            },
+            HumlNumber::U128(u) => {
+                serde_json::Number::from_f64(*u as f64).map_or(JsonValue::Null, JsonValue::Number)
+            }
             HumlNumber::Float(f) => {
                 if let Some(num) = serde_json::Number::from_f64(*f) {
                     JsonValue::Number(num)
@@ -123,7 +133,7 @@ fn values_match_with_multiline_tolerance(expected: &JsonValue, actual: &JsonValu
         (JsonValue::Object(exp_obj), JsonValue::Object(act_obj)) => {
             exp_obj.len() == act_obj.len()
                 && exp_obj.iter().all(|(key, exp_val)| {
-                    act_obj.get(key).map_or(false, |act_val| {
+                    act_obj.get(key).is_some_and(|act_val| {
                         values_match_with_multiline_tolerance(exp_val, act_val)
                     })
                 })
@@ -0,0 +1,260 @@
+//! Equality, ordering and hashing of values, and comparing them by meaning
+//! rather than representation.
+
+use crate::{HumlNumber, HumlValue};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Floats within this many machine epsilons of each other, relative to their
+/// magnitude, compare as semantically equal.
+const FLOAT_TOLERANCE: f64 = 4.0 * f64::EPSILON;
+
+impl HumlValue {
+    /// Compares two values by meaning, for round-trip tests.
+    ///
+    /// Unlike `==`, integers and floats compare by value (`1`, `0x1` and `1.0`
+    /// are equal), NaN equals NaN, and floats that differ
+    /// only by rounding error are equal. Dicts compare without regard to order.
+    ///
+    /// ```
+    /// use huml_rs::huml;
+    ///
+    /// let (_, doc) = huml_rs::parse_huml("mask: 0xff\nratio: 0.30000000000000004\n").unwrap();
+    /// assert!(doc.root.semantically_equals(&huml!({ mask: 255, ratio: 0.3 })));
+    /// ```
+    pub fn semantically_equals(&self, other: &HumlValue) -> bool {
+        match (self, other) {
+            (HumlValue::Number(a), HumlValue::Number(b)) => numbers_equal(a, b),
+            (HumlValue::List(a), HumlValue::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.semantically_equals(b))
+            }
+            (HumlValue::Dict(a), HumlValue::Dict(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, a)| b.get(key).is_some_and(|b| a.semantically_equals(b)))
+            }
+            _ => self == other,
+        }
+    }
+}
+
+fn numbers_equal(a: &HumlNumber, b: &HumlNumber) -> bool {
+    let (a, b) = (HumlValue::Number(a.clone()), HumlValue::Number(b.clone()));
+    if a.is_integer() && b.is_integer() {
+        return a.as_i128() == b.as_i128() && a.as_u128() == b.as_u128();
+    }
+    let (Some(a), Some(b)) = (a.as_f64(), b.as_f64()) else {
+        return false;
+    };
+    if a.is_nan() || b.is_nan() {
+        return a.is_nan() && b.is_nan();
+    }
+    a == b || (a - b).abs() <= FLOAT_TOLERANCE * a.abs().max(b.abs())
+}
+
+impl HumlNumber {
+    /// The radix of a prefixed integer is only a formatting hint, so `0xff`
+    /// ranks, compares and hashes as `255`.
+    fn rank(&self) -> u8 {
+        match self {
+            HumlNumber::Integer(_) | HumlNumber::Prefixed(..) => 0,
+            HumlNumber::I128(_) => 1,
+            HumlNumber::U128(_) => 2,
+            HumlNumber::Float(_) => 3,
+            HumlNumber::Nan => 4,
+            HumlNumber::Infinity(_) => 5,
+        }
+    }
+}
+
+/// Maps every NaN to one NaN and `-0.0` to `0.0`.
+fn canonical_float(f: f64) -> f64 {
+    if f.is_nan() {
+        f64::NAN
+    } else if f == 0.0 {
+        0.0
+    } else {
+        f
+    }
+}
+
+impl PartialEq for HumlNumber {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for HumlNumber {}
+
+impl PartialOrd for HumlNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HumlNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (
+                HumlNumber::Integer(a) | HumlNumber::Prefixed(a, _),
+                HumlNumber::Integer(b) | HumlNumber::Prefixed(b, _),
+            ) => a.cmp(b),
+            (HumlNumber::I128(a), HumlNumber::I128(b)) => a.cmp(b),
+            (HumlNumber::U128(a), HumlNumber::U128(b)) => a.cmp(b),
+            (HumlNumber::Float(a), HumlNumber::Float(b)) => {
+                canonical_float(*a).total_cmp(&canonical_float(*b))
+            }
+            (HumlNumber::Infinity(a), HumlNumber::Infinity(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl Hash for HumlNumber {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            HumlNumber::Integer(i) | HumlNumber::Prefixed(i, _) => i.hash(state),
+            HumlNumber::I128(i) => i.hash(state),
+            HumlNumber::U128(u) => u.hash(state),
+            HumlNumber::Float(f) => canonical_float(*f).to_bits().hash(state),
+            HumlNumber::Nan => {}
+            HumlNumber::Infinity(positive) => positive.hash(state),
+        }
+    }
+}
+
+impl HumlValue {
+    fn rank(&self) -> u8 {
+        match self {
+            HumlValue::String(_) => 0,
+            HumlValue::Number(_) => 1,
+            HumlValue::Boolean(_) => 2,
+            HumlValue::Null => 3,
+            HumlValue::List(_) => 4,
+            HumlValue::Dict(_) => 5,
+        }
+    }
+}
+
+impl PartialOrd for HumlValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HumlValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (HumlValue::String(a), HumlValue::String(b)) => a.cmp(b),
+            (HumlValue::Number(a), HumlValue::Number(b)) => a.cmp(b),
+            (HumlValue::Boolean(a), HumlValue::Boolean(b)) => a.cmp(b),
+            (HumlValue::List(a), HumlValue::List(b)) => a.cmp(b),
+            (HumlValue::Dict(a), HumlValue::Dict(b)) => {
+                let mut a: Vec<_> = a.iter().collect();
+                let mut b: Vec<_> = b.iter().collect();
+                a.sort_unstable();
+                b.sort_unstable();
+                a.cmp(&b)
+            }
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl Hash for HumlValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            HumlValue::String(s) => s.hash(state),
+            HumlValue::Number(n) => n.hash(state),
+            HumlValue::Boolean(b) => b.hash(state),
+            HumlValue::Null => {}
+            HumlValue::List(items) => items.hash(state),
+            HumlValue::Dict(dict) => {
+                // Dict equality ignores entry order, so the hash has to as
+                // well: hash each entry on its own and combine the results
+                // with a commutative sum.
+                let sum = dict
+                    .iter()
+                    .map(|entry| {
+                        let mut hasher = DefaultHasher::new();
+                        entry.hash(&mut hasher);
+                        hasher.finish()
+                    })
+                    .fold(0u64, u64::wrapping_add);
+                dict.len().hash(state);
+                sum.hash(state);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HumlNumber, HumlValue, Radix};
+
+    #[test]
+    fn semantic_equality_ignores_number_representation() {
+        let hex = HumlValue::Number(HumlNumber::Prefixed(16, Radix::Hexadecimal));
+        assert!(hex.semantically_equals(&huml!(16)));
+        assert!(huml!(16).semantically_equals(&huml!(16.0)));
+        assert!(huml!(f64::NAN).semantically_equals(&huml!(f64::NAN)));
+        assert!(huml!([0.1 + 0.2]).semantically_equals(&huml!([0.3])));
+        assert!(!huml!(0.3).semantically_equals(&huml!(0.3001)));
+        assert!(!huml!(1).semantically_equals(&huml!("1")));
+        assert!(!huml!({ a: 1 }).semantically_equals(&huml!({ a: 1, b: 2 })));
+        assert!(!huml!(u128::MAX).semantically_equals(&huml!(-1)));
+        assert_huml_eq!(huml!({ a: [1, 2.0] }), huml!({ a: [1.0, 2] }));
+    }
+
+    #[test]
+    fn values_dedup_in_hash_sets() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let values = [
+            huml!({ a: 1, b: [0.0, (f64::NAN)] }),
+            huml!({ b: [-0.0, (f64::NAN)], a: 1 }),
+            HumlValue::Number(HumlNumber::Float(f64::NAN)),
+            HumlValue::Number(HumlNumber::Float(-f64::NAN)),
+            huml!(1),
+            huml!(1.0),
+        ];
+        assert_eq!(values.iter().collect::<HashSet<_>>().len(), 4);
+        assert_eq!(values.iter().collect::<BTreeSet<_>>().len(), 4);
+
+        let mut sorted = vec![huml!(null), huml!(true), huml!(2), huml!(1), huml!("z")];
+        sorted.sort();
+        assert_eq!(
+            sorted,
+            [huml!("z"), huml!(1), huml!(2), huml!(true), huml!(null)]
+        );
+        assert!(huml!({ a: 1 }) < huml!({ a: 2 }));
+        assert!(huml!({ a: 9 }) < huml!({ b: 0 }));
+    }
+
+    #[test]
+    fn prefixed_integers_equal_their_value() {
+        use std::collections::HashSet;
+
+        let hex = HumlValue::Number(HumlNumber::Prefixed(255, Radix::Hexadecimal));
+        let binary = HumlValue::Number(HumlNumber::Prefixed(255, Radix::Binary));
+        assert_eq!(hex, huml!(255));
+        assert_eq!(hex, binary);
+        assert!(hex < huml!(256) && hex > huml!(254));
+        assert_eq!(
+            [hex, binary, huml!(255)]
+                .iter()
+                .collect::<HashSet<_>>()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "values are not semantically equal")]
+    fn assert_huml_eq_panics_on_mismatch() {
+        assert_huml_eq!(huml!([1]), huml!([2]));
+    }
+}
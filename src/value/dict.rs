@@ -0,0 +1,392 @@
+//! The [`HumlDict`] payload of [`HumlValue::Dict`].
+
+use crate::HumlValue;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{self, Range};
+use std::slice;
+use std::vec;
+
+/// The entries of a HUML dict, in insertion order.
+///
+/// Parsed dicts keep the order their keys were written in, and new keys are
+/// appended. Lookups by key are constant time. Equality ignores entry order,
+/// as it does for the dict a document describes.
+#[derive(Clone, Default)]
+pub struct HumlDict {
+    entries: Vec<(String, HumlValue)>,
+    index: HashMap<String, usize>,
+}
+
+impl HumlDict {
+    /// Creates an empty dict.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty dict with room for `capacity` entries.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            index: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the dict has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether the dict has an entry for `key`.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// The value for `key`, if present.
+    pub fn get(&self, key: &str) -> Option<&HumlValue> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    /// The value for `key` as a mutable reference, if present.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut HumlValue> {
+        self.index.get(key).map(|&i| &mut self.entries[i].1)
+    }
+
+    /// The entry at `position` in iteration order.
+    pub fn get_index(&self, position: usize) -> Option<(&String, &HumlValue)> {
+        self.entries.get(position).map(|(k, v)| (k, v))
+    }
+
+    /// The value for `key`, inserting the result of `default` first if the key
+    /// is missing.
+    pub fn get_or_insert_with<F>(&mut self, key: String, default: F) -> &mut HumlValue
+    where
+        F: FnOnce() -> HumlValue,
+    {
+        let position = match self.index.get(&key) {
+            Some(&i) => i,
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, default()));
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[position].1
+    }
+
+    /// Inserts an entry, returning the previous value for `key`.
+    ///
+    /// Replacing a value keeps the entry in its original position.
+    pub fn insert(&mut self, key: String, value: HumlValue) -> Option<HumlValue> {
+        match self.index.entry(key) {
+            Entry::Occupied(entry) => {
+                Some(std::mem::replace(&mut self.entries[*entry.get()].1, value))
+            }
+            Entry::Vacant(entry) => {
+                self.entries.push((entry.key().clone(), value));
+                entry.insert(self.entries.len() - 1);
+                None
+            }
+        }
+    }
+
+    /// Appends an entry for a key that isn't in the dict yet, returning its
+    /// value, or gives the key back if it is already there.
+    pub(crate) fn insert_new(
+        &mut self,
+        key: String,
+        value: HumlValue,
+    ) -> Result<&mut HumlValue, String> {
+        match self.index.entry(key) {
+            Entry::Occupied(entry) => Err(entry.key().clone()),
+            Entry::Vacant(entry) => {
+                self.entries.push((entry.key().clone(), value));
+                entry.insert(self.entries.len() - 1);
+                Ok(&mut self.entries.last_mut().unwrap().1)
+            }
+        }
+    }
+
+    /// The value of the last entry.
+    pub(crate) fn last_value_mut(&mut self) -> Option<&mut HumlValue> {
+        self.entries.last_mut().map(|(_, v)| v)
+    }
+
+    /// Removes the entry for `key`, keeping the order of the remaining entries.
+    pub fn remove(&mut self, key: &str) -> Option<HumlValue> {
+        let position = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(position);
+        self.reindex_from(position);
+        Some(value)
+    }
+
+    /// Keeps only the entries for which `keep` returns `true`.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&str, &mut HumlValue) -> bool,
+    {
+        let before = self.entries.len();
+        self.entries.retain_mut(|(k, v)| keep(k, v));
+        if self.entries.len() != before {
+            self.index.clear();
+            self.reindex_from(0);
+        }
+    }
+
+    /// Replaces the entries at `range` with `entries`, which must not share a
+    /// key with the entries outside it.
+    pub(crate) fn splice<I>(&mut self, range: Range<usize>, entries: I)
+    where
+        I: IntoIterator<Item = (String, HumlValue)>,
+    {
+        let start = range.start;
+        let removed: Vec<_> = self.entries.splice(range, entries).collect();
+        for (key, _) in removed {
+            self.index.remove(&key);
+        }
+        self.reindex_from(start);
+    }
+
+    /// Removes every entry.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+    }
+
+    /// Puts the entries into key order.
+    pub fn sort_keys(&mut self) {
+        self.entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        self.reindex_from(0);
+    }
+
+    /// The keys, in iteration order.
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &String> + ExactSizeIterator {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    /// The values, in iteration order.
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &HumlValue> + ExactSizeIterator {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    /// The values as mutable references, in iteration order.
+    pub fn values_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = &mut HumlValue> + ExactSizeIterator {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+
+    /// The entries, in iteration order.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.entries.iter())
+    }
+
+    /// The entries with mutable values, in iteration order.
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut(self.entries.iter_mut())
+    }
+
+    /// The entries sorted by key, independent of insertion order.
+    pub fn iter_sorted(&self) -> vec::IntoIter<(&String, &HumlValue)> {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable_by_key(|(key, _)| *key);
+        entries.into_iter()
+    }
+
+    fn reindex_from(&mut self, start: usize) {
+        for (i, (key, _)) in self.entries.iter().enumerate().skip(start) {
+            match self.index.get_mut(key.as_str()) {
+                Some(position) => *position = i,
+                None => {
+                    self.index.insert(key.clone(), i);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for HumlDict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for HumlDict {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl Eq for HumlDict {}
+
+impl ops::Index<&str> for HumlDict {
+    type Output = HumlValue;
+
+    /// Panics if `key` is missing.
+    fn index(&self, key: &str) -> &HumlValue {
+        self.get(key)
+            .unwrap_or_else(|| panic!("no entry for key {key:?}"))
+    }
+}
+
+impl Extend<(String, HumlValue)> for HumlDict {
+    fn extend<I: IntoIterator<Item = (String, HumlValue)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl FromIterator<(String, HumlValue)> for HumlDict {
+    fn from_iter<I: IntoIterator<Item = (String, HumlValue)>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut dict = HumlDict::with_capacity(iter.size_hint().0);
+        dict.extend(iter);
+        dict
+    }
+}
+
+impl From<HashMap<String, HumlValue>> for HumlDict {
+    fn from(map: HashMap<String, HumlValue>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl From<HumlDict> for HashMap<String, HumlValue> {
+    fn from(dict: HumlDict) -> Self {
+        dict.entries.into_iter().collect()
+    }
+}
+
+/// Iterator over the entries of a [`HumlDict`].
+#[derive(Debug, Clone)]
+pub struct Iter<'a>(slice::Iter<'a, (String, HumlValue)>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a String, &'a HumlValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+/// Iterator over the entries of a [`HumlDict`] with mutable values.
+#[derive(Debug)]
+pub struct IterMut<'a>(slice::IterMut<'a, (String, HumlValue)>);
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = (&'a String, &'a mut HumlValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (&*k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IterMut<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (&*k, v))
+    }
+}
+
+impl ExactSizeIterator for IterMut<'_> {}
+
+/// Owning iterator over the entries of a [`HumlDict`].
+#[derive(Debug)]
+pub struct IntoIter(vec::IntoIter<(String, HumlValue)>);
+
+impl Iterator for IntoIter {
+    type Item = (String, HumlValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl IntoIterator for HumlDict {
+    type Item = (String, HumlValue);
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter(self.entries.into_iter())
+    }
+}
+
+impl<'a> IntoIterator for &'a HumlDict {
+    type Item = (&'a String, &'a HumlValue);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut HumlDict {
+    type Item = (&'a String, &'a mut HumlValue);
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> IterMut<'a> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dict_keeps_insertion_order_through_edits() {
+        let mut dict: HumlDict = [("b", 1), ("a", 2), ("c", 3)]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), HumlValue::from(v)))
+            .collect();
+        assert_eq!(dict.insert("a".into(), HumlValue::from(20)), Some(2.into()));
+        assert_eq!(dict.remove("b"), Some(1.into()));
+        dict.insert("d".into(), HumlValue::Null);
+
+        assert!(dict.keys().eq(["a", "c", "d"]));
+        assert_eq!(dict["c"], 3);
+        assert_eq!(dict.get_index(2).map(|(k, _)| k.as_str()), Some("d"));
+
+        let mut reordered = dict.clone();
+        reordered.retain(|key, _| key != "a");
+        reordered.insert("a".into(), HumlValue::from(20));
+        assert!(reordered.keys().eq(["c", "d", "a"]));
+        assert_eq!(reordered, dict);
+        reordered.sort_keys();
+        assert!(reordered.keys().eq(["a", "c", "d"]));
+    }
+}
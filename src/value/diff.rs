@@ -0,0 +1,144 @@
+//! Structural differences between two values.
+
+use super::{Path, PathSegment};
+use crate::HumlValue;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+/// One difference reported by [`diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffEntry<'a> {
+    /// `value` exists only in the new tree.
+    Added {
+        path: Path<'a>,
+        value: &'a HumlValue,
+    },
+    /// `value` exists only in the old tree.
+    Removed {
+        path: Path<'a>,
+        value: &'a HumlValue,
+    },
+    /// The value at `path` differs and the two are not both dicts or both lists.
+    Changed {
+        path: Path<'a>,
+        old: &'a HumlValue,
+        new: &'a HumlValue,
+    },
+}
+
+impl<'a> DiffEntry<'a> {
+    /// The location of the difference.
+    pub fn path(&self) -> &Path<'a> {
+        match self {
+            DiffEntry::Added { path, .. }
+            | DiffEntry::Removed { path, .. }
+            | DiffEntry::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// Lists the differences between `old` and `new`.
+///
+/// Dicts are compared key by key and lists index by index, so an item
+/// inserted in the middle of a list shows up as changes to every later
+/// index. Entries are ordered by path, with dict keys in sorted order. Equal
+/// trees produce no entries.
+///
+/// ```
+/// use huml_rs::{diff, huml, DiffEntry};
+///
+/// let old = huml!({ port: 80, hosts: ["a"] });
+/// let new = huml!({ port: 8080, hosts: ["a", "b"], debug: true });
+/// let paths: Vec<String> = diff(&old, &new).iter().map(|e| e.path().to_string()).collect();
+/// assert_eq!(paths, ["debug", "hosts[1]", "port"]);
+/// assert!(matches!(diff(&old, &new)[0], DiffEntry::Added { .. }));
+/// ```
+pub fn diff<'a>(old: &'a HumlValue, new: &'a HumlValue) -> Vec<DiffEntry<'a>> {
+    let mut entries = Vec::new();
+    diff_into(old, new, &mut Path::root(), &mut entries);
+    entries
+}
+
+fn diff_into<'a>(
+    old: &'a HumlValue,
+    new: &'a HumlValue,
+    path: &mut Path<'a>,
+    entries: &mut Vec<DiffEntry<'a>>,
+) {
+    match (old, new) {
+        (HumlValue::Dict(old_dict), HumlValue::Dict(new_dict)) => {
+            let keys: BTreeSet<&String> = old_dict.keys().chain(new_dict.keys()).collect();
+            for key in keys {
+                path.push(PathSegment::Key(Cow::Borrowed(key)));
+                match (old_dict.get(key), new_dict.get(key)) {
+                    (Some(old), Some(new)) => diff_into(old, new, path, entries),
+                    (Some(value), None) => entries.push(DiffEntry::Removed {
+                        path: path.clone(),
+                        value,
+                    }),
+                    (None, Some(value)) => entries.push(DiffEntry::Added {
+                        path: path.clone(),
+                        value,
+                    }),
+                    (None, None) => unreachable!("key comes from one of the dicts"),
+                }
+                path.pop();
+            }
+        }
+        (HumlValue::List(old_items), HumlValue::List(new_items)) => {
+            for index in 0..old_items.len().max(new_items.len()) {
+                path.push(PathSegment::Index(index));
+                match (old_items.get(index), new_items.get(index)) {
+                    (Some(old), Some(new)) => diff_into(old, new, path, entries),
+                    (Some(value), None) => entries.push(DiffEntry::Removed {
+                        path: path.clone(),
+                        value,
+                    }),
+                    (None, Some(value)) => entries.push(DiffEntry::Added {
+                        path: path.clone(),
+                        value,
+                    }),
+                    (None, None) => unreachable!("index is below one of the lengths"),
+                }
+                path.pop();
+            }
+        }
+        _ if old != new => entries.push(DiffEntry::Changed {
+            path: path.clone(),
+            old,
+            new,
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_reports_added_removed_and_changed_paths() {
+        let old = huml!({ a: { x: 1, y: [1, 2, 3] }, b: "s", nan: (f64::NAN) });
+        let new = huml!({ a: { x: 2, y: [1, 2] }, c: null, nan: (f64::NAN) });
+        let entries = diff(&old, &new);
+        let summary: Vec<String> = entries
+            .iter()
+            .map(|entry| match entry {
+                DiffEntry::Added { path, .. } => format!("+{path}"),
+                DiffEntry::Removed { path, .. } => format!("-{path}"),
+                DiffEntry::Changed { path, old, new } => format!("~{path} {old:?} {new:?}"),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                "~a.x Number(Integer(1)) Number(Integer(2))",
+                "-a.y[2]",
+                "-b",
+                "+c",
+            ]
+        );
+        assert!(diff(&new, &new).is_empty());
+        assert_eq!(diff(&huml!(1), &huml!("1")).len(), 1);
+    }
+}
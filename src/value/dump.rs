@@ -0,0 +1,96 @@
+//! Type-annotated outlines of values for debugging.
+
+use crate::{HumlNumber, HumlValue};
+use std::fmt::Write;
+
+impl HumlValue {
+    /// Renders an indented outline of the value with the type of every node,
+    /// for inspecting parse results.
+    ///
+    /// Collections show their length and dict keys are listed in sorted order.
+    ///
+    /// ```
+    /// use huml_rs::huml;
+    ///
+    /// let value = huml!({ port: 8080, tags: ["a"], tls: null });
+    /// assert_eq!(
+    ///     value.dump_tree(),
+    ///     "dict(3)\n  \"port\": int 8080\n  \"tags\": list(1)\n    [0]: string \"a\"\n  \"tls\": null\n"
+    /// );
+    /// ```
+    pub fn dump_tree(&self) -> String {
+        let mut out = String::new();
+        dump(&mut out, self, 0);
+        out
+    }
+}
+
+fn dump(out: &mut String, value: &HumlValue, depth: usize) {
+    // Writing to a `String` can't fail.
+    let _ = match value {
+        HumlValue::String(s) => writeln!(out, "string {:?}", s.as_str()),
+        HumlValue::Number(n) => writeln!(out, "{} {n}", number_type(n)),
+        HumlValue::Boolean(b) => writeln!(out, "bool {b}"),
+        HumlValue::Null => writeln!(out, "null"),
+        HumlValue::List(items) => {
+            let _ = writeln!(out, "list({})", items.len());
+            for (index, item) in items.iter().enumerate() {
+                let _ = write!(out, "{:width$}[{index}]: ", "", width = (depth + 1) * 2);
+                dump(out, item, depth + 1);
+            }
+            Ok(())
+        }
+        HumlValue::Dict(dict) => {
+            let _ = writeln!(out, "dict({})", dict.len());
+            let mut entries: Vec<_> = dict.iter().collect();
+            entries.sort_unstable_by_key(|(key, _)| *key);
+            for (key, item) in entries {
+                let _ = write!(out, "{:width$}{key:?}: ", "", width = (depth + 1) * 2);
+                dump(out, item, depth + 1);
+            }
+            Ok(())
+        }
+    };
+}
+
+fn number_type(n: &HumlNumber) -> &'static str {
+    match n {
+        HumlNumber::Integer(_)
+        | HumlNumber::I128(_)
+        | HumlNumber::U128(_)
+        | HumlNumber::Prefixed(..) => "int",
+        HumlNumber::Float(_) | HumlNumber::Nan | HumlNumber::Infinity(_) => "float",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_huml, HumlNumber, HumlValue, Radix};
+
+    #[test]
+    fn dump_tree_outlines_nested_values() {
+        let mask = HumlValue::Number(HumlNumber::Prefixed(255, Radix::Hexadecimal));
+        let value = huml!([{ "a b": [true], mask: mask }, "line\nbreak", (f64::NAN), {}]);
+        assert_eq!(
+            value.dump_tree(),
+            "list(4)
+  [0]: dict(2)
+    \"a b\": list(1)
+      [0]: bool true
+    \"mask\": int 0xff
+  [1]: string \"line\\nbreak\"
+  [2]: float nan
+  [3]: dict(0)
+"
+        );
+    }
+
+    #[test]
+    fn dump_tree_shows_block_strings_as_text() {
+        let (_, doc) = parse_huml("script: \"\"\"\n  a\n  b\n\"\"\"\n").unwrap();
+        assert_eq!(
+            doc.root.dump_tree(),
+            "dict(1)\n  \"script\": string \"a\\nb\"\n"
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! The [`HumlList`] payload of [`HumlValue::List`].
+
+use crate::HumlValue;
+use std::fmt;
+use std::ops;
+use std::slice;
+use std::vec;
+
+/// The items of a HUML list.
+///
+/// Dereferences to a slice, so slice methods such as `len`, `iter`, `first`
+/// and indexing are available directly.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct HumlList {
+    items: Vec<HumlValue>,
+}
+
+impl HumlList {
+    /// Creates an empty list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty list with room for `capacity` items.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            items: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Appends an item.
+    pub fn push(&mut self, value: HumlValue) {
+        self.items.push(value);
+    }
+
+    /// Removes and returns the last item.
+    pub fn pop(&mut self) -> Option<HumlValue> {
+        self.items.pop()
+    }
+
+    /// Inserts an item at `index`, shifting later items back.
+    ///
+    /// Panics if `index` is greater than the length.
+    pub fn insert(&mut self, index: usize, value: HumlValue) {
+        self.items.insert(index, value);
+    }
+
+    /// Removes and returns the item at `index`, shifting later items forward.
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> HumlValue {
+        self.items.remove(index)
+    }
+
+    /// Shortens the list to `len` items.
+    pub fn truncate(&mut self, len: usize) {
+        self.items.truncate(len);
+    }
+
+    /// Keeps only the items for which `keep` returns `true`.
+    pub fn retain<F>(&mut self, keep: F)
+    where
+        F: FnMut(&HumlValue) -> bool,
+    {
+        self.items.retain(keep);
+    }
+
+    /// Removes every item.
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// Unwraps the items.
+    pub fn into_vec(self) -> Vec<HumlValue> {
+        self.items
+    }
+}
+
+impl fmt::Debug for HumlList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.items).finish()
+    }
+}
+
+impl ops::Deref for HumlList {
+    type Target = [HumlValue];
+
+    fn deref(&self) -> &[HumlValue] {
+        &self.items
+    }
+}
+
+impl ops::DerefMut for HumlList {
+    fn deref_mut(&mut self) -> &mut [HumlValue] {
+        &mut self.items
+    }
+}
+
+impl Extend<HumlValue> for HumlList {
+    fn extend<I: IntoIterator<Item = HumlValue>>(&mut self, iter: I) {
+        self.items.extend(iter);
+    }
+}
+
+impl FromIterator<HumlValue> for HumlList {
+    fn from_iter<I: IntoIterator<Item = HumlValue>>(iter: I) -> Self {
+        Self {
+            items: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl From<Vec<HumlValue>> for HumlList {
+    fn from(items: Vec<HumlValue>) -> Self {
+        Self { items }
+    }
+}
+
+impl From<HumlList> for Vec<HumlValue> {
+    fn from(list: HumlList) -> Self {
+        list.items
+    }
+}
+
+impl IntoIterator for HumlList {
+    type Item = HumlValue;
+    type IntoIter = vec::IntoIter<HumlValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a HumlList {
+    type Item = &'a HumlValue;
+    type IntoIter = slice::Iter<'a, HumlValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut HumlList {
+    type Item = &'a mut HumlValue;
+    type IntoIter = slice::IterMut<'a, HumlValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter_mut()
+    }
+}
@@ -0,0 +1,151 @@
+//! Deep merging of one value into another.
+
+use crate::{HumlDict, HumlValue};
+
+/// How [`HumlValue::merge`] combines an override into a base value.
+///
+/// The default merges dicts key by key, lets lists in the override replace
+/// lists in the base, and stores `null` like any other value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeStrategy {
+    /// Merge dicts key by key, recursively. When disabled, only the top-level
+    /// dicts are merged key by key, and a nested dict in the override replaces
+    /// the base dict whole.
+    pub deep_dicts: bool,
+    /// How to combine two lists.
+    pub lists: ListMerge,
+    /// Treat `null` in an override dict as deleting the key from the base.
+    pub null_deletes: bool,
+}
+
+/// How [`MergeStrategy`] combines a list in the override with a list in the base.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListMerge {
+    /// The override list replaces the base list.
+    Replace,
+    /// The override items are appended to the base list.
+    Append,
+}
+
+impl MergeStrategy {
+    /// Merge only the top-level keys, replacing nested dicts whole.
+    pub fn shallow(mut self) -> Self {
+        self.deep_dicts = false;
+        self
+    }
+
+    /// Append override lists to base lists.
+    pub fn with_list_append(mut self) -> Self {
+        self.lists = ListMerge::Append;
+        self
+    }
+
+    /// Remove keys whose override value is `null`.
+    pub fn with_null_deletes(mut self) -> Self {
+        self.null_deletes = true;
+        self
+    }
+}
+
+impl Default for MergeStrategy {
+    fn default() -> Self {
+        Self {
+            deep_dicts: true,
+            lists: ListMerge::Replace,
+            null_deletes: false,
+        }
+    }
+}
+
+impl HumlValue {
+    /// Merges `other` into this value, with `other` taking precedence.
+    ///
+    /// Values of different types, and scalars, are replaced by `other`.
+    ///
+    /// ```
+    /// use huml_rs::huml;
+    /// use huml_rs::value::MergeStrategy;
+    ///
+    /// let mut config = huml!({ server: { host: "localhost", port: 80 }, debug: true });
+    /// let overrides = huml!({ server: { port: 8080 }, debug: null });
+    /// config.merge(overrides, MergeStrategy::default().with_null_deletes());
+    /// assert_eq!(config, huml!({ server: { host: "localhost", port: 8080 } }));
+    /// ```
+    pub fn merge(&mut self, other: HumlValue, strategy: MergeStrategy) {
+        self.merge_at(other, strategy, true);
+    }
+
+    fn merge_at(&mut self, other: HumlValue, strategy: MergeStrategy, top_level: bool) {
+        match (self, other) {
+            (HumlValue::Dict(base), HumlValue::Dict(other)) if strategy.deep_dicts || top_level => {
+                for (key, value) in other {
+                    if value.is_null() && strategy.null_deletes {
+                        base.remove(&key);
+                        continue;
+                    }
+                    match base.get_mut(&key) {
+                        Some(existing) => existing.merge_at(value, strategy, false),
+                        None => {
+                            // Merge into an empty dict so nested nulls are dropped too.
+                            let mut new = match value {
+                                HumlValue::Dict(_) => HumlValue::Dict(HumlDict::new()),
+                                _ => HumlValue::Null,
+                            };
+                            new.merge_at(value, strategy, false);
+                            base.insert(key, new);
+                        }
+                    }
+                }
+            }
+            (HumlValue::List(base), HumlValue::List(other))
+                if strategy.lists == ListMerge::Append =>
+            {
+                base.extend(other);
+            }
+            (this, other) => *this = other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_follows_strategy() {
+        let base = huml!({ a: { x: 1, y: [1] }, b: "keep", c: 3 });
+        let overlay = huml!({ a: { y: [2], z: { n: null } }, c: null });
+
+        let mut merged = base.clone();
+        merged.merge(overlay.clone(), MergeStrategy::default());
+        assert_eq!(
+            merged,
+            huml!({ a: { x: 1, y: [2], z: { n: null } }, b: "keep", c: null })
+        );
+
+        let mut merged = base.clone();
+        let strategy = MergeStrategy::default()
+            .with_list_append()
+            .with_null_deletes();
+        merged.merge(overlay.clone(), strategy);
+        assert_eq!(merged, huml!({ a: { x: 1, y: [1, 2], z: {} }, b: "keep" }));
+
+        let mut merged = base;
+        merged.merge(overlay, MergeStrategy::default().shallow());
+        assert_eq!(
+            merged,
+            huml!({ a: { y: [2], z: { n: null } }, b: "keep", c: null })
+        );
+    }
+
+    #[test]
+    fn shallow_merge_keeps_top_level_keys_from_both_sides() {
+        let mut config = huml!({ server: { host: "a", port: 80 }, name: "app", tags: [1] });
+        let overrides = huml!({ server: { port: 8080 }, debug: true, tags: [2] });
+        config.merge(overrides, MergeStrategy::default().shallow());
+        assert_eq!(
+            config,
+            huml!({ server: { port: 8080 }, name: "app", debug: true, tags: [2] })
+        );
+    }
+}
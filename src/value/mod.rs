@@ -0,0 +1,1013 @@
+//! Accessors, conversions and indexing for [`HumlValue`].
+
+use crate::path::{list_index, parse_path};
+pub use crate::path::{Path, PathSegment};
+use crate::{HumlNumber, HumlValue};
+pub use dict::HumlDict;
+pub use diff::{diff, DiffEntry};
+pub use list::HumlList;
+pub use merge::{ListMerge, MergeStrategy};
+pub use patch::{Patch, PatchError, PatchOperation};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::ops;
+pub use string::{HumlString, StringStyle};
+
+mod compare;
+pub mod dict;
+mod diff;
+mod dump;
+mod list;
+mod merge;
+mod patch;
+mod string;
+
+impl HumlValue {
+    /// Returns the string if this is a `String`.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            HumlValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the number if this is a `Number`.
+    pub fn as_number(&self) -> Option<&HumlNumber> {
+        match self {
+            HumlValue::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if this is an integer that fits in an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            HumlValue::Number(HumlNumber::Integer(i) | HumlNumber::Prefixed(i, _)) => Some(i),
+            HumlValue::Number(HumlNumber::I128(i)) => i.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if this is an integer that fits in a `u64`.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            HumlValue::Number(HumlNumber::Integer(i) | HumlNumber::Prefixed(i, _)) => {
+                i.try_into().ok()
+            }
+            HumlValue::Number(HumlNumber::I128(i)) => i.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if this is an integer that fits in an `i128`.
+    pub fn as_i128(&self) -> Option<i128> {
+        match *self {
+            HumlValue::Number(HumlNumber::Integer(i) | HumlNumber::Prefixed(i, _)) => {
+                Some(i.into())
+            }
+            HumlValue::Number(HumlNumber::I128(i)) => Some(i),
+            HumlValue::Number(HumlNumber::U128(u)) => u.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if this is a non-negative integer.
+    pub fn as_u128(&self) -> Option<u128> {
+        match *self {
+            HumlValue::Number(HumlNumber::U128(u)) => Some(u),
+            _ => self.as_i128()?.try_into().ok(),
+        }
+    }
+
+    /// Returns any number as an `f64`, rounding large integers.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            HumlValue::Number(HumlNumber::Float(f)) => Some(f),
+            HumlValue::Number(HumlNumber::Integer(i) | HumlNumber::Prefixed(i, _)) => {
+                Some(i as f64)
+            }
+            HumlValue::Number(HumlNumber::I128(i)) => Some(i as f64),
+            HumlValue::Number(HumlNumber::U128(u)) => Some(u as f64),
+            HumlValue::Number(HumlNumber::Nan) => Some(f64::NAN),
+            HumlValue::Number(HumlNumber::Infinity(true)) => Some(f64::INFINITY),
+            HumlValue::Number(HumlNumber::Infinity(false)) => Some(f64::NEG_INFINITY),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if this is a `Boolean`.
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            HumlValue::Boolean(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Returns the items if this is a `List`.
+    pub fn as_list(&self) -> Option<&HumlList> {
+        match self {
+            HumlValue::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Returns the items mutably if this is a `List`.
+    pub fn as_list_mut(&mut self) -> Option<&mut HumlList> {
+        match self {
+            HumlValue::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Returns the entries if this is a `Dict`.
+    pub fn as_dict(&self) -> Option<&HumlDict> {
+        match self {
+            HumlValue::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// Returns the entries mutably if this is a `Dict`.
+    pub fn as_dict_mut(&mut self) -> Option<&mut HumlDict> {
+        match self {
+            HumlValue::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// Returns true if this is a `String`.
+    pub fn is_string(&self) -> bool {
+        matches!(self, HumlValue::String(_))
+    }
+
+    /// Returns true if this is a `Number`.
+    pub fn is_number(&self) -> bool {
+        matches!(self, HumlValue::Number(_))
+    }
+
+    /// Returns true for integers of any width, including prefixed literals.
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            HumlValue::Number(
+                HumlNumber::Integer(_)
+                    | HumlNumber::I128(_)
+                    | HumlNumber::U128(_)
+                    | HumlNumber::Prefixed(..)
+            )
+        )
+    }
+
+    /// Returns true if this is a `Boolean`.
+    pub fn is_bool(&self) -> bool {
+        matches!(self, HumlValue::Boolean(_))
+    }
+
+    /// Returns true if this is `Null`.
+    pub fn is_null(&self) -> bool {
+        matches!(self, HumlValue::Null)
+    }
+
+    /// Returns true if this is a `List`.
+    pub fn is_list(&self) -> bool {
+        matches!(self, HumlValue::List(_))
+    }
+
+    /// Returns true if this is a `Dict`.
+    pub fn is_dict(&self) -> bool {
+        matches!(self, HumlValue::Dict(_))
+    }
+
+    /// Looks up a value by [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901),
+    /// e.g. `"/servers/0/host"`.
+    ///
+    /// The empty pointer refers to the whole value. `~1` and `~0` in a segment
+    /// stand for `/` and `~`. Returns `None` if the pointer is malformed or any
+    /// segment doesn't resolve.
+    ///
+    /// ```
+    /// use huml_rs::huml;
+    ///
+    /// let value = huml!({ servers: [{ host: "a" }, { host: "b" }] });
+    /// assert_eq!(value.pointer("/servers/1/host"), Some(&huml!("b")));
+    /// assert_eq!(value.pointer("/servers/2/host"), None);
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Option<&HumlValue> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        pointer
+            .strip_prefix('/')?
+            .split('/')
+            .map(unescape_segment)
+            .try_fold(self, |value, segment| match value {
+                HumlValue::Dict(dict) => dict.get(&segment),
+                HumlValue::List(items) => items.get(list_index(&segment)?),
+                _ => None,
+            })
+    }
+
+    /// Looks up a value by dotted key path with bracketed list indices, e.g.
+    /// `"database.replicas[2].host"`.
+    ///
+    /// Keys containing `.`, `[` or `"` can be quoted: `"servers.\"eu.west\".host"`.
+    /// The empty path refers to the whole value. Returns `None` if the path is
+    /// malformed or any segment doesn't resolve.
+    ///
+    /// ```
+    /// use huml_rs::huml;
+    ///
+    /// let value = huml!({ database: { replicas: [{ host: "a" }, { host: "b" }] } });
+    /// assert_eq!(value.get_path("database.replicas[1].host"), Some(&huml!("b")));
+    /// assert_eq!(value.get_path("database.replicas.1"), None);
+    /// ```
+    pub fn get_path(&self, path: &str) -> Option<&HumlValue> {
+        parse_path(path)?
+            .iter()
+            .try_fold(self, |value, segment| segment.get(value))
+    }
+
+    /// Like [`get_path`](Self::get_path), returning a mutable reference.
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut HumlValue> {
+        parse_path(path)?
+            .iter()
+            .try_fold(self, |value, segment| segment.get_mut(value))
+    }
+
+    /// Removes the entry at `path` from its parent dict or list and returns it.
+    ///
+    /// Later items of a list shift down. Returns `None`, leaving the value
+    /// unchanged, if the path is malformed, empty, or doesn't resolve. To leave
+    /// `Null` in place instead, use [`get_path_mut`](Self::get_path_mut) with
+    /// [`take`](Self::take).
+    ///
+    /// ```
+    /// use huml_rs::huml;
+    ///
+    /// let mut value = huml!({ db: { host: "a", password: "secret" } });
+    /// assert_eq!(value.remove_path("db.password"), Some(huml!("secret")));
+    /// assert_eq!(value, huml!({ db: { host: "a" } }));
+    ///
+    /// let host = value.get_path_mut("db.host").map(|v| v.take());
+    /// assert_eq!(host, Some(huml!("a")));
+    /// assert_eq!(value, huml!({ db: { host: null } }));
+    /// ```
+    pub fn remove_path(&mut self, path: &str) -> Option<HumlValue> {
+        let segments = parse_path(path)?;
+        let (last, parents) = segments.split_last()?;
+        let parent = parents
+            .iter()
+            .try_fold(self, |value, segment| segment.get_mut(value))?;
+        last.remove(parent)
+    }
+
+    /// Visits this value and everything inside it depth-first, parents before
+    /// children, passing each one's path from this value.
+    ///
+    /// List items are visited in order and dict entries in document order.
+    ///
+    /// ```
+    /// use huml_rs::huml;
+    ///
+    /// let value = huml!({ db: { password: "x", hosts: ["a"] } });
+    /// let mut paths = Vec::new();
+    /// value.walk(|path, _| paths.push(path.to_string()));
+    /// assert_eq!(paths, ["", "db", "db.password", "db.hosts", "db.hosts[0]"]);
+    /// ```
+    pub fn walk<'v, F>(&'v self, mut visit: F)
+    where
+        F: FnMut(&Path<'v>, &'v HumlValue),
+    {
+        fn walk_inner<'v, F>(value: &'v HumlValue, path: &mut Path<'v>, visit: &mut F)
+        where
+            F: FnMut(&Path<'v>, &'v HumlValue),
+        {
+            visit(path, value);
+            match value {
+                HumlValue::List(items) => {
+                    for (index, item) in items.iter().enumerate() {
+                        path.push(PathSegment::Index(index));
+                        walk_inner(item, path, visit);
+                        path.pop();
+                    }
+                }
+                HumlValue::Dict(dict) => {
+                    for (key, item) in dict.iter() {
+                        path.push(PathSegment::Key(Cow::Borrowed(key)));
+                        walk_inner(item, path, visit);
+                        path.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        walk_inner(self, &mut Path::root(), &mut visit);
+    }
+
+    /// Walks the value like [`walk`](Self::walk), replacing each node for which
+    /// `replace` returns `Some`. Returns how many nodes were replaced.
+    ///
+    /// The children of a replaced node are not visited, so a replacement is
+    /// never transformed again.
+    ///
+    /// ```
+    /// use huml_rs::huml;
+    ///
+    /// let mut value = huml!({ user: "${USER}", paths: ["${HOME}/bin", "/usr/bin"] });
+    /// let changed = value.transform(|_, node| {
+    ///     let s = node.as_str()?;
+    ///     s.contains("${HOME}").then(|| s.replace("${HOME}", "/home/me").into())
+    /// });
+    /// assert_eq!(changed, 1);
+    /// assert_eq!(value["paths"][0], "/home/me/bin");
+    /// ```
+    pub fn transform<F>(&mut self, mut replace: F) -> usize
+    where
+        F: FnMut(&Path<'_>, &HumlValue) -> Option<HumlValue>,
+    {
+        fn transform_inner<'v, F>(
+            value: &'v mut HumlValue,
+            path: &mut Path<'v>,
+            replace: &mut F,
+        ) -> usize
+        where
+            F: FnMut(&Path<'_>, &HumlValue) -> Option<HumlValue>,
+        {
+            if let Some(new) = replace(path, value) {
+                *value = new;
+                return 1;
+            }
+            let mut changed = 0;
+            match value {
+                HumlValue::List(items) => {
+                    for (index, item) in items.iter_mut().enumerate() {
+                        path.push(PathSegment::Index(index));
+                        changed += transform_inner(item, path, replace);
+                        path.pop();
+                    }
+                }
+                HumlValue::Dict(dict) => {
+                    for (key, item) in dict.iter_mut() {
+                        path.push(PathSegment::Key(Cow::Borrowed(key)));
+                        changed += transform_inner(item, path, replace);
+                        path.pop();
+                    }
+                }
+                _ => {}
+            }
+            changed
+        }
+
+        transform_inner(self, &mut Path::root(), &mut replace)
+    }
+
+    /// Recursively puts the entries of every dict into key order, so the value
+    /// is written out the same way however it was built.
+    ///
+    /// ```
+    /// use huml_rs::huml;
+    ///
+    /// let mut value = huml!({ b: 1, a: { d: 2, c: 3 } });
+    /// value.sort_keys();
+    /// assert_eq!(value.to_huml_string(), "a::\n  c: 3\n  d: 2\nb: 1\n");
+    /// ```
+    pub fn sort_keys(&mut self) {
+        match self {
+            HumlValue::List(items) => items.iter_mut().for_each(HumlValue::sort_keys),
+            HumlValue::Dict(dict) => {
+                dict.sort_keys();
+                dict.values_mut().for_each(HumlValue::sort_keys);
+            }
+            _ => {}
+        }
+    }
+
+    /// Takes the value out, leaving `Null` in its place.
+    pub fn take(&mut self) -> HumlValue {
+        std::mem::replace(self, HumlValue::Null)
+    }
+
+    /// Like [`pointer`](Self::pointer), returning a mutable reference.
+    ///
+    /// ```
+    /// use huml_rs::huml;
+    ///
+    /// let mut value = huml!({ server: { port: 80 } });
+    /// *value.pointer_mut("/server/port").unwrap() = huml!(8080);
+    /// assert_eq!(value["server"]["port"], huml!(8080));
+    /// ```
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut HumlValue> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        pointer
+            .strip_prefix('/')?
+            .split('/')
+            .map(unescape_segment)
+            .try_fold(self, |value, segment| match value {
+                HumlValue::Dict(dict) => dict.get_mut(&segment),
+                HumlValue::List(items) => items.get_mut(list_index(&segment)?),
+                _ => None,
+            })
+    }
+}
+
+fn unescape_segment(segment: &str) -> Cow<'_, str> {
+    if segment.contains('~') {
+        Cow::Owned(segment.replace("~1", "/").replace("~0", "~"))
+    } else {
+        Cow::Borrowed(segment)
+    }
+}
+
+impl From<bool> for HumlValue {
+    fn from(value: bool) -> Self {
+        HumlValue::Boolean(value)
+    }
+}
+
+impl From<&str> for HumlValue {
+    fn from(value: &str) -> Self {
+        HumlValue::String(value.into())
+    }
+}
+
+impl From<String> for HumlValue {
+    fn from(value: String) -> Self {
+        HumlValue::String(value.into())
+    }
+}
+
+impl From<HumlNumber> for HumlValue {
+    fn from(value: HumlNumber) -> Self {
+        HumlValue::Number(value)
+    }
+}
+
+macro_rules! from_small_int {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for HumlValue {
+            fn from(value: $ty) -> Self {
+                HumlValue::Number(HumlNumber::Integer(value.into()))
+            }
+        }
+    )*};
+}
+
+from_small_int!(i8, i16, i32, i64, u8, u16, u32);
+
+impl From<u64> for HumlValue {
+    fn from(value: u64) -> Self {
+        HumlValue::Number(match i64::try_from(value) {
+            Ok(i) => HumlNumber::Integer(i),
+            Err(_) => HumlNumber::I128(value.into()),
+        })
+    }
+}
+
+impl From<i128> for HumlValue {
+    fn from(value: i128) -> Self {
+        HumlValue::Number(match i64::try_from(value) {
+            Ok(i) => HumlNumber::Integer(i),
+            Err(_) => HumlNumber::I128(value),
+        })
+    }
+}
+
+impl From<u128> for HumlValue {
+    fn from(value: u128) -> Self {
+        match i128::try_from(value) {
+            Ok(i) => i.into(),
+            Err(_) => HumlValue::Number(HumlNumber::U128(value)),
+        }
+    }
+}
+
+impl From<f32> for HumlValue {
+    fn from(value: f32) -> Self {
+        f64::from(value).into()
+    }
+}
+
+/// NaN and infinities become [`HumlNumber::Nan`] and [`HumlNumber::Infinity`].
+impl From<f64> for HumlValue {
+    fn from(value: f64) -> Self {
+        HumlValue::Number(if value.is_nan() {
+            HumlNumber::Nan
+        } else if value.is_infinite() {
+            HumlNumber::Infinity(value.is_sign_positive())
+        } else {
+            HumlNumber::Float(value)
+        })
+    }
+}
+
+impl From<usize> for HumlValue {
+    fn from(value: usize) -> Self {
+        (value as u64).into()
+    }
+}
+
+impl From<isize> for HumlValue {
+    fn from(value: isize) -> Self {
+        (value as i64).into()
+    }
+}
+
+impl From<char> for HumlValue {
+    fn from(value: char) -> Self {
+        HumlValue::String(value.to_string().into())
+    }
+}
+
+impl From<&String> for HumlValue {
+    fn from(value: &String) -> Self {
+        HumlValue::String(value.as_str().into())
+    }
+}
+
+impl From<Cow<'_, str>> for HumlValue {
+    fn from(value: Cow<'_, str>) -> Self {
+        HumlValue::String(value.into())
+    }
+}
+
+/// `()` converts to `Null`.
+impl From<()> for HumlValue {
+    fn from((): ()) -> Self {
+        HumlValue::Null
+    }
+}
+
+/// `None` converts to `Null`.
+impl<T: Into<HumlValue>> From<Option<T>> for HumlValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(HumlValue::Null, Into::into)
+    }
+}
+
+impl<T: Into<HumlValue>> From<Vec<T>> for HumlValue {
+    fn from(value: Vec<T>) -> Self {
+        value.into_iter().collect()
+    }
+}
+
+impl<T: Into<HumlValue>, const N: usize> From<[T; N]> for HumlValue {
+    fn from(value: [T; N]) -> Self {
+        value.into_iter().collect()
+    }
+}
+
+impl<T: Clone + Into<HumlValue>> From<&[T]> for HumlValue {
+    fn from(value: &[T]) -> Self {
+        value.iter().cloned().collect()
+    }
+}
+
+impl<K: Into<String>, V: Into<HumlValue>> From<HashMap<K, V>> for HumlValue {
+    fn from(value: HashMap<K, V>) -> Self {
+        value.into_iter().collect()
+    }
+}
+
+impl<K: Into<String>, V: Into<HumlValue>> From<BTreeMap<K, V>> for HumlValue {
+    fn from(value: BTreeMap<K, V>) -> Self {
+        value.into_iter().collect()
+    }
+}
+
+/// Collects into a `List`.
+impl<T: Into<HumlValue>> FromIterator<T> for HumlValue {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        HumlValue::List(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Collects into a `Dict`. Later entries replace earlier ones with the same key.
+impl<K: Into<String>, V: Into<HumlValue>> FromIterator<(K, V)> for HumlValue {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        HumlValue::Dict(
+            iter.into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+impl PartialEq<str> for HumlValue {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == Some(other)
+    }
+}
+
+impl PartialEq<&str> for HumlValue {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == Some(*other)
+    }
+}
+
+impl PartialEq<String> for HumlValue {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == Some(other.as_str())
+    }
+}
+
+impl PartialEq<HumlValue> for str {
+    fn eq(&self, other: &HumlValue) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<HumlValue> for &str {
+    fn eq(&self, other: &HumlValue) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<HumlValue> for String {
+    fn eq(&self, other: &HumlValue) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<bool> for HumlValue {
+    fn eq(&self, other: &bool) -> bool {
+        self.as_bool() == Some(*other)
+    }
+}
+
+impl PartialEq<HumlValue> for bool {
+    fn eq(&self, other: &HumlValue) -> bool {
+        other == self
+    }
+}
+
+/// Integers compare by value regardless of how the number is stored; floats
+/// compare against any number, so `1 == 1.0`.
+macro_rules! partial_eq_number {
+    ($($ty:ty => $as:ident($wide:ty)),* $(,)?) => {$(
+        impl PartialEq<$ty> for HumlValue {
+            fn eq(&self, other: &$ty) -> bool {
+                self.$as() == Some(<$wide>::from(*other))
+            }
+        }
+
+        impl PartialEq<HumlValue> for $ty {
+            fn eq(&self, other: &HumlValue) -> bool {
+                other == self
+            }
+        }
+    )*};
+}
+
+partial_eq_number! {
+    i8 => as_i128(i128),
+    i16 => as_i128(i128),
+    i32 => as_i128(i128),
+    i64 => as_i128(i128),
+    i128 => as_i128(i128),
+    u8 => as_u128(u128),
+    u16 => as_u128(u128),
+    u32 => as_u128(u128),
+    u64 => as_u128(u128),
+    u128 => as_u128(u128),
+    f64 => as_f64(f64),
+}
+
+// Narrowed rather than widened, so `0.1` equals `0.1f32`
+impl PartialEq<f32> for HumlValue {
+    fn eq(&self, other: &f32) -> bool {
+        self.as_f64().map(|f| f as f32) == Some(*other)
+    }
+}
+
+impl PartialEq<HumlValue> for f32 {
+    fn eq(&self, other: &HumlValue) -> bool {
+        other == self
+    }
+}
+
+/// A type that can index into a [`HumlValue`]: `usize` for lists and string
+/// types for dicts.
+///
+/// `value[index]` returns [`HumlValue::Null`] when the index doesn't apply,
+/// so lookups can be chained: `value["database"]["port"]`. Assigning through
+/// `value[key]` inserts the key into a dict, turning `Null` into an empty dict
+/// first. It panics on other values and on list indices out of bounds.
+pub trait Index: private::Sealed {
+    #[doc(hidden)]
+    fn index_into<'v>(&self, value: &'v HumlValue) -> Option<&'v HumlValue>;
+
+    #[doc(hidden)]
+    fn index_into_mut<'v>(&self, value: &'v mut HumlValue) -> Option<&'v mut HumlValue>;
+
+    #[doc(hidden)]
+    fn index_or_insert<'v>(&self, value: &'v mut HumlValue) -> &'v mut HumlValue;
+}
+
+impl Index for usize {
+    fn index_into<'v>(&self, value: &'v HumlValue) -> Option<&'v HumlValue> {
+        match value {
+            HumlValue::List(items) => items.get(*self),
+            _ => None,
+        }
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut HumlValue) -> Option<&'v mut HumlValue> {
+        match value {
+            HumlValue::List(items) => items.get_mut(*self),
+            _ => None,
+        }
+    }
+
+    fn index_or_insert<'v>(&self, value: &'v mut HumlValue) -> &'v mut HumlValue {
+        match value {
+            HumlValue::List(items) => {
+                let len = items.len();
+                items.get_mut(*self).unwrap_or_else(|| {
+                    panic!("cannot access index {self} of a list of length {len}")
+                })
+            }
+            _ => panic!("cannot access index {self} of {}", kind(value)),
+        }
+    }
+}
+
+impl Index for str {
+    fn index_into<'v>(&self, value: &'v HumlValue) -> Option<&'v HumlValue> {
+        match value {
+            HumlValue::Dict(dict) => dict.get(self),
+            _ => None,
+        }
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut HumlValue) -> Option<&'v mut HumlValue> {
+        match value {
+            HumlValue::Dict(dict) => dict.get_mut(self),
+            _ => None,
+        }
+    }
+
+    fn index_or_insert<'v>(&self, value: &'v mut HumlValue) -> &'v mut HumlValue {
+        if let HumlValue::Null = value {
+            *value = HumlValue::Dict(HumlDict::new());
+        }
+        match value {
+            HumlValue::Dict(dict) => dict.get_or_insert_with(self.to_string(), || HumlValue::Null),
+            _ => panic!("cannot access key {self:?} of {}", kind(value)),
+        }
+    }
+}
+
+impl Index for String {
+    fn index_into<'v>(&self, value: &'v HumlValue) -> Option<&'v HumlValue> {
+        self.as_str().index_into(value)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut HumlValue) -> Option<&'v mut HumlValue> {
+        self.as_str().index_into_mut(value)
+    }
+
+    fn index_or_insert<'v>(&self, value: &'v mut HumlValue) -> &'v mut HumlValue {
+        self.as_str().index_or_insert(value)
+    }
+}
+
+impl<T: Index + ?Sized> Index for &T {
+    fn index_into<'v>(&self, value: &'v HumlValue) -> Option<&'v HumlValue> {
+        (**self).index_into(value)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut HumlValue) -> Option<&'v mut HumlValue> {
+        (**self).index_into_mut(value)
+    }
+
+    fn index_or_insert<'v>(&self, value: &'v mut HumlValue) -> &'v mut HumlValue {
+        (**self).index_or_insert(value)
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for usize {}
+    impl Sealed for str {}
+    impl Sealed for String {}
+    impl<T: Sealed + ?Sized> Sealed for &T {}
+}
+
+fn kind(value: &HumlValue) -> &'static str {
+    match value {
+        HumlValue::String(_) => "a string",
+        HumlValue::Number(_) => "a number",
+        HumlValue::Boolean(_) => "a boolean",
+        HumlValue::Null => "null",
+        HumlValue::List(_) => "a list",
+        HumlValue::Dict(_) => "a dict",
+    }
+}
+
+impl<I: Index> ops::Index<I> for HumlValue {
+    type Output = HumlValue;
+
+    fn index(&self, index: I) -> &HumlValue {
+        static NULL: HumlValue = HumlValue::Null;
+        index.index_into(self).unwrap_or(&NULL)
+    }
+}
+
+impl<I: Index> ops::IndexMut<I> for HumlValue {
+    fn index_mut(&mut self, index: I) -> &mut HumlValue {
+        index.index_or_insert(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PathSegment;
+    use crate::HumlValue;
+    use std::collections::HashMap;
+
+    #[test]
+    fn index_reads_nested_values_or_null() {
+        let value = huml!({ database: { port: 5432, hosts: ["a", "b"] } });
+        assert_eq!(value["database"]["port"], huml!(5432));
+        assert_eq!(value["database"]["hosts"][1], huml!("b"));
+        assert_eq!(value["database"]["hosts"][2], HumlValue::Null);
+        assert_eq!(value["missing"]["port"][0], HumlValue::Null);
+        assert_eq!(value[String::from("database")]["port"], huml!(5432));
+    }
+
+    #[test]
+    fn index_mut_creates_dict_entries() {
+        let mut value = HumlValue::Null;
+        value["server"]["port"] = huml!(80);
+        value["server"]["hosts"] = huml!(["a"]);
+        value["server"]["hosts"][0] = huml!("b");
+        assert_eq!(value, huml!({ server: { port: 80, hosts: ["b"] } }));
+    }
+
+    #[test]
+    fn accessors_read_matching_variants() {
+        let value =
+            huml!({ name: "app", port: 8080, ratio: 0.5, on: true, tags: ["a"], none: null });
+        assert_eq!(value["name"].as_str(), Some("app"));
+        assert_eq!(value["port"].as_i64(), Some(8080));
+        assert_eq!(value["port"].as_u64(), Some(8080));
+        assert_eq!(value["port"].as_f64(), Some(8080.0));
+        assert_eq!(value["ratio"].as_i64(), None);
+        assert_eq!(value["on"].as_bool(), Some(true));
+        assert_eq!(value["tags"].as_list().map(|items| items.len()), Some(1));
+        assert!(value.as_dict().is_some_and(|d| d.contains_key("none")));
+        assert!(value["none"].is_null() && value["missing"].is_null());
+        assert!(value["port"].is_integer() && !value["ratio"].is_integer());
+
+        assert_eq!(huml!(-1).as_u64(), None);
+        assert_eq!(huml!(u64::MAX).as_u64(), Some(u64::MAX));
+        assert_eq!(huml!(u128::MAX).as_i128(), None);
+        assert_eq!(huml!(u128::MAX).as_u128(), Some(u128::MAX));
+    }
+
+    #[test]
+    fn conversions_build_values() {
+        let dict = HashMap::from([("port", 80), ("timeout", 30)]);
+        assert_eq!(HumlValue::from(dict), huml!({ port: 80, timeout: 30 }));
+        assert_eq!(HumlValue::from(vec![Some("a"), None]), huml!(["a", null]));
+        assert_eq!(HumlValue::from(&[1.5, 2.0][..]), huml!([1.5, 2.0]));
+        assert_eq!(HumlValue::from('x'), huml!("x"));
+        assert_eq!(HumlValue::from(()), HumlValue::Null);
+        let squares: HumlValue = (1..=3).map(|i| i * i).collect();
+        assert_eq!(squares, huml!([1, 4, 9]));
+        let names: HumlValue = [("a", true)].into_iter().collect();
+        assert_eq!(names, huml!({ a: true }));
+    }
+
+    #[test]
+    fn values_compare_with_primitives() {
+        let value = huml!({ mode: "production", port: 8080, ratio: 1.0, on: true });
+        assert_eq!(value["mode"], "production");
+        assert_eq!(value["mode"], String::from("production"));
+        assert_ne!(value["port"], "8080");
+        assert_eq!(value["port"], 8080);
+        assert_eq!(value["port"], 8080u16);
+        assert_eq!(value["port"], 8080.0);
+        assert_eq!(value["ratio"], 1.0);
+        assert_ne!(value["ratio"], 1);
+        assert_eq!(huml!(0.1), 0.1f32);
+        assert_eq!(0.1f32, huml!(0.1));
+        assert_eq!(HumlValue::from(0.1f32), 0.1f32);
+        assert_eq!(value["on"], true);
+        assert_eq!(true, value["on"]);
+        assert_ne!(value["missing"], false);
+        assert_ne!(huml!(-1), u64::MAX);
+        assert_eq!(u128::MAX, huml!(u128::MAX));
+    }
+
+    #[test]
+    fn pointer_resolves_escaped_segments_and_indices() {
+        let value = huml!({ "a/b": { "m~n": [10, 20] }, "": 1 });
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(value.pointer("/"), Some(&huml!(1)));
+        assert_eq!(value.pointer("/a~1b/m~0n/1"), Some(&huml!(20)));
+        assert_eq!(value.pointer("/a~1b/m~0n/01"), None);
+        assert_eq!(value.pointer("/a~1b/m~0n/+1"), None);
+        assert_eq!(value.pointer("a~1b"), None);
+    }
+
+    #[test]
+    fn get_path_follows_keys_and_indices() {
+        let value = huml!({ db: { "eu.west": [{ host: "a" }] } });
+        assert_eq!(value.get_path(r#"db."eu.west"[0].host"#), Some(&huml!("a")));
+        assert_eq!(value.get_path(""), Some(&value));
+        assert_eq!(value.get_path("db[0]"), None);
+        assert_eq!(value.get_path("db.eu.west"), None);
+    }
+
+    #[test]
+    fn remove_path_extracts_subtrees() {
+        let mut value = huml!({ servers: [{ host: "a" }, { host: "b" }], key: "x" });
+        assert_eq!(value.remove_path("servers[0]"), Some(huml!({ host: "a" })));
+        assert_eq!(value.remove_path("servers[1]"), None);
+        assert_eq!(value.remove_path(""), None);
+        assert_eq!(value.remove_path("key.inner"), None);
+        assert_eq!(value["servers"][0]["host"].take(), "b");
+        assert_eq!(value, huml!({ servers: [{ host: null }], key: "x" }));
+    }
+
+    #[test]
+    fn walk_passes_paths_to_every_node() {
+        let value = huml!({ b: [1, { "x.y": true }], a: null });
+        let mut seen = Vec::new();
+        value.walk(|path, node| seen.push((path.to_string(), node.is_dict())));
+        let expected = [
+            ("", true),
+            ("b", false),
+            ("b[0]", false),
+            ("b[1]", true),
+            (r#"b[1]."x.y""#, false),
+            ("a", false),
+        ];
+        assert_eq!(seen, expected.map(|(p, d)| (p.to_string(), d)));
+
+        value.walk(|path, node| assert_eq!(value.get_path(&path.to_string()), Some(node)));
+    }
+
+    #[test]
+    fn transform_replaces_nodes_and_counts_them() {
+        let mut value = huml!({ db: { password: "x", hosts: ["a", "b"] }, password: "y" });
+        let changed = value.transform(|path, _| {
+            matches!(path.segments().last(), Some(PathSegment::Key(k)) if k == "password")
+                .then(|| huml!("***"))
+        });
+        assert_eq!(changed, 2);
+
+        // Replacements are not descended into.
+        let changed =
+            value.transform(|path, node| node.is_list().then(|| huml!([path.to_string()])));
+        assert_eq!(changed, 1);
+        assert_eq!(
+            value,
+            huml!({ db: { password: "***", hosts: ["db.hosts"] }, password: "***" })
+        );
+
+        // Entries are visited, and stay, in document order.
+        let mut seen = Vec::new();
+        value.transform(|path, _| {
+            seen.push(path.to_string());
+            None
+        });
+        assert_eq!(
+            seen,
+            [
+                "",
+                "db",
+                "db.password",
+                "db.hosts",
+                "db.hosts[0]",
+                "password"
+            ]
+        );
+        assert_eq!(
+            value.as_dict().unwrap().keys().collect::<Vec<_>>(),
+            ["db", "password"]
+        );
+    }
+
+    #[test]
+    fn pointer_mut_edits_in_place() {
+        let mut value = huml!({ servers: [{ host: "a" }] });
+        *value.pointer_mut("/servers/0/host").unwrap() = huml!("b");
+        assert!(value.pointer_mut("/servers/1").is_none());
+        if let Some(HumlValue::List(servers)) = value.pointer_mut("/servers") {
+            servers.push(huml!({ host: "c" }));
+        }
+        assert_eq!(value, huml!({ servers: [{ host: "b" }, { host: "c" }] }));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot access index 1 of a list of length 1")]
+    fn index_mut_panics_out_of_bounds() {
+        let mut value = huml!([1]);
+        value[1] = HumlValue::Null;
+    }
+}
@@ -0,0 +1,255 @@
+//! Declarative edits addressed by key path, in the spirit of JSON Patch.
+
+use crate::path::{parse_path, PathSegment};
+use crate::HumlValue;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A sequence of edits applied in order by [`HumlValue::apply_patch`].
+///
+/// Paths use the dotted syntax of [`HumlValue::get_path`]. Patches can also be
+/// read from a document, one dict per operation with an `op` key:
+///
+/// ```
+/// use huml_rs::huml;
+/// use huml_rs::value::Patch;
+///
+/// let patch: Patch = huml_rs::serde::from_str(r#"
+/// - ::
+///   op: "replace"
+///   path: "server.port"
+///   value: 8080
+/// - ::
+///   op: "move"
+///   from: "server.name"
+///   path: "name"
+/// "#).unwrap();
+///
+/// let mut config = huml!({ server: { port: 80, name: "web" } });
+/// config.apply_patch(&patch).unwrap();
+/// assert_eq!(config, huml!({ server: { port: 8080 }, name: "web" }));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Patch {
+    operations: Vec<PatchOperation>,
+}
+
+/// One edit in a [`Patch`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    /// Sets a dict entry, or inserts into a list at an index up to its length.
+    /// The parent must exist.
+    Add { path: String, value: HumlValue },
+    /// Removes an existing dict entry or list item.
+    Remove { path: String },
+    /// Replaces an existing value.
+    Replace { path: String, value: HumlValue },
+    /// Removes the value at `from` and adds it at `path`.
+    Move { from: String, path: String },
+}
+
+impl Patch {
+    /// Creates an empty patch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The operations of the patch, in the order they are applied.
+    pub fn operations(&self) -> &[PatchOperation] {
+        &self.operations
+    }
+
+    /// Appends `operation` to the end of the patch.
+    pub fn push(&mut self, operation: PatchOperation) {
+        self.operations.push(operation);
+    }
+
+    /// Appends an [`Add`](PatchOperation::Add) of `value` at `path`.
+    pub fn add(mut self, path: impl Into<String>, value: impl Into<HumlValue>) -> Self {
+        self.push(PatchOperation::Add {
+            path: path.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Appends a [`Remove`](PatchOperation::Remove) of the value at `path`.
+    pub fn remove(mut self, path: impl Into<String>) -> Self {
+        self.push(PatchOperation::Remove { path: path.into() });
+        self
+    }
+
+    /// Appends a [`Replace`](PatchOperation::Replace) of the value at `path` with `value`.
+    pub fn replace(mut self, path: impl Into<String>, value: impl Into<HumlValue>) -> Self {
+        self.push(PatchOperation::Replace {
+            path: path.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Appends a [`Move`](PatchOperation::Move) of the value at `from` to `path`.
+    pub fn move_path(mut self, from: impl Into<String>, path: impl Into<String>) -> Self {
+        self.push(PatchOperation::Move {
+            from: from.into(),
+            path: path.into(),
+        });
+        self
+    }
+}
+
+impl From<Vec<PatchOperation>> for Patch {
+    fn from(operations: Vec<PatchOperation>) -> Self {
+        Self { operations }
+    }
+}
+
+/// Error returned when a patch operation cannot be applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchError {
+    /// Position of the failing operation in the patch.
+    pub index: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "patch operation {}: {}", self.index, self.message)
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl HumlValue {
+    /// Applies every operation of `patch` in order.
+    ///
+    /// The patch is atomic: if any operation fails, the value is left as it
+    /// was and the error names the failing operation.
+    pub fn apply_patch(&mut self, patch: &Patch) -> Result<(), PatchError> {
+        let mut patched = self.clone();
+        for (index, operation) in patch.operations.iter().enumerate() {
+            apply(&mut patched, operation).map_err(|message| PatchError { index, message })?;
+        }
+        *self = patched;
+        Ok(())
+    }
+}
+
+fn apply(target: &mut HumlValue, operation: &PatchOperation) -> Result<(), String> {
+    match operation {
+        PatchOperation::Add { path, value } => add(target, path, value.clone()),
+        PatchOperation::Remove { path } => remove(target, path).map(drop),
+        PatchOperation::Replace { path, value } => {
+            let slot = target
+                .get_path_mut(path)
+                .ok_or_else(|| format!("no value at '{path}'"))?;
+            *slot = value.clone();
+            Ok(())
+        }
+        PatchOperation::Move { from, path } => {
+            let from_segments = segments(from)?;
+            let to_segments = segments(path)?;
+            if to_segments.len() > from_segments.len() && to_segments.starts_with(&from_segments) {
+                return Err(format!("cannot move '{from}' into itself"));
+            }
+            let value = remove(target, from)?;
+            add(target, path, value)
+        }
+    }
+}
+
+fn segments(path: &str) -> Result<Vec<PathSegment<'_>>, String> {
+    parse_path(path).ok_or_else(|| format!("invalid path '{path}'"))
+}
+
+/// Resolves the parent of `path`, returning it with the final segment.
+fn parent<'v, 'p>(
+    target: &'v mut HumlValue,
+    path: &'p str,
+) -> Result<(&'v mut HumlValue, PathSegment<'p>), String> {
+    let mut segments = segments(path)?;
+    let last = segments
+        .pop()
+        .ok_or_else(|| "the root has no parent".to_string())?;
+    let parent = segments
+        .iter()
+        .try_fold(target, |value, segment| segment.get_mut(value))
+        .ok_or_else(|| format!("parent of '{path}' does not exist"))?;
+    Ok((parent, last))
+}
+
+fn add(target: &mut HumlValue, path: &str, value: HumlValue) -> Result<(), String> {
+    if path.is_empty() {
+        *target = value;
+        return Ok(());
+    }
+    match parent(target, path)? {
+        (HumlValue::Dict(dict), PathSegment::Key(key)) => {
+            dict.insert(key.into_owned(), value);
+            Ok(())
+        }
+        (HumlValue::List(items), PathSegment::Index(index)) if index <= items.len() => {
+            items.insert(index, value);
+            Ok(())
+        }
+        (HumlValue::List(items), PathSegment::Index(index)) => Err(format!(
+            "index {index} is past the end of a list of length {}",
+            items.len()
+        )),
+        _ => Err(format!("parent of '{path}' cannot hold that key")),
+    }
+}
+
+fn remove(target: &mut HumlValue, path: &str) -> Result<HumlValue, String> {
+    let (parent, last) = parent(target, path)?;
+    last.remove(parent)
+        .ok_or_else(|| format!("no value at '{path}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patches_apply_in_order() {
+        let mut value = huml!({ list: [1, 3], dict: { a: 1 } });
+        let patch = Patch::new()
+            .add("list[1]", 2)
+            .add("list[3]", 4)
+            .add("dict.b", 2)
+            .remove("dict.a")
+            .replace("list[0]", 0)
+            .move_path("dict", "moved");
+        value.apply_patch(&patch).unwrap();
+        assert_eq!(value, huml!({ list: [0, 2, 3, 4], moved: { b: 2 } }));
+    }
+
+    #[test]
+    fn failed_patches_leave_the_value_unchanged() {
+        let original = huml!({ a: { b: [1] } });
+        let mut value = original.clone();
+        for (patch, message) in [
+            (
+                Patch::new().add("x", 1).remove("missing"),
+                "no value at 'missing'",
+            ),
+            (Patch::new().add("a.b[2]", 1), "index 2 is past the end"),
+            (
+                Patch::new().add("a.c.d", 1),
+                "parent of 'a.c.d' does not exist",
+            ),
+            (Patch::new().replace("a.b[1]", 1), "no value at 'a.b[1]'"),
+            (
+                Patch::new().move_path("a", "a.b.c"),
+                "cannot move 'a' into itself",
+            ),
+            (Patch::new().remove("a..b"), "invalid path 'a..b'"),
+        ] {
+            let err = value.apply_patch(&patch).unwrap_err();
+            assert!(err.message.contains(message), "{err}");
+            assert_eq!(value, original);
+        }
+    }
+}
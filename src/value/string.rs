@@ -0,0 +1,182 @@
+//! The [`HumlString`] payload of [`HumlValue::String`](crate::HumlValue::String).
+
+use std::borrow::{Borrow, Cow};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops;
+
+/// How a string was written in the source document.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum StringStyle {
+    /// A single-line `"..."` string.
+    #[default]
+    Quoted,
+    /// A `"""` multiline block.
+    MultilineQuoted,
+    /// A ```` ``` ```` multiline block, from a v0.1.0 document.
+    MultilineBacktick,
+}
+
+impl StringStyle {
+    /// Whether this is one of the multiline block styles.
+    pub fn is_multiline(self) -> bool {
+        !matches!(self, StringStyle::Quoted)
+    }
+}
+
+/// A HUML string, along with the [`StringStyle`] it was written in.
+///
+/// Parsed strings remember their style so that writing the value back out can
+/// keep multiline blocks as blocks; strings built in code are
+/// [`Quoted`](StringStyle::Quoted). The indentation of a block is implied by
+/// its key, so it is not stored. Comparison and hashing only look at the text.
+///
+/// Dereferences to `str`.
+#[derive(Clone, Default)]
+pub struct HumlString {
+    value: String,
+    style: StringStyle,
+}
+
+impl HumlString {
+    /// Creates a [`Quoted`](StringStyle::Quoted) string.
+    pub fn new(value: impl Into<String>) -> Self {
+        Self::with_style(value, StringStyle::Quoted)
+    }
+
+    /// Creates a string written in `style`.
+    pub fn with_style(value: impl Into<String>, style: StringStyle) -> Self {
+        Self {
+            value: value.into(),
+            style,
+        }
+    }
+
+    /// The text of the string.
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+
+    /// The style the string was written in.
+    pub fn style(&self) -> StringStyle {
+        self.style
+    }
+
+    /// Changes the style the string will be written in.
+    pub fn set_style(&mut self, style: StringStyle) {
+        self.style = style;
+    }
+
+    /// Unwraps the text, dropping the style.
+    pub fn into_string(self) -> String {
+        self.value
+    }
+}
+
+impl fmt::Debug for HumlString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.style {
+            StringStyle::Quoted => fmt::Debug::fmt(&self.value, f),
+            style => f
+                .debug_tuple("HumlString")
+                .field(&self.value)
+                .field(&style)
+                .finish(),
+        }
+    }
+}
+
+impl fmt::Display for HumlString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.value)
+    }
+}
+
+impl ops::Deref for HumlString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl AsRef<str> for HumlString {
+    fn as_ref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl Borrow<str> for HumlString {
+    fn borrow(&self) -> &str {
+        &self.value
+    }
+}
+
+impl PartialEq for HumlString {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Eq for HumlString {}
+
+impl PartialEq<str> for HumlString {
+    fn eq(&self, other: &str) -> bool {
+        self.value == other
+    }
+}
+
+impl PartialEq<&str> for HumlString {
+    fn eq(&self, other: &&str) -> bool {
+        self.value == *other
+    }
+}
+
+impl PartialEq<String> for HumlString {
+    fn eq(&self, other: &String) -> bool {
+        self.value == *other
+    }
+}
+
+impl PartialOrd for HumlString {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HumlString {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl Hash for HumlString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+impl From<String> for HumlString {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&str> for HumlString {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<Cow<'_, str>> for HumlString {
+    fn from(value: Cow<'_, str>) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<HumlString> for String {
+    fn from(value: HumlString) -> Self {
+        value.value
+    }
+}
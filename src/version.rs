@@ -0,0 +1,93 @@
+//! Registry of HUML specification versions understood by the parser.
+//!
+//! Documents declare their version with a `%HUML vX.Y.Z` line. The parser looks the
+//! declared version up here and adjusts the few rules that differ between releases;
+//! documents without a version line are parsed with [`SpecVersion::LATEST`].
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A released version of the HUML specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SpecVersion {
+    /// v0.1.0, which also allowed ```` ``` ```` multiline strings.
+    V0_1_0,
+    /// v0.2.0, the current specification.
+    V0_2_0,
+}
+
+impl SpecVersion {
+    /// Every version the parser accepts, oldest first.
+    pub const ALL: &'static [SpecVersion] = &[SpecVersion::V0_1_0, SpecVersion::V0_2_0];
+
+    /// The version assumed for documents without a `%HUML` line.
+    pub const LATEST: SpecVersion = SpecVersion::V0_2_0;
+
+    /// The version number without the `v` prefix, e.g. `"0.2.0"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SpecVersion::V0_1_0 => "0.1.0",
+            SpecVersion::V0_2_0 => "0.2.0",
+        }
+    }
+
+    /// Whether ```` ``` ```` delimited multiline strings are allowed.
+    pub(crate) fn allows_backtick_strings(self) -> bool {
+        self == SpecVersion::V0_1_0
+    }
+
+    /// Whether `"""` multiline strings strip the surrounding whitespace of every line,
+    /// rather than only the indentation implied by the key.
+    pub(crate) fn trims_quoted_multiline_lines(self) -> bool {
+        self == SpecVersion::V0_1_0
+    }
+}
+
+/// Returns the versions of the specification this parser supports, oldest first.
+pub fn supported_versions() -> &'static [SpecVersion] {
+    SpecVersion::ALL
+}
+
+impl fmt::Display for SpecVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}", self.as_str())
+    }
+}
+
+impl FromStr for SpecVersion {
+    type Err = String;
+
+    /// Parse a version number, with or without the leading `v`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = s.strip_prefix('v').unwrap_or(s);
+        SpecVersion::ALL
+            .iter()
+            .copied()
+            .find(|version| version.as_str() == number)
+            .ok_or_else(|| {
+                let supported: Vec<String> =
+                    SpecVersion::ALL.iter().map(|v| v.to_string()).collect();
+                format!(
+                    "unsupported version 'v{}'. expected one of {}",
+                    number,
+                    supported.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_known_versions() {
+        assert_eq!("v0.1.0".parse(), Ok(SpecVersion::V0_1_0));
+        assert_eq!("0.2.0".parse(), Ok(SpecVersion::V0_2_0));
+        assert_eq!(SpecVersion::LATEST.to_string(), "v0.2.0");
+        assert_eq!(supported_versions().last(), Some(&SpecVersion::LATEST));
+
+        let err = "v9.9.9".parse::<SpecVersion>().unwrap_err();
+        assert!(err.contains("expected one of v0.1.0, v0.2.0"));
+    }
+}